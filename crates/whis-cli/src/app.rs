@@ -1,27 +1,41 @@
 use anyhow::Result;
 use std::io::Write;
-use whis_core::{Settings, TranscriptionProvider};
+use std::path::Path;
+use whis_core::provider::local;
+use whis_core::{AudioFormat, FinalizeOptions, Settings, TranscriptionProvider, WhisError};
 
 /// Configuration for transcription, including provider, API key, and language
 pub struct TranscriptionConfig {
     pub provider: TranscriptionProvider,
     pub api_key: String,
     pub language: Option<String>,
+    /// How recordings are encoded before upload
+    pub finalize: FinalizeOptions,
+    /// Translate the speech to English instead of transcribing it
+    pub translate: bool,
+    /// Settings the rest of the configuration was loaded from
+    pub settings: Settings,
 }
 
-pub fn ensure_ffmpeg_installed() -> Result<()> {
+/// Exit with install instructions if `format` needs FFmpeg and it is missing
+pub fn ensure_ffmpeg_installed(format: AudioFormat) -> Result<()> {
+    if !format.requires_ffmpeg() {
+        return Ok(());
+    }
     if std::process::Command::new("ffmpeg")
         .arg("-version")
         .output()
         .is_err()
     {
         eprintln!("Error: FFmpeg is not installed or not in PATH.");
-        eprintln!("\nwhis requires FFmpeg for audio compression.");
+        eprintln!("\nwhis requires FFmpeg to encode {format} audio.");
         eprintln!("Please install FFmpeg:");
         eprintln!("  - Ubuntu/Debian: sudo apt install ffmpeg");
         eprintln!("  - macOS: brew install ffmpeg");
         eprintln!("  - Windows: choco install ffmpeg or download from ffmpeg.org");
         eprintln!("  - Or visit: https://ffmpeg.org/download.html\n");
+        eprintln!("Or switch to a built-in format:");
+        eprintln!("  whis config --audio-format flac\n");
         std::process::exit(1);
     }
     Ok(())
@@ -31,12 +45,13 @@ pub fn load_transcription_config() -> Result<TranscriptionConfig> {
     let settings = Settings::load();
     let provider = settings.provider.clone();
     let language = settings.language.clone();
+    let finalize = FinalizeOptions::from_settings(&settings);
 
     // Load API key based on provider
     let api_key = match &provider {
         TranscriptionProvider::OpenAI => {
             // Priority: settings file > environment variable
-            if let Some(key) = settings.openai_api_key {
+            if let Some(key) = settings.openai_api_key.clone() {
                 key
             } else {
                 // Fallback to environment
@@ -54,7 +69,7 @@ pub fn load_transcription_config() -> Result<TranscriptionConfig> {
         }
         TranscriptionProvider::Mistral => {
             // Priority: settings file > environment variable
-            if let Some(key) = settings.mistral_api_key {
+            if let Some(key) = settings.mistral_api_key.clone() {
                 key
             } else {
                 // Fallback to environment
@@ -70,12 +85,47 @@ pub fn load_transcription_config() -> Result<TranscriptionConfig> {
                 }
             }
         }
+        TranscriptionProvider::Custom => {
+            // The endpoint may not require a key; an empty key skips the auth header
+            match settings.get_api_key() {
+                Some(key) => key,
+                None => {
+                    eprintln!("Error: No custom endpoint configured.");
+                    eprintln!("\nSet the server URL with:");
+                    eprintln!("  whis config --custom-base-url http://localhost:8000/v1\n");
+                    std::process::exit(1);
+                }
+            }
+        }
+        TranscriptionProvider::Local => {
+            if !local::is_available() {
+                eprintln!("Error: This build of whis has no local transcription support.");
+                eprintln!("\nReinstall with:");
+                eprintln!("  cargo install whis --features local-whisper\n");
+                std::process::exit(1);
+            }
+            let Some(model_path) = &settings.local_model_path else {
+                eprintln!("Error: No local whisper model configured.");
+                eprintln!("\nSet the model file with:");
+                eprintln!("  whis config --local-model ~/models/ggml-base.bin\n");
+                std::process::exit(1);
+            };
+            if let Err(e) = local::validate_model_path(Path::new(model_path)) {
+                eprintln!("Error: {e}");
+                std::process::exit(1);
+            }
+            // Local transcription needs no key
+            String::new()
+        }
     };
 
     Ok(TranscriptionConfig {
         provider,
         api_key,
         language,
+        finalize,
+        translate: false,
+        settings,
     })
 }
 
@@ -85,3 +135,10 @@ pub fn wait_for_enter() -> Result<()> {
     std::io::stdin().read_line(&mut input)?;
     Ok(())
 }
+
+/// Print what the user can do about `error`, if whis-core knows
+pub fn print_hint(error: &anyhow::Error) {
+    if let Some(hint) = WhisError::find(error).and_then(WhisError::hint) {
+        eprintln!("{hint}");
+    }
+}
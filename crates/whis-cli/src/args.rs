@@ -1,4 +1,6 @@
-use clap::{Parser, Subcommand};
+use clap::{Args, Parser, Subcommand};
+use std::path::PathBuf;
+use whis_core::{CaptionOptions, TranscriptFormat};
 
 #[derive(Parser)]
 #[command(name = "whis")]
@@ -10,45 +12,233 @@ pub struct Cli {
     #[arg(short, long, global = true)]
     pub verbose: bool,
 
+    /// Translate the speech to English instead of transcribing it (OpenAI, custom or local provider)
+    #[arg(long, global = true)]
+    pub translate: bool,
+
     #[command(subcommand)]
     pub command: Option<Commands>,
 }
 
 #[derive(Subcommand)]
+#[allow(clippy::large_enum_variant)] // Parsed once at startup
 pub enum Commands {
     /// Start the background service that listens for hotkey triggers
     Listen {
         /// Hotkey to trigger recording (e.g., "ctrl+shift+r")
         #[arg(short = 'k', long, default_value = "ctrl+shift+r")]
         hotkey: String,
+
+        /// Hotkey to discard the recording or abort its transcription
+        #[arg(long, default_value = "ctrl+shift+x")]
+        cancel_hotkey: String,
+
+        /// Hotkey to record and translate the speech to English
+        #[arg(long, default_value = "ctrl+shift+t")]
+        translate_hotkey: String,
     },
 
     /// Stop the background service
     Stop,
 
+    /// Discard the service's current recording, or abort its transcription
+    Cancel,
+
     /// Check service status
     Status,
 
     /// Configure settings (API keys, provider, etc.)
-    Config {
-        /// Set your OpenAI API key
-        #[arg(long)]
-        openai_api_key: Option<String>,
-
-        /// Set your Mistral API key
-        #[arg(long)]
-        mistral_api_key: Option<String>,
-
-        /// Set the transcription provider (openai or mistral)
-        #[arg(long)]
-        provider: Option<String>,
-
-        /// Set the language hint (ISO-639-1 code: en, de, fr, etc.) or "auto" for auto-detect
-        #[arg(long)]
-        language: Option<String>,
-
-        /// Show current configuration
-        #[arg(long)]
-        show: bool,
-    },
+    Config(ConfigArgs),
+
+    /// List audio input devices and their supported configurations
+    Devices,
+
+    /// Transcribe an audio or video file (wav, mp3, m4a, ogg, flac, mp4), or every such file in a directory
+    Transcribe(TranscribeArgs),
+}
+
+#[derive(Args)]
+pub struct TranscribeArgs {
+    /// File to transcribe, "-" to read from stdin, or a directory to transcribe every file in it
+    #[arg(value_name = "FILE|DIR")]
+    pub input: PathBuf,
+
+    /// Write the transcript to this file instead of stdout (for a directory:
+    /// write transcripts under this directory instead of next to each file)
+    #[arg(short, long, value_name = "PATH")]
+    pub output: Option<PathBuf>,
+
+    /// Output format: text, srt, vtt (WebVTT) or json (with segment and word timestamps)
+    #[arg(short, long, value_name = "FORMAT", default_value = "text")]
+    pub format: TranscriptFormat,
+
+    /// Characters per subtitle line
+    #[arg(long, value_name = "CHARS", default_value_t = CaptionOptions::default().max_line_chars)]
+    pub max_line_length: usize,
+
+    /// Longest time a subtitle stays on screen, in seconds
+    #[arg(long, value_name = "SECS", default_value_t = CaptionOptions::default().max_duration_secs)]
+    pub max_caption_duration: f64,
+}
+
+impl TranscribeArgs {
+    pub fn caption_options(&self) -> CaptionOptions {
+        CaptionOptions {
+            max_line_chars: self.max_line_length,
+            max_duration_secs: self.max_caption_duration,
+            ..CaptionOptions::default()
+        }
+    }
+}
+
+#[derive(Args)]
+pub struct ConfigArgs {
+    /// Set your OpenAI API key
+    #[arg(long)]
+    pub openai_api_key: Option<String>,
+
+    /// Set your Mistral API key
+    #[arg(long)]
+    pub mistral_api_key: Option<String>,
+
+    /// Set the transcription provider (openai, mistral, custom or local)
+    #[arg(long)]
+    pub provider: Option<String>,
+
+    /// Set providers to fall back to, in order, when the provider fails (e.g. mistral,custom; "none" to clear)
+    #[arg(long, value_name = "LIST")]
+    pub fallback_providers: Option<String>,
+
+    /// Set the language hint (ISO-639-1 code: en, de, fr, etc.) or "auto" for auto-detect
+    #[arg(long)]
+    pub language: Option<String>,
+
+    /// Set names and terms the provider should spell correctly (e.g. "Whis, Voxtral, tokio"; "" to clear)
+    #[arg(long, value_name = "TEXT")]
+    pub prompt: Option<String>,
+
+    /// Prompt each chunk of a long recording with the previous chunk's text; chunks then run one at a time (true/false)
+    #[arg(long, value_name = "BOOL")]
+    pub prompt_previous_chunk: Option<bool>,
+
+    /// Set the base URL of an OpenAI-compatible server (e.g., http://localhost:8000/v1)
+    #[arg(long)]
+    pub custom_base_url: Option<String>,
+
+    /// Set the model name sent to the custom server
+    #[arg(long)]
+    pub custom_model: Option<String>,
+
+    /// Set the API key for the custom server
+    #[arg(long)]
+    pub custom_api_key: Option<String>,
+
+    /// Set the header carrying the custom API key (default: Authorization: Bearer)
+    #[arg(long)]
+    pub custom_auth_header: Option<String>,
+
+    /// Add an extra form field sent to the custom server (KEY=VALUE, repeatable; KEY= removes it)
+    #[arg(long = "custom-field", value_name = "KEY=VALUE")]
+    pub custom_fields: Vec<String>,
+
+    /// Set the GGML whisper.cpp model file for the local provider
+    #[arg(long, value_name = "PATH")]
+    pub local_model: Option<String>,
+
+    /// Set the CPU thread count for local transcription (0 = all cores)
+    #[arg(long, value_name = "N")]
+    pub local_threads: Option<usize>,
+
+    /// Set the upload audio format (flac, wav, opus, or mp3)
+    #[arg(long, value_name = "FORMAT")]
+    pub audio_format: Option<String>,
+
+    /// Set the upload sample rate in Hz (default 16000, 0 = device rate)
+    #[arg(long, value_name = "HZ")]
+    pub audio_sample_rate: Option<u32>,
+
+    /// Downmix recordings to mono before upload (true/false)
+    #[arg(long, value_name = "BOOL")]
+    pub audio_mono: Option<bool>,
+
+    /// Set the input device by index or name from `whis devices`, stored by name ("default" for system default)
+    #[arg(long, value_name = "INDEX|NAME")]
+    pub input_device: Option<String>,
+
+    /// What to do if the input device is missing: "default" (fall back) or "error"
+    #[arg(long, value_name = "POLICY")]
+    pub input_device_fallback: Option<String>,
+
+    /// Trim silence and skip transcription when no speech is detected (true/false)
+    #[arg(long, value_name = "BOOL")]
+    pub vad: Option<bool>,
+
+    /// Set the speech level threshold in dBFS (e.g., -40), or "auto" to adapt to background noise
+    #[arg(long, value_name = "DB|auto", allow_hyphen_values = true)]
+    pub vad_threshold: Option<String>,
+
+    /// Split recordings whose encoded size exceeds this many megabytes (default 20)
+    #[arg(long, value_name = "MB")]
+    pub chunk_threshold: Option<u32>,
+
+    /// Set the maximum duration of each chunk in seconds (default 300)
+    #[arg(long, value_name = "SECS")]
+    pub chunk_duration: Option<u32>,
+
+    /// Spill recordings to disk once they use this many megabytes of memory (0 = never, default 256)
+    #[arg(long, value_name = "MB")]
+    pub recording_memory_limit: Option<u32>,
+
+    /// Stop recordings after this many seconds (0 = no limit)
+    #[arg(long, value_name = "SECS")]
+    pub max_duration: Option<u32>,
+
+    /// Stop recordings after this many seconds without speech (0 = never)
+    #[arg(long, value_name = "SECS")]
+    pub silence_timeout: Option<u32>,
+
+    /// Keep the microphone open while `whis listen` is idle, so recordings start with a pre-roll (true/false)
+    #[arg(long, value_name = "BOOL")]
+    pub warm_microphone: Option<bool>,
+
+    /// Set how much audio from before the hotkey a warm microphone keeps, in milliseconds (default 1000)
+    #[arg(long, value_name = "MS")]
+    pub pre_roll: Option<u32>,
+
+    /// Retry failed requests (rate limits, server and network errors) this many times (0 = never, default 3)
+    #[arg(long, value_name = "N")]
+    pub max_retries: Option<u32>,
+
+    /// Wait this long before the first retry, in milliseconds; doubled for every further one (default 1000)
+    #[arg(long, value_name = "MS")]
+    pub retry_delay: Option<u32>,
+
+    /// Wait at most this long between retries, in seconds (default 30)
+    #[arg(long, value_name = "SECS")]
+    pub retry_max_delay: Option<u32>,
+
+    /// Show current configuration
+    #[arg(long)]
+    pub show: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn translate_applies_to_every_command() {
+        for args in [
+            ["whis", "--translate", "transcribe", "a.wav"],
+            ["whis", "transcribe", "a.wav", "--translate"],
+        ] {
+            let cli = Cli::try_parse_from(args).unwrap();
+            assert!(cli.translate);
+            assert!(matches!(cli.command, Some(Commands::Transcribe(_))));
+        }
+
+        let cli = Cli::try_parse_from(["whis", "--translate", "listen"]).unwrap();
+        assert!(cli.translate);
+        assert!(matches!(cli.command, Some(Commands::Listen { .. })));
+    }
 }
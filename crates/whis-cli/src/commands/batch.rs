@@ -0,0 +1,412 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::UNIX_EPOCH;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+use whis_core::export::{self, CaptionOptions};
+use whis_core::{MAX_CONCURRENT_REQUESTS, RecordingData, TranscriptFormat};
+
+use crate::app::TranscriptionConfig;
+use crate::args::TranscribeArgs;
+use crate::commands::transcribe;
+
+/// Extensions of the files picked up from a directory
+const EXTENSIONS: &[&str] = &["wav", "mp3", "m4a", "ogg", "opus", "flac", "mp4", "webm"];
+/// Manifest of finished files, kept in the output directory
+const MANIFEST_NAME: &str = ".whis-batch.json";
+
+/// Files a batch has finished, so an interrupted run resumes where it stopped
+#[derive(Default, Serialize, Deserialize)]
+struct Manifest {
+    /// Keyed by path relative to the input directory
+    files: BTreeMap<String, ManifestEntry>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ManifestEntry {
+    /// Size and modification time of the version that was transcribed
+    size: u64,
+    modified: u64,
+    /// Transcript path relative to the output directory (None = no speech)
+    transcript: Option<String>,
+}
+
+impl Manifest {
+    fn load(path: &Path) -> Self {
+        let Ok(contents) = fs::read_to_string(path) else {
+            return Self::default();
+        };
+        serde_json::from_str(&contents).unwrap_or_else(|e| {
+            eprintln!("Warning: ignoring unreadable {}: {e}", path.display());
+            Self::default()
+        })
+    }
+
+    /// Save atomically, so an interrupted run never leaves a truncated manifest
+    fn save(&self, path: &Path) -> Result<()> {
+        let temp = path.with_extension("json.tmp");
+        fs::write(&temp, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("Failed to write {}", temp.display()))?;
+        fs::rename(&temp, path).with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    /// Record that `file` was transcribed in its current version
+    fn record(&mut self, file: &BatchFile, has_speech: bool) {
+        self.files.insert(
+            file.key.clone(),
+            ManifestEntry {
+                size: file.size,
+                modified: file.modified,
+                transcript: has_speech.then(|| file.transcript.to_string_lossy().into_owned()),
+            },
+        );
+    }
+
+    /// Whether `file` was transcribed in its current version (and format)
+    fn is_done(&self, file: &BatchFile, output: &Path) -> bool {
+        self.files.get(&file.key).is_some_and(|entry| {
+            entry.size == file.size
+                && entry.modified == file.modified
+                && entry.transcript.as_ref().is_none_or(|transcript| {
+                    Path::new(transcript) == file.transcript && output.join(transcript).exists()
+                })
+        })
+    }
+}
+
+/// A file of the batch and where its transcript goes
+struct BatchFile {
+    source: PathBuf,
+    key: String,
+    /// Relative to the output directory
+    transcript: PathBuf,
+    size: u64,
+    modified: u64,
+}
+
+/// Transcribe every audio and video file under `args.input`, writing a
+/// transcript in `args.format` next to each (or in the same layout under
+/// `args.output`)
+///
+/// Finished files are recorded in a manifest and skipped on the next run
+/// unless they changed.
+pub async fn run(args: &TranscribeArgs, config: TranscriptionConfig) -> Result<()> {
+    let dir = args.input.as_path();
+    let output = args.output.as_deref().unwrap_or(dir);
+    let format = args.format;
+    let captions = args.caption_options();
+    fs::create_dir_all(output).with_context(|| format!("Failed to create {}", output.display()))?;
+    let manifest_path = output.join(MANIFEST_NAME);
+    let mut manifest = Manifest::load(&manifest_path);
+
+    let files = collect_files(dir, format)?;
+    anyhow::ensure!(
+        !files.is_empty(),
+        "No audio or video files found in {}",
+        dir.display()
+    );
+    let found = files.len();
+    let pending: Vec<BatchFile> = files
+        .into_iter()
+        .filter(|file| !manifest.is_done(file, output))
+        .collect();
+    if pending.len() < found {
+        eprintln!(
+            "Skipping {} file(s) already transcribed (delete {} to start over)",
+            found - pending.len(),
+            manifest_path.display()
+        );
+    }
+    if pending.is_empty() {
+        return Ok(());
+    }
+
+    let memory_limit = config.settings.recording_memory_limit();
+    let config = Arc::new(config);
+    // Each file in flight holds its decoded audio; their requests also share
+    // the process-wide MAX_CONCURRENT_REQUESTS limit
+    let files_in_flight = Arc::new(Semaphore::new(MAX_CONCURRENT_REQUESTS));
+    let mut tasks = JoinSet::new();
+    for file in pending {
+        let config = config.clone();
+        let files_in_flight = files_in_flight.clone();
+        let output = output.to_path_buf();
+        tasks.spawn(async move {
+            let _permit = files_in_flight.acquire_owned().await;
+            let result =
+                transcribe_file(&config, &file, &output, format, &captions, memory_limit).await;
+            (file, result)
+        });
+    }
+
+    let total = tasks.len();
+    let (mut finished, mut failed, mut no_speech) = (0, 0, 0);
+    while let Some(joined) = tasks.join_next().await {
+        let (file, result) = joined.context("Failed to join task")?;
+        finished += 1;
+        match result {
+            Ok(has_speech) => {
+                if !has_speech {
+                    no_speech += 1;
+                }
+                let status = if has_speech {
+                    "done"
+                } else {
+                    "no speech detected"
+                };
+                eprintln!("[{finished}/{total}] {}: {status}", file.key);
+                manifest.record(&file, has_speech);
+                // Returning here would abort the files still in flight; at
+                // worst the next run transcribes this one again
+                if let Err(e) = manifest.save(&manifest_path) {
+                    eprintln!("Warning: {e:#}");
+                }
+            }
+            Err(e) => {
+                failed += 1;
+                eprintln!("[{finished}/{total}] {}: error: {e:#}", file.key);
+            }
+        }
+    }
+
+    eprintln!(
+        "Transcribed {} of {total} file(s) ({no_speech} without speech)",
+        total - failed
+    );
+    if failed > 0 {
+        anyhow::bail!("{failed} file(s) failed; run the same command again to retry them");
+    }
+    Ok(())
+}
+
+/// Decode and transcribe one file; returns false when it contains no speech
+async fn transcribe_file(
+    config: &TranscriptionConfig,
+    file: &BatchFile,
+    output: &Path,
+    format: TranscriptFormat,
+    captions: &CaptionOptions,
+    memory_limit: Option<usize>,
+) -> Result<bool> {
+    let source = file.source.clone();
+    let recording =
+        tokio::task::spawn_blocking(move || RecordingData::from_file(&source, memory_limit))
+            .await
+            .context("Failed to join task")??;
+    let Some(transcript) = transcribe::transcribe(config, recording, format, None).await? else {
+        return Ok(false);
+    };
+    let rendered = export::render(&transcript, format, captions)?;
+
+    let path = output.join(&file.transcript);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    fs::write(&path, rendered).with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(true)
+}
+
+/// Find the audio and video files under `dir` (skipping hidden ones), sorted by path
+fn collect_files(dir: &Path, format: TranscriptFormat) -> Result<Vec<BatchFile>> {
+    let mut sources = Vec::new();
+    let mut dirs = vec![dir.to_path_buf()];
+    while let Some(current) = dirs.pop() {
+        let entries = fs::read_dir(&current)
+            .with_context(|| format!("Failed to read {}", current.display()))?;
+        for entry in entries {
+            let path = entry?.path();
+            let hidden = path
+                .file_name()
+                .is_some_and(|name| name.to_string_lossy().starts_with('.'));
+            if hidden {
+                continue;
+            }
+            if path.is_dir() {
+                dirs.push(path);
+            } else if path.extension().is_some_and(|extension| {
+                EXTENSIONS.contains(&extension.to_string_lossy().to_lowercase().as_str())
+            }) {
+                sources.push(path);
+            }
+        }
+    }
+    sources.sort();
+
+    // interview.mp3 -> interview.txt, unless interview.wav is there too
+    let extension = format.extension();
+    let stem_counts = sources.iter().fold(HashMap::new(), |mut counts, path| {
+        *counts.entry(path.with_extension("")).or_insert(0) += 1;
+        counts
+    });
+
+    sources
+        .into_iter()
+        .map(|source| {
+            let relative = source.strip_prefix(dir).unwrap_or(&source).to_path_buf();
+            let transcript = if stem_counts[&source.with_extension("")] > 1 {
+                let mut name = relative.clone().into_os_string();
+                name.push(".");
+                name.push(extension);
+                PathBuf::from(name)
+            } else {
+                relative.with_extension(extension)
+            };
+            let metadata = fs::metadata(&source)
+                .with_context(|| format!("Failed to read {}", source.display()))?;
+            let modified = metadata
+                .modified()
+                .ok()
+                .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                .map_or(0, |age| age.as_secs());
+
+            Ok(BatchFile {
+                key: relative.to_string_lossy().into_owned(),
+                source,
+                transcript,
+                size: metadata.len(),
+                modified,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::File;
+    use std::time::{Duration, SystemTime};
+
+    /// Scratch directory removed when dropped
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let dir = std::env::temp_dir().join(format!("whis-{name}-{}", std::process::id()));
+            let _ = fs::remove_dir_all(&dir);
+            fs::create_dir_all(&dir).unwrap();
+            Self(dir)
+        }
+
+        fn touch(&self, relative: &str) {
+            let path = self.0.join(relative);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, b"audio").unwrap();
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn transcripts(files: &[BatchFile]) -> Vec<(&str, &Path)> {
+        files
+            .iter()
+            .map(|file| (file.key.as_str(), file.transcript.as_path()))
+            .collect()
+    }
+
+    #[test]
+    fn collects_audio_files_and_keeps_transcript_names_apart() {
+        let dir = TempDir::new("batch-collect");
+        for name in [
+            "a.mp3",
+            "a.wav",
+            "b.OGG",
+            ".hidden.mp3",
+            "notes.txt",
+            "sub/c.flac",
+        ] {
+            dir.touch(name);
+        }
+
+        let files = collect_files(&dir.0, TranscriptFormat::Text).unwrap();
+        assert_eq!(
+            transcripts(&files),
+            [
+                ("a.mp3", Path::new("a.mp3.txt")),
+                ("a.wav", Path::new("a.wav.txt")),
+                ("b.OGG", Path::new("b.txt")),
+                ("sub/c.flac", Path::new("sub/c.txt")),
+            ]
+        );
+    }
+
+    #[test]
+    fn files_are_done_only_in_the_version_and_format_transcribed() {
+        let dir = TempDir::new("batch-done");
+        dir.touch("a.mp3");
+        dir.touch("a.txt");
+        let file = || {
+            collect_files(&dir.0, TranscriptFormat::Text)
+                .unwrap()
+                .remove(0)
+        };
+
+        let mut manifest = Manifest::default();
+        assert!(!manifest.is_done(&file(), &dir.0));
+        manifest.record(&file(), true);
+        assert!(manifest.is_done(&file(), &dir.0));
+
+        // Another format writes another transcript
+        let srt = collect_files(&dir.0, TranscriptFormat::Srt)
+            .unwrap()
+            .remove(0);
+        assert!(!manifest.is_done(&srt, &dir.0));
+
+        // A deleted transcript is written again
+        fs::remove_file(dir.0.join("a.txt")).unwrap();
+        assert!(!manifest.is_done(&file(), &dir.0));
+        dir.touch("a.txt");
+
+        let modified = SystemTime::now() + Duration::from_secs(3600);
+        let source = File::options()
+            .write(true)
+            .open(dir.0.join("a.mp3"))
+            .unwrap();
+        source.set_modified(modified).unwrap();
+        assert!(!manifest.is_done(&file(), &dir.0));
+        manifest.record(&file(), true);
+        assert!(manifest.is_done(&file(), &dir.0));
+
+        fs::write(dir.0.join("a.mp3"), b"longer audio").unwrap();
+        File::options()
+            .write(true)
+            .open(dir.0.join("a.mp3"))
+            .unwrap()
+            .set_modified(modified)
+            .unwrap();
+        assert!(!manifest.is_done(&file(), &dir.0));
+    }
+
+    #[test]
+    fn resumes_with_the_files_not_yet_transcribed() {
+        let dir = TempDir::new("batch-resume");
+        for name in ["a.mp3", "b.mp3", "c.mp3"] {
+            dir.touch(name);
+        }
+        dir.touch("a.txt");
+        let files = collect_files(&dir.0, TranscriptFormat::Text).unwrap();
+
+        // A run that finished a.mp3 (speech) and b.mp3 (no speech) and then stopped
+        let path = dir.0.join(MANIFEST_NAME);
+        let mut manifest = Manifest::default();
+        manifest.record(&files[0], true);
+        manifest.record(&files[1], false);
+        manifest.save(&path).unwrap();
+
+        let manifest = Manifest::load(&path);
+        let pending: Vec<_> = files
+            .iter()
+            .filter(|file| !manifest.is_done(file, &dir.0))
+            .map(|file| file.key.as_str())
+            .collect();
+        assert_eq!(pending, ["c.mp3"]);
+    }
+}
@@ -0,0 +1,20 @@
+use crate::ipc;
+use anyhow::Result;
+
+pub fn run() -> Result<()> {
+    if !ipc::is_service_running() {
+        println!("Nothing to cancel: the service is not running");
+        return Ok(());
+    }
+
+    let mut client = ipc::IpcClient::connect()?;
+    match client.send_message(ipc::IpcMessage::Cancel)? {
+        ipc::IpcResponse::Success => println!("Cancelled"),
+        ipc::IpcResponse::Error(e) => {
+            eprintln!("Error: {e}");
+            std::process::exit(1);
+        }
+        _ => println!("Nothing to cancel"),
+    }
+    Ok(())
+}
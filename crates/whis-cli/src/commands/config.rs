@@ -1,13 +1,47 @@
 use anyhow::Result;
-use whis_core::{Settings, TranscriptionProvider};
-
-pub fn run(
-    openai_api_key: Option<String>,
-    mistral_api_key: Option<String>,
-    provider: Option<String>,
-    language: Option<String>,
-    show: bool,
-) -> Result<()> {
+use whis_core::provider::local;
+use whis_core::{
+    AudioFormat, DeviceFallback, Settings, TranscriptionProvider, resolve_input_device,
+};
+
+use crate::args::ConfigArgs;
+
+pub fn run(args: ConfigArgs) -> Result<()> {
+    let ConfigArgs {
+        openai_api_key,
+        mistral_api_key,
+        provider,
+        fallback_providers,
+        language,
+        prompt,
+        prompt_previous_chunk,
+        custom_base_url,
+        custom_model,
+        custom_api_key,
+        custom_auth_header,
+        custom_fields,
+        local_model,
+        local_threads,
+        audio_format,
+        audio_sample_rate,
+        audio_mono,
+        input_device,
+        input_device_fallback,
+        vad,
+        vad_threshold,
+        chunk_threshold,
+        chunk_duration,
+        recording_memory_limit,
+        max_duration,
+        silence_timeout,
+        warm_microphone,
+        pre_roll,
+        max_retries,
+        retry_delay,
+        retry_max_delay,
+        show,
+    } = args;
+
     let mut settings = Settings::load();
     let mut changed = false;
 
@@ -26,6 +60,34 @@ pub fn run(
         }
     }
 
+    if let Some(list) = fallback_providers {
+        let list = list.trim();
+        let parsed: Result<Vec<TranscriptionProvider>, String> =
+            if list.is_empty() || list.eq_ignore_ascii_case("none") {
+                Ok(Vec::new())
+            } else {
+                list.split(',').map(|name| name.trim().parse()).collect()
+            };
+        match parsed {
+            Ok(providers) => {
+                if providers.is_empty() {
+                    println!("Fallback providers cleared");
+                } else {
+                    println!(
+                        "Fallback providers set to: {}",
+                        format_providers(&providers)
+                    );
+                }
+                settings.fallback_providers = providers;
+                changed = true;
+            }
+            Err(e) => {
+                eprintln!("{e}");
+                std::process::exit(1);
+            }
+        }
+    }
+
     // Handle language change
     if let Some(lang) = language {
         if lang.to_lowercase() == "auto" {
@@ -45,6 +107,27 @@ pub fn run(
         }
     }
 
+    if let Some(prompt) = prompt {
+        let prompt = prompt.trim();
+        if prompt.is_empty() {
+            settings.prompt = None;
+            println!("Prompt cleared");
+        } else {
+            settings.prompt = Some(prompt.to_string());
+            println!("Prompt set to: {}", prompt);
+        }
+        changed = true;
+    }
+
+    if let Some(enabled) = prompt_previous_chunk {
+        settings.prompt_previous_chunk = enabled;
+        println!(
+            "Prompt with previous chunk: {}",
+            if enabled { "on" } else { "off" }
+        );
+        changed = true;
+    }
+
     // Handle OpenAI API key
     if let Some(key) = openai_api_key {
         // Validate format for OpenAI
@@ -74,6 +157,285 @@ pub fn run(
         println!("Mistral API key saved");
     }
 
+    // Handle custom OpenAI-compatible endpoint
+    if let Some(url) = custom_base_url {
+        let url = url.trim().trim_end_matches('/').to_string();
+        if !url.starts_with("http://") && !url.starts_with("https://") {
+            eprintln!("Invalid base URL. It must start with http:// or https://");
+            std::process::exit(1);
+        }
+        println!("Custom base URL set to: {}", url);
+        settings.custom_base_url = Some(url);
+        changed = true;
+    }
+
+    if let Some(model) = custom_model {
+        let model = model.trim().to_string();
+        if model.is_empty() {
+            settings.custom_model = None;
+            println!("Custom model reset to default");
+        } else {
+            println!("Custom model set to: {}", model);
+            settings.custom_model = Some(model);
+        }
+        changed = true;
+    }
+
+    if let Some(key) = custom_api_key {
+        let key = key.trim().to_string();
+        settings.custom_api_key = if key.is_empty() { None } else { Some(key) };
+        changed = true;
+        println!("Custom API key saved");
+    }
+
+    if let Some(header) = custom_auth_header {
+        let header = header.trim().to_string();
+        if header.is_empty() {
+            settings.custom_auth_header = None;
+            println!("Custom auth header reset to: Authorization: Bearer");
+        } else {
+            println!("Custom auth header set to: {}", header);
+            settings.custom_auth_header = Some(header);
+        }
+        changed = true;
+    }
+
+    for field in custom_fields {
+        let Some((name, value)) = field.split_once('=') else {
+            eprintln!("Invalid custom field '{field}'. Use KEY=VALUE");
+            std::process::exit(1);
+        };
+        let name = name.trim();
+        if name.is_empty() {
+            eprintln!("Invalid custom field '{field}'. The key cannot be empty");
+            std::process::exit(1);
+        }
+        if value.is_empty() {
+            settings.custom_form_fields.remove(name);
+            println!("Custom field removed: {}", name);
+        } else {
+            settings
+                .custom_form_fields
+                .insert(name.to_string(), value.to_string());
+            println!("Custom field set: {}={}", name, value);
+        }
+        changed = true;
+    }
+
+    // Handle local whisper.cpp model
+    if let Some(path) = local_model {
+        let path = std::path::absolute(path.trim())?;
+        if let Err(e) = local::validate_model_path(&path) {
+            eprintln!("{e}");
+            std::process::exit(1);
+        }
+        if !local::is_available() {
+            eprintln!("Warning: this build has no local transcription support (feature `local-whisper`)");
+        }
+        println!("Local model set to: {}", path.display());
+        settings.local_model_path = Some(path.to_string_lossy().into_owned());
+        changed = true;
+    }
+
+    if let Some(threads) = local_threads {
+        if threads == 0 {
+            settings.local_threads = None;
+            println!("Local threads set to: all cores");
+        } else {
+            settings.local_threads = Some(threads);
+            println!("Local threads set to: {}", threads);
+        }
+        changed = true;
+    }
+
+    if let Some(format_str) = audio_format {
+        match format_str.parse::<AudioFormat>() {
+            Ok(format) => {
+                settings.audio_format = format;
+                changed = true;
+                println!("Audio format set to: {}", format);
+                if format.requires_ffmpeg() {
+                    println!("Note: encoding {} requires FFmpeg to be installed", format);
+                }
+            }
+            Err(e) => {
+                eprintln!("{e}");
+                std::process::exit(1);
+            }
+        }
+    }
+
+    if let Some(rate) = audio_sample_rate {
+        if rate != 0 && !(8_000..=192_000).contains(&rate) {
+            eprintln!("Invalid sample rate: {rate}. Use 8000-192000 Hz, or 0 for the device rate");
+            std::process::exit(1);
+        }
+        settings.audio_sample_rate = rate;
+        if rate == 0 {
+            println!("Audio sample rate set to: device rate");
+        } else {
+            println!("Audio sample rate set to: {} Hz", rate);
+        }
+        changed = true;
+    }
+
+    if let Some(mono) = audio_mono {
+        settings.audio_mono = mono;
+        println!("Audio downmix to mono: {}", if mono { "on" } else { "off" });
+        changed = true;
+    }
+
+    if let Some(device) = input_device {
+        let device = device.trim();
+        if device.is_empty() || device.eq_ignore_ascii_case("default") {
+            settings.input_device = None;
+            println!("Input device set to: system default");
+        } else {
+            // Store the name: indices change when devices are plugged in or out
+            let name = match resolve_input_device(device) {
+                Ok(Some(name)) => name,
+                Ok(None) => {
+                    eprintln!("Warning: no connected input device matches '{device}'");
+                    device.to_string()
+                }
+                Err(e) => {
+                    eprintln!("{e}");
+                    std::process::exit(1);
+                }
+            };
+            println!("Input device set to: {}", name);
+            settings.input_device = Some(name);
+        }
+        changed = true;
+    }
+
+    if let Some(policy) = input_device_fallback {
+        match policy.parse::<DeviceFallback>() {
+            Ok(fallback) => {
+                settings.input_device_fallback = fallback;
+                changed = true;
+                println!("Input device fallback set to: {}", fallback);
+            }
+            Err(e) => {
+                eprintln!("{e}");
+                std::process::exit(1);
+            }
+        }
+    }
+
+    if let Some(enabled) = vad {
+        settings.vad_enabled = enabled;
+        println!("Silence trimming: {}", if enabled { "on" } else { "off" });
+        changed = true;
+    }
+
+    if let Some(threshold) = vad_threshold {
+        if threshold.eq_ignore_ascii_case("auto") {
+            settings.vad_threshold_db = None;
+            println!("Speech threshold set to: auto");
+        } else {
+            match threshold.parse::<f32>() {
+                Ok(db) if (-90.0..=0.0).contains(&db) => {
+                    settings.vad_threshold_db = Some(db);
+                    println!("Speech threshold set to: {} dBFS", db);
+                }
+                _ => {
+                    eprintln!("Invalid speech threshold: {threshold}. Use -90 to 0 dBFS, or 'auto'");
+                    std::process::exit(1);
+                }
+            }
+        }
+        changed = true;
+    }
+
+    if let Some(mb) = chunk_threshold {
+        if mb == 0 {
+            eprintln!("Invalid chunk threshold: must be at least 1 MB");
+            std::process::exit(1);
+        }
+        settings.chunk_threshold_mb = mb;
+        println!("Chunk threshold set to: {} MB", mb);
+        changed = true;
+    }
+
+    if let Some(secs) = chunk_duration {
+        if secs < 10 {
+            eprintln!("Invalid chunk duration: must be at least 10 seconds");
+            std::process::exit(1);
+        }
+        settings.chunk_duration_secs = secs;
+        println!("Chunk duration set to: {}s", secs);
+        changed = true;
+    }
+
+    if let Some(mb) = recording_memory_limit {
+        settings.recording_memory_limit_mb = mb;
+        if mb == 0 {
+            println!("Recording memory limit disabled");
+        } else {
+            println!("Recording memory limit set to: {} MB", mb);
+        }
+        changed = true;
+    }
+
+    if let Some(secs) = max_duration {
+        settings.max_recording_secs = secs;
+        if secs == 0 {
+            println!("Maximum recording duration disabled");
+        } else {
+            println!("Maximum recording duration set to: {}s", secs);
+        }
+        changed = true;
+    }
+
+    if let Some(secs) = silence_timeout {
+        settings.silence_timeout_secs = secs;
+        if secs == 0 {
+            println!("Auto-stop on silence disabled");
+        } else {
+            println!("Auto-stop on silence set to: {}s", secs);
+        }
+        changed = true;
+    }
+
+    if let Some(enabled) = warm_microphone {
+        settings.warm_microphone = enabled;
+        if enabled {
+            println!("Warm microphone: on (the microphone stays open while `whis listen` runs)");
+        } else {
+            println!("Warm microphone: off");
+        }
+        changed = true;
+    }
+
+    if let Some(ms) = pre_roll {
+        settings.pre_roll_ms = ms;
+        println!("Pre-roll set to: {}ms", ms);
+        changed = true;
+    }
+
+    if let Some(retries) = max_retries {
+        settings.max_retries = retries;
+        if retries == 0 {
+            println!("Retries disabled");
+        } else {
+            println!("Max retries set to: {}", retries);
+        }
+        changed = true;
+    }
+
+    if let Some(ms) = retry_delay {
+        settings.retry_initial_delay_ms = ms;
+        println!("Initial retry delay set to: {}ms", ms);
+        changed = true;
+    }
+
+    if let Some(secs) = retry_max_delay {
+        settings.retry_max_delay_secs = secs;
+        println!("Maximum retry delay set to: {}s", secs);
+        changed = true;
+    }
+
     // Save if anything changed
     if changed {
         settings.save()?;
@@ -84,10 +446,30 @@ pub fn run(
     if show {
         println!("Config file: {}", Settings::path().display());
         println!("Provider: {}", settings.provider);
+        if settings.fallback_providers.is_empty() {
+            println!("Fallback providers: none");
+        } else {
+            println!(
+                "Fallback providers: {}",
+                format_providers(&settings.fallback_providers)
+            );
+        }
         println!(
             "Language: {}",
             settings.language.as_deref().unwrap_or("auto-detect")
         );
+        println!(
+            "Prompt: {}",
+            settings.prompt.as_deref().unwrap_or("(not set)")
+        );
+        println!(
+            "Prompt with previous chunk: {}",
+            if settings.prompt_previous_chunk {
+                "on"
+            } else {
+                "off"
+            }
+        );
         println!("Shortcut: {}", settings.shortcut);
 
         // OpenAI API key
@@ -104,19 +486,128 @@ pub fn run(
             println!("Mistral API key: (not set, using $MISTRAL_API_KEY)");
         }
 
+        // Custom endpoint
+        if let Some(url) = &settings.custom_base_url {
+            println!("Custom base URL: {}", url);
+            println!(
+                "Custom model: {}",
+                settings.custom_model.as_deref().unwrap_or("whisper-1")
+            );
+            if let Some(key) = &settings.custom_api_key {
+                println!("Custom API key: {}", mask_key(key));
+            } else {
+                println!("Custom API key: (not set, using $WHIS_CUSTOM_API_KEY if present)");
+            }
+            println!(
+                "Custom auth header: {}",
+                settings
+                    .custom_auth_header
+                    .as_deref()
+                    .unwrap_or("Authorization: Bearer")
+            );
+            for (name, value) in &settings.custom_form_fields {
+                println!("Custom field: {}={}", name, value);
+            }
+        } else {
+            println!("Custom base URL: (not set)");
+        }
+
+        // Local whisper.cpp model
+        println!(
+            "Local model: {}",
+            settings.local_model_path.as_deref().unwrap_or("(not set)")
+        );
+        match settings.local_threads {
+            Some(threads) => println!("Local threads: {}", threads),
+            None => println!("Local threads: all cores"),
+        }
+        println!("Audio format: {}", settings.audio_format);
+        match settings.audio_sample_rate {
+            0 => println!("Audio sample rate: device rate"),
+            rate => println!("Audio sample rate: {} Hz", rate),
+        }
+        println!("Audio mono: {}", if settings.audio_mono { "on" } else { "off" });
+        println!(
+            "Input device: {} (fallback: {})",
+            settings.input_device.as_deref().unwrap_or("system default"),
+            settings.input_device_fallback
+        );
+        println!(
+            "Silence trimming: {} (threshold: {})",
+            if settings.vad_enabled { "on" } else { "off" },
+            settings
+                .vad_threshold_db
+                .map(|db| format!("{db} dBFS"))
+                .unwrap_or_else(|| "auto".to_string())
+        );
+        println!(
+            "Chunking: above {} MB, up to {}s per chunk",
+            settings.chunk_threshold_mb, settings.chunk_duration_secs
+        );
+        if settings.recording_memory_limit_mb == 0 {
+            println!("Recording memory limit: none");
+        } else {
+            println!(
+                "Recording memory limit: {} MB (longer recordings spill to disk)",
+                settings.recording_memory_limit_mb
+            );
+        }
+        match settings.max_recording_secs {
+            0 => println!("Maximum recording duration: no limit"),
+            secs => println!("Maximum recording duration: {}s", secs),
+        }
+        match settings.silence_timeout_secs {
+            0 => println!("Auto-stop on silence: off"),
+            secs => println!("Auto-stop on silence: after {}s without speech", secs),
+        }
+        match settings.pre_roll() {
+            Some(_) => println!("Warm microphone: on ({}ms pre-roll)", settings.pre_roll_ms),
+            None => println!("Warm microphone: off"),
+        }
+        match settings.max_retries {
+            0 => println!("Retries: off"),
+            retries => println!(
+                "Retries: up to {} (from {}ms, at most {}s apart)",
+                retries, settings.retry_initial_delay_ms, settings.retry_max_delay_secs
+            ),
+        }
+
         return Ok(());
     }
 
     // No flags - show help
     eprintln!("Usage:");
-    eprintln!("  whis config --provider <openai|mistral>");
+    eprintln!("  whis config --provider <openai|mistral|custom|local>");
+    eprintln!("  whis config --fallback-providers <PROVIDER,...|none>");
     eprintln!("  whis config --language <en|de|fr|...|auto>");
+    eprintln!("  whis config --prompt <TEXT> [--prompt-previous-chunk <true|false>]");
     eprintln!("  whis config --openai-api-key <KEY>");
     eprintln!("  whis config --mistral-api-key <KEY>");
+    eprintln!("  whis config --custom-base-url <URL> [--custom-model <MODEL>]");
+    eprintln!("  whis config --custom-api-key <KEY> [--custom-auth-header <HEADER>]");
+    eprintln!("  whis config --custom-field <KEY=VALUE>");
+    eprintln!("  whis config --local-model <PATH> [--local-threads <N>]");
+    eprintln!("  whis config --audio-format <flac|wav|opus|mp3>");
+    eprintln!("  whis config --audio-sample-rate <HZ> [--audio-mono <true|false>]");
+    eprintln!("  whis config --input-device <INDEX|NAME|default> [--input-device-fallback <default|error>]");
+    eprintln!("  whis config --vad <true|false> [--vad-threshold <DB|auto>]");
+    eprintln!("  whis config --chunk-threshold <MB> [--chunk-duration <SECS>]");
+    eprintln!("  whis config --recording-memory-limit <MB>");
+    eprintln!("  whis config --max-duration <SECS> [--silence-timeout <SECS>]");
+    eprintln!("  whis config --warm-microphone <true|false> [--pre-roll <MS>]");
+    eprintln!("  whis config --max-retries <N> [--retry-delay <MS>] [--retry-max-delay <SECS>]");
     eprintln!("  whis config --show");
     std::process::exit(1);
 }
 
+fn format_providers(providers: &[TranscriptionProvider]) -> String {
+    providers
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
 fn mask_key(key: &str) -> String {
     if key.len() > 10 {
         format!("{}...{}", &key[..6], &key[key.len() - 4..])
@@ -0,0 +1,47 @@
+use anyhow::Result;
+use whis_core::{Settings, list_input_devices};
+
+pub fn run() -> Result<()> {
+    let devices = list_input_devices()?;
+    let settings = Settings::load();
+
+    if devices.is_empty() {
+        println!("No input devices found");
+        return Ok(());
+    }
+
+    println!("Input devices:");
+    for device in &devices {
+        let selected = settings.input_device.as_deref() == Some(device.name.as_str());
+        let marker = if selected { '*' } else { ' ' };
+        let default = if device.is_default { " (default)" } else { "" };
+        println!("{marker} [{}] {}{default}", device.index, device.name);
+
+        if let Some(config) = &device.default_config {
+            println!(
+                "      records at: {} Hz, {} ch, {}",
+                config.min_sample_rate, config.channels, config.sample_format
+            );
+        }
+        for config in &device.configs {
+            let rates = if config.min_sample_rate == config.max_sample_rate {
+                format!("{} Hz", config.min_sample_rate)
+            } else {
+                format!("{}-{} Hz", config.min_sample_rate, config.max_sample_rate)
+            };
+            println!(
+                "      supports:   {rates}, {} ch, {}",
+                config.channels, config.sample_format
+            );
+        }
+    }
+
+    println!();
+    match &settings.input_device {
+        Some(selector) => println!("Configured device: {selector} (marked with *)"),
+        None => println!("Configured device: system default"),
+    }
+    println!("Select one with: whis config --input-device <INDEX|NAME>");
+
+    Ok(())
+}
@@ -1,5 +1,6 @@
 use anyhow::Result;
-use crate::{app, hotkey, ipc, service};
+use crate::app::{self, TranscriptionConfig};
+use crate::{hotkey, ipc, service};
 
 /// Guard to clean up PID and socket files on exit
 struct CleanupGuard;
@@ -10,10 +11,12 @@ impl Drop for CleanupGuard {
     }
 }
 
-pub fn run(hotkey_str: String) -> Result<()> {
-    // Check if FFmpeg is available
-    app::ensure_ffmpeg_installed()?;
-
+pub fn run(
+    hotkey_str: String,
+    cancel_hotkey_str: String,
+    translate_hotkey_str: String,
+    translate: bool,
+) -> Result<()> {
     // Check if service is already running
     if ipc::is_service_running() {
         eprintln!("Error: whis service is already running.");
@@ -22,7 +25,13 @@ pub fn run(hotkey_str: String) -> Result<()> {
     }
 
     // Load transcription configuration (provider + API key)
-    let config = app::load_transcription_config()?;
+    let config = TranscriptionConfig {
+        translate,
+        ..app::load_transcription_config()?
+    };
+
+    // FFmpeg is only needed for formats without a built-in encoder
+    app::ensure_ffmpeg_installed(config.finalize.format)?;
 
     // Write PID file
     ipc::write_pid_file()?;
@@ -32,8 +41,15 @@ pub fn run(hotkey_str: String) -> Result<()> {
 
     // Setup hotkey listener
     // This handles platform differences internally
-    println!("Registering hotkey: {}", hotkey_str);
-    let (hotkey_rx, _guard) = hotkey::setup(&hotkey_str)?;
+    println!(
+        "Registering hotkey: {} (cancel: {}, translate: {})",
+        hotkey_str, cancel_hotkey_str, translate_hotkey_str
+    );
+    let (hotkey_rx, _guard) = hotkey::setup(&[
+        (&hotkey_str, hotkey::HotkeyAction::Toggle),
+        (&cancel_hotkey_str, hotkey::HotkeyAction::Cancel),
+        (&translate_hotkey_str, hotkey::HotkeyAction::Translate),
+    ])?;
 
     // Create Tokio runtime
     let runtime = tokio::runtime::Runtime::new()?;
@@ -1,5 +1,9 @@
+pub mod batch;
+pub mod cancel;
 pub mod config;
+pub mod devices;
 pub mod listen;
 pub mod record_once;
 pub mod status;
 pub mod stop;
+pub mod transcribe;
@@ -1,81 +1,112 @@
 use anyhow::Result;
-use std::io::{self, Write};
+use std::io::{self, IsTerminal, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::Duration;
 use whis_core::{
-    AudioRecorder, RecordingOutput, copy_to_clipboard, parallel_transcribe,
-    transcribe_audio,
+    AudioRecorder, LevelReceiver, ProviderChain, StreamingTranscription, TranscriptionOptions,
+    copy_to_clipboard,
 };
 use crate::app;
 
-pub fn run() -> Result<()> {
+/// Width of the level meter bar in characters
+const METER_WIDTH: usize = 20;
+/// How often the level meter is redrawn
+const METER_REFRESH: Duration = Duration::from_millis(100);
+
+pub fn run(translate: bool) -> Result<()> {
     // Create Tokio runtime for async operations
     let runtime = tokio::runtime::Runtime::new()?;
 
-    // Check if FFmpeg is available
-    app::ensure_ffmpeg_installed()?;
-
     // Load transcription configuration (provider + API key)
     let config = app::load_transcription_config()?;
+    let settings = &config.settings;
+
+    // FFmpeg is only needed for formats without a built-in encoder
+    app::ensure_ffmpeg_installed(config.finalize.format)?;
+
+    let chain = ProviderChain::from_settings(settings, &config.provider)?;
+    let options = TranscriptionOptions {
+        translate,
+        ..TranscriptionOptions::from_settings(settings, &config.api_key, config.language.as_deref())
+    };
+    chain.check_options(&options)?;
 
     // Create recorder and start recording
-    let mut recorder = AudioRecorder::new()?;
-    recorder.start_recording()?;
+    let mut recorder = AudioRecorder::from_settings(settings)?;
+    // Chunks are transcribed while the recording continues
+    let stream = StreamingTranscription::start(
+        &mut recorder,
+        config.finalize,
+        chain,
+        options,
+        runtime.handle(),
+    )?;
+    if let Some(warning) = recorder.fallback_warning() {
+        eprintln!("Warning: {warning}");
+    }
 
     print!("Recording... (press Enter to stop)");
     io::stdout().flush()?;
+    let stop_meter = Arc::new(AtomicBool::new(false));
+    let meter = spawn_level_meter(recorder.subscribe_levels(), stop_meter.clone());
     app::wait_for_enter()?;
+    stop_meter.store(true, Ordering::Release);
+    let _ = meter.join();
 
-    // Finalize recording and get output
-    let audio_result = recorder.finalize_recording()?;
-
-    // Transcribe based on output type
-    let transcription = match audio_result {
-        RecordingOutput::Single(audio_data) => {
-            // Small file - simple transcription
-            print!("\rTranscribing...                        \n");
-            io::stdout().flush()?;
-
-            match transcribe_audio(
-                &config.provider,
-                &config.api_key,
-                config.language.as_deref(),
-                audio_data,
-            ) {
-                Ok(text) => text,
-                Err(e) => {
-                    eprintln!("Transcription error: {e}");
-                    std::process::exit(1);
-                }
-            }
-        }
-        RecordingOutput::Chunked(chunks) => {
-            // Large file - parallel transcription
-            print!("\rTranscribing...                        \n");
-            io::stdout().flush()?;
+    let transcript = stream.stop(&Mutex::new(Some(recorder)))?;
+    print!("\rTranscribing...                        \n");
+    io::stdout().flush()?;
 
-            runtime.block_on(async {
-                match parallel_transcribe(
-                    &config.provider,
-                    &config.api_key,
-                    config.language.as_deref(),
-                    chunks,
-                    None,
-                )
-                .await
-                {
-                    Ok(text) => text,
-                    Err(e) => {
-                        eprintln!("Transcription error: {e}");
-                        std::process::exit(1);
-                    }
-                }
-            })
-        }
+    let Some(transcript) = runtime.block_on(transcript)? else {
+        report_no_speech();
+        return Ok(());
     };
 
     // Copy to clipboard
-    copy_to_clipboard(&transcription)?;
+    copy_to_clipboard(&transcript.text)?;
 
     println!("Copied to clipboard");
 
     Ok(())
 }
+
+/// Redraw the recording line with the live input level until `stop` is set
+///
+/// When stdout isn't a terminal only the near-silence warning is printed.
+fn spawn_level_meter(levels: LevelReceiver, stop: Arc<AtomicBool>) -> JoinHandle<()> {
+    std::thread::spawn(move || {
+        let interactive = io::stdout().is_terminal();
+        let mut warned = false;
+
+        while !stop.load(Ordering::Acquire) {
+            let level = *levels.borrow();
+            if interactive {
+                let status = if level.silence_warning {
+                    format!("no input for {:.0}s, check your microphone", level.silent_secs)
+                } else {
+                    let filled = (level.meter() * METER_WIDTH as f32).round() as usize;
+                    format!(
+                        "[{}{}] {:>4.0} dB",
+                        "#".repeat(filled),
+                        " ".repeat(METER_WIDTH - filled),
+                        level.rms_db
+                    )
+                };
+                print!("\rRecording... {status:<40} (press Enter to stop)");
+                let _ = io::stdout().flush();
+            } else if level.silence_warning && !warned {
+                eprintln!("Warning: no input from the microphone, check that it isn't muted");
+            }
+            warned = level.silence_warning;
+            std::thread::sleep(METER_REFRESH);
+        }
+    })
+}
+
+/// Tell the user the recording was discarded rather than copying an empty
+/// or hallucinated transcript
+fn report_no_speech() {
+    println!("\rNo speech detected, nothing copied      ");
+}
@@ -12,8 +12,18 @@ pub fn run() -> Result<()> {
     let response = client.send_message(ipc::IpcMessage::Status)?;
 
     match response {
-        ipc::IpcResponse::Idle => println!("Status: Running (idle)"),
-        ipc::IpcResponse::Recording => println!("Status: Running (recording)"),
+        ipc::IpcResponse::Idle { microphone_on } => {
+            println!("Status: Running (idle)");
+            if microphone_on {
+                println!("Microphone: on (warm mode)");
+            }
+        }
+        ipc::IpcResponse::Recording(capture) => {
+            println!("Status: Running (recording)");
+            if !capture.is_clean() {
+                println!("Capture: {capture}");
+            }
+        }
         ipc::IpcResponse::Transcribing => println!("Status: Running (transcribing)"),
         ipc::IpcResponse::Error(e) => {
             eprintln!("Error: {e}");
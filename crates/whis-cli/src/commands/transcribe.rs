@@ -0,0 +1,115 @@
+use anyhow::{Context, Result};
+use std::io::{self, IsTerminal, Read, Write};
+use std::path::Path;
+use whis_core::{
+    AudioChunk, ProviderChain, RecordingData, RecordingOutput, Transcript, TranscriptFormat,
+    TranscriptionOptions, parallel_transcribe,
+};
+
+use crate::app::{self, TranscriptionConfig};
+use crate::args::TranscribeArgs;
+use crate::commands::batch;
+
+/// Reports chunks transcribed so far out of the total
+pub type Progress = Box<dyn Fn(usize, usize) + Send + Sync>;
+
+pub fn run(args: TranscribeArgs, translate: bool) -> Result<()> {
+    let runtime = tokio::runtime::Runtime::new()?;
+    let config = TranscriptionConfig {
+        translate,
+        ..app::load_transcription_config()?
+    };
+    app::ensure_ffmpeg_installed(config.finalize.format)?;
+
+    if args.input.is_dir() {
+        return runtime.block_on(batch::run(&args, config));
+    }
+
+    let memory_limit = config.settings.recording_memory_limit();
+    let recording = if args.input == Path::new("-") {
+        let mut data = Vec::new();
+        io::stdin()
+            .read_to_end(&mut data)
+            .context("Failed to read audio from stdin")?;
+        RecordingData::from_bytes(data, memory_limit)?
+    } else {
+        RecordingData::from_file(&args.input, memory_limit)?
+    };
+
+    // Progress goes to stderr so stdout holds only the transcript
+    eprint!("Transcribing...");
+    let progress: Progress = Box::new(|done, total| {
+        if total > 1 {
+            eprint!("\rTranscribing... {done}/{total} chunks");
+        }
+    });
+    let Some(transcript) =
+        runtime.block_on(transcribe(&config, recording, args.format, Some(progress)))?
+    else {
+        eprintln!("\rNo speech detected                    ");
+        return Ok(());
+    };
+    eprintln!("\rDone                                  ");
+
+    let rendered = whis_core::export::render(&transcript, args.format, &args.caption_options())?;
+    match &args.output {
+        Some(path) => {
+            std::fs::write(path, rendered)
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+            if io::stderr().is_terminal() {
+                eprintln!("Saved to {}", path.display());
+            }
+        }
+        None => {
+            let mut stdout = io::stdout().lock();
+            stdout.write_all(rendered.as_bytes())?;
+            stdout.flush()?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Transcribe a decoded recording through the usual chunking pipeline
+///
+/// For any `format` with timestamps, silence is kept instead of trimmed so
+/// that the times match the file. Returns None when no speech was detected.
+pub async fn transcribe(
+    config: &TranscriptionConfig,
+    recording: RecordingData,
+    format: TranscriptFormat,
+    progress: Option<Progress>,
+) -> Result<Option<Transcript>> {
+    let settings = &config.settings;
+    let timed = format.is_timed();
+    let mut finalize = config.finalize;
+    if timed {
+        finalize.vad = None;
+    }
+
+    let output = tokio::task::spawn_blocking(move || recording.finalize_with(&finalize))
+        .await
+        .context("Failed to join task")??;
+    let chunks = match output {
+        RecordingOutput::Single(audio) => vec![AudioChunk {
+            data: audio.data,
+            format: audio.format,
+            index: 0,
+            has_leading_overlap: false,
+            start_secs: 0.0,
+            exact_timing: true,
+        }],
+        RecordingOutput::Chunked(chunks) => chunks,
+        RecordingOutput::NoSpeech => return Ok(None),
+    };
+
+    let chain = ProviderChain::from_settings(settings, &config.provider)?;
+    let options = TranscriptionOptions {
+        word_timestamps: timed,
+        translate: config.translate,
+        ..TranscriptionOptions::from_settings(settings, &config.api_key, config.language.as_deref())
+    };
+    parallel_transcribe(chain, &options, chunks, progress)
+        .await
+        .map(Some)
+}
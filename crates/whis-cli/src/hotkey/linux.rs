@@ -4,15 +4,20 @@ use std::collections::HashSet;
 use std::sync::{Arc, Mutex};
 use std::sync::mpsc::Receiver;
 
+use super::HotkeyAction;
+
 pub struct HotkeyGuard;
 
-pub fn setup(hotkey_str: &str) -> Result<(Receiver<()>, HotkeyGuard)> {
-    let hotkey = Hotkey::parse(hotkey_str)?;
+pub fn setup(bindings: &[(&str, HotkeyAction)]) -> Result<(Receiver<HotkeyAction>, HotkeyGuard)> {
+    let hotkeys = bindings
+        .iter()
+        .map(|&(hotkey, action)| Ok((Hotkey::parse(hotkey)?, action)))
+        .collect::<Result<Vec<_>>>()?;
     let (tx, rx) = std::sync::mpsc::channel();
 
     std::thread::spawn(move || {
-        if let Err(e) = listen_for_hotkey(hotkey, move || {
-            let _ = tx.send(());
+        if let Err(e) = listen_for_hotkeys(hotkeys, move |action| {
+            let _ = tx.send(action);
         }) {
             eprintln!("Hotkey error: {e}");
         }
@@ -69,6 +74,18 @@ impl Hotkey {
             key,
         })
     }
+
+    /// Whether the combination is held down in `keys`
+    fn is_pressed(&self, keys: &HashSet<Key>) -> bool {
+        let ctrl_ok =
+            !self.ctrl || keys.contains(&Key::ControlLeft) || keys.contains(&Key::ControlRight);
+        let shift_ok =
+            !self.shift || keys.contains(&Key::ShiftLeft) || keys.contains(&Key::ShiftRight);
+        let alt_ok = !self.alt || keys.contains(&Key::Alt) || keys.contains(&Key::AltGr);
+        let super_ok =
+            !self.super_key || keys.contains(&Key::MetaLeft) || keys.contains(&Key::MetaRight);
+        ctrl_ok && shift_ok && alt_ok && super_ok && keys.contains(&self.key)
+    }
 }
 
 /// Parse a single key string into an rdev Key
@@ -142,11 +159,13 @@ fn parse_key(s: &str) -> Result<Key> {
     Ok(key)
 }
 
-/// Listen for a hotkey and call the callback when pressed
+/// Listen for hotkeys and call the callback with the action of the one pressed
 /// This function blocks and runs until an error occurs
-pub fn listen_for_hotkey<F>(hotkey: Hotkey, on_press: F) -> Result<()>
+///
+/// All hotkeys share one keyboard grab, since only one can be active at a time.
+pub fn listen_for_hotkeys<F>(hotkeys: Vec<(Hotkey, HotkeyAction)>, on_press: F) -> Result<()>
 where
-    F: Fn() + Send + 'static,
+    F: Fn(HotkeyAction) + Send + 'static,
 {
     let pressed_keys: Arc<Mutex<HashSet<Key>>> = Arc::new(Mutex::new(HashSet::new()));
     let pressed_keys_clone = pressed_keys.clone();
@@ -157,21 +176,11 @@ where
                 let mut keys = pressed_keys_clone.lock().unwrap();
                 keys.insert(key);
 
-                // Check if hotkey combination is pressed
-                let ctrl_ok = !hotkey.ctrl
-                    || keys.contains(&Key::ControlLeft)
-                    || keys.contains(&Key::ControlRight);
-                let shift_ok = !hotkey.shift
-                    || keys.contains(&Key::ShiftLeft)
-                    || keys.contains(&Key::ShiftRight);
-                let alt_ok = !hotkey.alt || keys.contains(&Key::Alt) || keys.contains(&Key::AltGr);
-                let super_ok = !hotkey.super_key
-                    || keys.contains(&Key::MetaLeft)
-                    || keys.contains(&Key::MetaRight);
-                let key_ok = keys.contains(&hotkey.key);
-
-                if ctrl_ok && shift_ok && alt_ok && super_ok && key_ok {
-                    on_press();
+                // Check if a hotkey combination is pressed
+                for (hotkey, action) in &hotkeys {
+                    if hotkey.is_pressed(&keys) {
+                        on_press(*action);
+                    }
                 }
             }
             EventType::KeyRelease(key) => {
@@ -16,12 +16,23 @@ mod non_linux;
 #[cfg(not(target_os = "linux"))]
 use non_linux as platform;
 
+/// What a hotkey does
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HotkeyAction {
+    /// Start a recording, or stop it and transcribe
+    Toggle,
+    /// Discard the recording or abort its transcription
+    Cancel,
+    /// Start a recording that is translated to English, or stop it like Toggle
+    Translate,
+}
+
 /// Opaque guard that keeps the hotkey listener alive
 pub struct HotkeyGuard(platform::HotkeyGuard);
 
-/// Setup the hotkey listener.
-/// Returns a receiver for hotkey events and a guard that must be kept alive.
-pub fn setup(hotkey_str: &str) -> Result<(Receiver<()>, HotkeyGuard)> {
-    let (rx, guard) = platform::setup(hotkey_str)?;
+/// Setup the hotkey listener for each (hotkey, action) pair.
+/// Returns a receiver for the actions triggered and a guard that must be kept alive.
+pub fn setup(bindings: &[(&str, HotkeyAction)]) -> Result<(Receiver<HotkeyAction>, HotkeyGuard)> {
+    let (rx, guard) = platform::setup(bindings)?;
     Ok((rx, HotkeyGuard(guard)))
 }
@@ -5,18 +5,25 @@ use interprocess::local_socket::{
 use serde::{Deserialize, Serialize};
 use std::io::{BufRead, BufReader, Write};
 use std::path::PathBuf;
+use whis_core::CaptureStats;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub enum IpcMessage {
     Stop,
     Status,
+    /// Discard the recording or abort its transcription
+    Cancel,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub enum IpcResponse {
     Success,
-    Recording,
-    Idle,
+    /// Recording, with the capture problems counted so far
+    Recording(CaptureStats),
+    Idle {
+        /// The microphone is kept open for pre-roll between recordings
+        microphone_on: bool,
+    },
     Transcribing,
     Error(String),
 }
@@ -7,25 +7,40 @@ mod service;
 
 use anyhow::Result;
 use clap::Parser;
+use std::process::ExitCode;
 use whis_core::set_verbose;
 
-fn main() -> Result<()> {
+fn main() -> ExitCode {
     let cli = args::Cli::parse();
 
     // Enable verbose logging if requested
     set_verbose(cli.verbose);
 
-    match cli.command {
-        Some(args::Commands::Listen { hotkey }) => commands::listen::run(hotkey),
+    match run(cli.command, cli.translate) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Error: {e:?}");
+            app::print_hint(&e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(command: Option<args::Commands>, translate: bool) -> Result<()> {
+    match command {
+        Some(args::Commands::Listen {
+            hotkey,
+            cancel_hotkey,
+            translate_hotkey,
+        }) => commands::listen::run(hotkey, cancel_hotkey, translate_hotkey, translate),
         Some(args::Commands::Stop) => commands::stop::run(),
+        Some(args::Commands::Cancel) => commands::cancel::run(),
         Some(args::Commands::Status) => commands::status::run(),
-        Some(args::Commands::Config {
-            openai_api_key,
-            mistral_api_key,
-            provider,
-            language,
-            show,
-        }) => commands::config::run(openai_api_key, mistral_api_key, provider, language, show),
-        None => commands::record_once::run(),
+        Some(args::Commands::Config(config_args)) => commands::config::run(config_args),
+        Some(args::Commands::Devices) => commands::devices::run(),
+        Some(args::Commands::Transcribe(transcribe_args)) => {
+            commands::transcribe::run(transcribe_args, translate)
+        }
+        None => commands::record_once::run(translate),
     }
 }
@@ -4,14 +4,20 @@ use std::sync::mpsc::Receiver;
 use std::sync::{Arc, Mutex};
 use tokio::time::sleep;
 
-use crate::app::TranscriptionConfig;
+use crate::app::{self, TranscriptionConfig};
+use crate::hotkey::HotkeyAction;
 use crate::ipc::{IpcMessage, IpcResponse, IpcServer};
 use std::time::Duration;
+use tokio::task::JoinHandle;
 use whis_core::{
-    AudioRecorder, RecordingOutput, TranscriptionProvider, copy_to_clipboard, parallel_transcribe,
-    transcribe_audio,
+    AudioRecorder, AutoStopOptions, AutoStopReason, CancellationToken, FinalizeOptions,
+    ProviderChain, Settings, StreamingTranscription, TranscriptionOptions, TranscriptionProvider,
+    copy_to_clipboard,
 };
 
+/// Transcription of a stopped recording (false = no speech, nothing copied)
+type TranscriptionTask = JoinHandle<Result<bool>>;
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 enum ServiceState {
     Idle,
@@ -22,9 +28,18 @@ enum ServiceState {
 pub struct Service {
     state: Arc<Mutex<ServiceState>>,
     recorder: Arc<Mutex<Option<AudioRecorder>>>,
+    /// Transcription of chunks streamed from the current recording
+    stream: Arc<Mutex<Option<StreamingTranscription>>>,
+    /// Transcription of the stopped recording, until its result is reported
+    transcription: Arc<Mutex<Option<TranscriptionTask>>>,
+    /// Cancels the requests of the current recording
+    cancel: Arc<Mutex<CancellationToken>>,
     provider: TranscriptionProvider,
     api_key: String,
     language: Option<String>,
+    finalize: FinalizeOptions,
+    /// Translate every recording, not only the translate hotkey's
+    translate: bool,
     recording_counter: Arc<Mutex<u32>>,
 }
 
@@ -33,19 +48,25 @@ impl Service {
         Ok(Self {
             state: Arc::new(Mutex::new(ServiceState::Idle)),
             recorder: Arc::new(Mutex::new(None)),
+            stream: Arc::new(Mutex::new(None)),
+            transcription: Arc::new(Mutex::new(None)),
+            cancel: Arc::new(Mutex::new(CancellationToken::new())),
             provider: config.provider,
             api_key: config.api_key,
             language: config.language,
+            finalize: config.finalize,
+            translate: config.translate,
             recording_counter: Arc::new(Mutex::new(0)),
         })
     }
 
     /// Run the service main loop
-    pub async fn run(&self, hotkey_rx: Option<Receiver<()>>) -> Result<()> {
+    pub async fn run(&self, hotkey_rx: Option<Receiver<HotkeyAction>>) -> Result<()> {
         // Create IPC server
         let ipc_server = IpcServer::new().context("Failed to create IPC server")?;
 
         println!("whis listening. Ctrl+C to stop.");
+        self.warm_microphone();
 
         loop {
             // Check for incoming IPC connections (non-blocking)
@@ -62,13 +83,32 @@ impl Service {
                 }
             }
 
-            // Check for hotkey toggle signal (non-blocking)
-            if let Some(ref rx) = hotkey_rx {
-                if rx.try_recv().is_ok() {
-                    self.handle_toggle().await;
+            // Check for hotkey signals (non-blocking)
+            if let Some(ref rx) = hotkey_rx
+                && let Ok(action) = rx.try_recv()
+            {
+                match action {
+                    HotkeyAction::Toggle => {
+                        self.handle_toggle(self.translate).await;
+                    }
+                    HotkeyAction::Translate => {
+                        self.handle_toggle(true).await;
+                    }
+                    HotkeyAction::Cancel => {
+                        self.handle_cancel();
+                    }
                 }
             }
 
+            // A recording that reached a limit stops the same way as with the hotkey
+            if let Some(reason) = self.auto_stop_reason() {
+                let count = *self.recording_counter.lock().unwrap();
+                println!("\r#{count} stopped: {reason}");
+                self.handle_toggle(false).await;
+            }
+
+            self.finish_transcription().await;
+
             // Small sleep to prevent busy waiting
             sleep(Duration::from_millis(10)).await;
         }
@@ -86,11 +126,21 @@ impl Service {
                 });
                 IpcResponse::Success
             }
+            IpcMessage::Cancel => self.handle_cancel(),
             IpcMessage::Status => {
                 let state = *self.state.lock().unwrap();
                 match state {
-                    ServiceState::Idle => IpcResponse::Idle,
-                    ServiceState::Recording => IpcResponse::Recording,
+                    ServiceState::Idle => IpcResponse::Idle {
+                        microphone_on: self.microphone_on(),
+                    },
+                    ServiceState::Recording => IpcResponse::Recording(
+                        self.recorder
+                            .lock()
+                            .unwrap()
+                            .as_ref()
+                            .map(AudioRecorder::capture_stats)
+                            .unwrap_or_default(),
+                    ),
                     ServiceState::Transcribing => IpcResponse::Transcribing,
                 }
             }
@@ -98,7 +148,10 @@ impl Service {
     }
 
     /// Handle toggle command (start/stop recording)
-    async fn handle_toggle(&self) -> IpcResponse {
+    ///
+    /// `translate` only matters when this starts a recording: it is then
+    /// translated to English instead of transcribed.
+    async fn handle_toggle(&self, translate: bool) -> IpcResponse {
         let current_state = *self.state.lock().unwrap();
 
         match current_state {
@@ -109,20 +162,27 @@ impl Service {
                     *c += 1;
                     *c
                 };
-                match self.start_recording().await {
+                match self.start_recording(translate).await {
+                    Ok(_) if translate => {
+                        print!("#{count} recording (translating to English)...");
+                        let _ = std::io::stdout().flush();
+                        IpcResponse::Recording(Default::default())
+                    }
                     Ok(_) => {
                         print!("#{count} recording...");
                         let _ = std::io::stdout().flush();
-                        IpcResponse::Recording
+                        IpcResponse::Recording(Default::default())
                     }
                     Err(e) => {
                         println!("#{count} error: {e}");
+                        app::print_hint(&e);
                         IpcResponse::Error(e.to_string())
                     }
                 }
             }
             ServiceState::Recording => {
-                // Stop recording and transcribe
+                // Stop recording and transcribe in the background, so the
+                // transcription can still be cancelled
                 *self.state.lock().unwrap() = ServiceState::Transcribing;
                 let count = *self.recording_counter.lock().unwrap();
 
@@ -130,15 +190,15 @@ impl Service {
                 print!("\r#{count} transcribing...");
                 let _ = std::io::stdout().flush();
 
-                match self.stop_and_transcribe().await {
-                    Ok(_) => {
-                        *self.state.lock().unwrap() = ServiceState::Idle;
-                        println!("\r#{count} done            ");
-                        IpcResponse::Success
+                match self.stop_and_transcribe() {
+                    Ok(task) => {
+                        *self.transcription.lock().unwrap() = Some(task);
+                        IpcResponse::Transcribing
                     }
                     Err(e) => {
                         *self.state.lock().unwrap() = ServiceState::Idle;
                         println!("\r#{count} error: {e}");
+                        app::print_hint(&e);
                         IpcResponse::Error(e.to_string())
                     }
                 }
@@ -150,10 +210,146 @@ impl Service {
         }
     }
 
+    /// Report the background transcription once it has finished
+    async fn finish_transcription(&self) {
+        let finished = {
+            let mut transcription = self.transcription.lock().unwrap();
+            match transcription.as_ref() {
+                Some(task) if task.is_finished() => transcription.take(),
+                _ => None,
+            }
+        };
+        let Some(task) = finished else {
+            return;
+        };
+
+        let result = match task.await {
+            Ok(result) => result,
+            Err(e) => Err(e).context("Failed to join task"),
+        };
+        *self.state.lock().unwrap() = ServiceState::Idle;
+        let count = *self.recording_counter.lock().unwrap();
+        match result {
+            Ok(true) => println!("\r#{count} done            "),
+            Ok(false) => println!("\r#{count} no speech detected"),
+            Err(e) => {
+                println!("\r#{count} error: {e}");
+                app::print_hint(&e);
+            }
+        }
+    }
+
+    /// Discard the current recording, or abort its transcription
+    fn handle_cancel(&self) -> IpcResponse {
+        let current_state = *self.state.lock().unwrap();
+        match current_state {
+            ServiceState::Idle => {
+                return IpcResponse::Idle {
+                    microphone_on: self.microphone_on(),
+                };
+            }
+            ServiceState::Recording | ServiceState::Transcribing => {
+                if let Some(task) = self.transcription.lock().unwrap().take() {
+                    task.abort();
+                }
+                let stream = self.stream.lock().unwrap().take();
+                if let Some(stream) = stream {
+                    stream.cancel(&self.recorder);
+                }
+            }
+        }
+        // Also stops the chunk requests of a stopped recording still in flight
+        self.cancel.lock().unwrap().cancel();
+
+        *self.state.lock().unwrap() = ServiceState::Idle;
+        let count = *self.recording_counter.lock().unwrap();
+        println!("\r#{count} cancelled         ");
+        IpcResponse::Success
+    }
+
+    /// Whether the microphone is kept open between recordings
+    fn microphone_on(&self) -> bool {
+        self.recorder
+            .lock()
+            .unwrap()
+            .as_ref()
+            .is_some_and(AudioRecorder::is_armed)
+    }
+
+    /// Open the microphone ahead of the first recording, if warm mode is on
+    ///
+    /// The recorder then stays in `recorder` between recordings.
+    fn warm_microphone(&self) {
+        let settings = Settings::load();
+        let Some(pre_roll) = settings.pre_roll() else {
+            return;
+        };
+
+        let armed = AudioRecorder::from_settings(&settings).and_then(|mut recorder| {
+            recorder.arm(pre_roll)?;
+            Ok(recorder)
+        });
+        match armed {
+            Ok(recorder) => {
+                println!(
+                    "Microphone is on: keeping the last {:.1}s between recordings (warm mode)",
+                    pre_roll.as_secs_f32()
+                );
+                *self.recorder.lock().unwrap() = Some(recorder);
+            }
+            Err(e) => eprintln!("Warning: failed to keep the microphone warm: {e}"),
+        }
+    }
+
+    /// Why the current recording should stop on its own, if it should
+    fn auto_stop_reason(&self) -> Option<AutoStopReason> {
+        self.recorder
+            .lock()
+            .unwrap()
+            .as_ref()
+            .and_then(AudioRecorder::auto_stop_reason)
+    }
+
     /// Start recording audio
-    async fn start_recording(&self) -> Result<()> {
-        let mut recorder = AudioRecorder::new()?;
-        recorder.start_recording()?;
+    async fn start_recording(&self, translate: bool) -> Result<()> {
+        let settings = Settings::load();
+        let chain = ProviderChain::from_settings(&settings, &self.provider)?;
+        let cancel = CancellationToken::new();
+        let options = TranscriptionOptions {
+            translate,
+            cancel: cancel.clone(),
+            ..TranscriptionOptions::from_settings(
+                &settings,
+                &self.api_key,
+                self.language.as_deref(),
+            )
+        };
+        chain.check_options(&options)?;
+
+        // Reuse the warm microphone; otherwise reload settings so device
+        // changes apply without restarting the service
+        let armed = self.recorder.lock().unwrap().take();
+        let mut recorder = match armed {
+            Some(mut recorder) => {
+                recorder.set_auto_stop(AutoStopOptions::from_settings(&settings));
+                recorder
+            }
+            None => AudioRecorder::from_settings(&settings)?,
+        };
+        // Chunks are transcribed while the recording continues
+        let stream = StreamingTranscription::start(
+            &mut recorder,
+            self.finalize,
+            chain,
+            options,
+            &tokio::runtime::Handle::current(),
+        )?;
+        if let Some(warning) = recorder.fallback_warning() {
+            eprintln!("\nWarning: {warning}");
+        }
+
+        *self.cancel.lock().unwrap() = cancel;
+        *self.stream.lock().unwrap() = Some(stream);
 
         *self.recorder.lock().unwrap() = Some(recorder);
         *self.state.lock().unwrap() = ServiceState::Recording;
@@ -161,49 +357,27 @@ impl Service {
         Ok(())
     }
 
-    /// Stop recording and transcribe
-    async fn stop_and_transcribe(&self) -> Result<()> {
-        // Get the recorder
-        let mut recorder = self
-            .recorder
+    /// Stop recording and start transcribing in the background
+    ///
+    /// The task returns false when no speech was detected and nothing was copied.
+    fn stop_and_transcribe(&self) -> Result<TranscriptionTask> {
+        let stream = self
+            .stream
             .lock()
             .unwrap()
             .take()
             .context("No active recording")?;
+        let transcript = stream.stop(&self.recorder)?;
+        Ok(tokio::spawn(async move {
+            let Some(transcript) = transcript.await? else {
+                return Ok(false);
+            };
 
-        // Stop recording and get the Send-safe recording data
-        // (cpal::Stream is dropped here, making RecordingData movable across threads)
-        let recording_data = recorder.stop_recording()?;
-
-        // Finalize recording (blocking operation, run in tokio blocking task)
-        let audio_result = tokio::task::spawn_blocking(move || recording_data.finalize())
-            .await
-            .context("Failed to join task")??;
-
-        // Transcribe based on output type
-        let api_key = self.api_key.clone();
-        let provider = self.provider.clone();
-        let language = self.language.clone();
-        let transcription = match audio_result {
-            RecordingOutput::Single(audio_data) => {
-                // Small file - use simple blocking transcription
-                tokio::task::spawn_blocking(move || {
-                    transcribe_audio(&provider, &api_key, language.as_deref(), audio_data)
-                })
+            tokio::task::spawn_blocking(move || copy_to_clipboard(&transcript.text))
                 .await
-                .context("Failed to join task")??
-            }
-            RecordingOutput::Chunked(chunks) => {
-                // Large file - use parallel async transcription
-                parallel_transcribe(&provider, &api_key, language.as_deref(), chunks, None).await?
-            }
-        };
-
-        // Copy to clipboard (blocking operation)
-        tokio::task::spawn_blocking(move || copy_to_clipboard(&transcription))
-            .await
-            .context("Failed to join task")??;
+                .context("Failed to join task")??;
 
-        Ok(())
+            Ok(true)
+        }))
     }
 }
@@ -0,0 +1,246 @@
+//! Ending recordings that were left running.
+//!
+//! The capture drain thread feeds every sample through an [`AutoStopMonitor`],
+//! which tracks how long the recording has run and how long it has been since
+//! the last speech. When a limit is reached it publishes the reason on a
+//! watch channel; the recorder keeps capturing until the caller stops it, so
+//! the usual stop-and-transcribe path handles auto-stops too.
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::time::Duration;
+use tokio::sync::watch;
+
+use super::vad::{self, FRAME_MS, VadOptions};
+
+/// How much recent audio the adaptive speech threshold is estimated from
+const NOISE_HISTORY_SECS: usize = 30;
+/// How often the adaptive speech threshold is re-estimated
+const THRESHOLD_UPDATE_MS: usize = 1000;
+
+/// Receives the reason once a recording should stop on its own
+pub type AutoStopReceiver = watch::Receiver<Option<AutoStopReason>>;
+
+/// Why a recording stopped without the user asking
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AutoStopReason {
+    /// The recording reached the maximum duration
+    MaxDuration(Duration),
+    /// No speech was heard for this long
+    Silence(Duration),
+}
+
+impl fmt::Display for AutoStopReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MaxDuration(limit) => {
+                write!(f, "reached the {}s recording limit", limit.as_secs())
+            }
+            Self::Silence(timeout) => write!(f, "no speech for {}s", timeout.as_secs()),
+        }
+    }
+}
+
+/// When a recording should stop on its own
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct AutoStopOptions {
+    /// Stop once the recording is this long (None = no limit)
+    pub max_duration: Option<Duration>,
+    /// Stop after this long without speech, counted from the start or the
+    /// last utterance (None = never)
+    pub silence: Option<Duration>,
+    /// Speech level in dBFS (None = adaptive, as for silence trimming)
+    pub threshold_db: Option<f32>,
+}
+
+impl AutoStopOptions {
+    pub fn from_settings(settings: &crate::Settings) -> Self {
+        let secs = |secs: u32| (secs > 0).then(|| Duration::from_secs(secs as u64));
+        Self {
+            max_duration: secs(settings.max_recording_secs),
+            silence: secs(settings.silence_timeout_secs),
+            threshold_db: settings.vad_threshold_db,
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.max_duration.is_some() || self.silence.is_some()
+    }
+}
+
+/// Watches captured samples and reports when a limit is reached
+pub(crate) struct AutoStopMonitor {
+    options: AutoStopOptions,
+    sender: watch::Sender<Option<AutoStopReason>>,
+    /// Interleaved samples per frame and per second
+    frame_len: usize,
+    rate: usize,
+    /// Voiced frames in a row needed to count as speech
+    min_speech_frames: usize,
+    frame_sum: f32,
+    frame_count: usize,
+    history: VecDeque<f32>,
+    threshold: Option<f32>,
+    frames: usize,
+    quiet_frames: usize,
+    voiced_run: usize,
+    stopped: bool,
+}
+
+impl AutoStopMonitor {
+    pub fn new(
+        options: AutoStopOptions,
+        sender: watch::Sender<Option<AutoStopReason>>,
+        sample_rate: u32,
+        channels: u16,
+    ) -> Self {
+        sender.send_replace(None);
+        let rate = sample_rate as usize * channels.max(1) as usize;
+        let min_speech_ms = VadOptions::default().min_speech_ms as usize;
+
+        Self {
+            options,
+            sender,
+            frame_len: (rate * FRAME_MS / 1000).max(1),
+            rate,
+            min_speech_frames: min_speech_ms.div_ceil(FRAME_MS),
+            frame_sum: 0.0,
+            frame_count: 0,
+            history: VecDeque::new(),
+            threshold: options.threshold_db,
+            frames: 0,
+            quiet_frames: 0,
+            voiced_run: 0,
+            stopped: false,
+        }
+    }
+
+    pub fn push(&mut self, samples: &[f32]) {
+        if self.stopped || !self.options.is_enabled() {
+            return;
+        }
+
+        for &sample in samples {
+            self.frame_sum += sample * sample;
+            self.frame_count += 1;
+            if self.frame_count == self.frame_len {
+                let mean_square = self.frame_sum / self.frame_count as f32;
+                let level = 10.0 * mean_square.max(1e-12).log10();
+                self.frame_sum = 0.0;
+                self.frame_count = 0;
+                if let Some(reason) = self.frame(level) {
+                    crate::verbose!("Auto-stop: {reason}");
+                    self.sender.send_replace(Some(reason));
+                    self.stopped = true;
+                    return;
+                }
+            }
+        }
+    }
+
+    fn frame(&mut self, level: f32) -> Option<AutoStopReason> {
+        self.frames += 1;
+
+        if self.options.threshold_db.is_none() {
+            self.history.push_back(level);
+            if self.history.len() > NOISE_HISTORY_SECS * 1000 / FRAME_MS {
+                self.history.pop_front();
+            }
+            if self.threshold.is_none()
+                || self.frames.is_multiple_of(THRESHOLD_UPDATE_MS / FRAME_MS)
+            {
+                self.threshold = Some(vad::adaptive_threshold(self.history.make_contiguous()));
+            }
+        }
+
+        let voiced = self.threshold.is_some_and(|threshold| level >= threshold);
+        self.voiced_run = if voiced { self.voiced_run + 1 } else { 0 };
+        if self.voiced_run >= self.min_speech_frames {
+            self.quiet_frames = 0;
+        } else {
+            self.quiet_frames += 1;
+        }
+
+        let elapsed = self.duration(self.frames);
+        if let Some(limit) = self.options.max_duration
+            && elapsed >= limit
+        {
+            return Some(AutoStopReason::MaxDuration(limit));
+        }
+        if let Some(timeout) = self.options.silence
+            && self.duration(self.quiet_frames) >= timeout
+        {
+            return Some(AutoStopReason::Silence(timeout));
+        }
+        None
+    }
+
+    fn duration(&self, frames: usize) -> Duration {
+        Duration::from_secs_f64((frames * self.frame_len) as f64 / self.rate as f64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RATE: u32 = 16_000;
+
+    fn monitor(options: AutoStopOptions) -> (AutoStopMonitor, AutoStopReceiver) {
+        let (sender, receiver) = watch::channel(None);
+        (AutoStopMonitor::new(options, sender, RATE, 1), receiver)
+    }
+
+    fn tone(secs: f32) -> Vec<f32> {
+        (0..(RATE as f32 * secs) as usize)
+            .map(|i| 0.3 * (i as f32 * 0.2).sin())
+            .collect()
+    }
+
+    fn noise(secs: f32) -> Vec<f32> {
+        (0..(RATE as f32 * secs) as usize)
+            .map(|i| 0.001 * ((i * 7919 % 1000) as f32 / 500.0 - 1.0))
+            .collect()
+    }
+
+    #[test]
+    fn stops_at_max_duration() {
+        let (mut monitor, reason) = monitor(AutoStopOptions {
+            max_duration: Some(Duration::from_secs(3)),
+            ..Default::default()
+        });
+        monitor.push(&tone(2.9));
+        assert_eq!(*reason.borrow(), None);
+        monitor.push(&tone(0.2));
+        assert_eq!(
+            *reason.borrow(),
+            Some(AutoStopReason::MaxDuration(Duration::from_secs(3)))
+        );
+    }
+
+    #[test]
+    fn stops_after_silence_following_speech() {
+        let timeout = Duration::from_secs(2);
+        let (mut monitor, reason) = monitor(AutoStopOptions {
+            silence: Some(timeout),
+            ..Default::default()
+        });
+
+        // Pauses shorter than the timeout don't stop the recording
+        for _ in 0..3 {
+            monitor.push(&noise(1.5));
+            monitor.push(&tone(1.0));
+        }
+        assert_eq!(*reason.borrow(), None);
+
+        monitor.push(&noise(2.1));
+        assert_eq!(*reason.borrow(), Some(AutoStopReason::Silence(timeout)));
+    }
+
+    #[test]
+    fn disabled_never_stops() {
+        let (mut monitor, reason) = monitor(AutoStopOptions::default());
+        monitor.push(&noise(10.0));
+        assert_eq!(*reason.borrow(), None);
+    }
+}
@@ -0,0 +1,450 @@
+//! Moving samples off the real-time audio thread.
+//!
+//! The cpal input callback must never block, so it only copies samples into
+//! a lock-free single-producer ring buffer. A drain thread empties the ring
+//! into the recorder's buffer, where the chunk streamer and spill worker pick
+//! them up, and hands each batch to an observer (level metering, auto-stop)
+//! on the way. While the microphone is kept open between recordings, it only
+//! keeps a short pre-roll that is prepended to the next recording.
+//!
+//! When the drain thread falls behind and the ring fills, the callback drops
+//! the samples that don't fit and counts an overrun instead of waiting.
+
+use rtrb::{Consumer, Producer, RingBuffer};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+/// How much audio the ring buffer holds before samples are dropped
+pub(crate) const RING_SECS: usize = 2;
+
+/// How often the drain thread empties the ring buffer
+const DRAIN_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Capture problems counted during a recording
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CaptureStats {
+    /// Callbacks whose samples didn't all fit in the ring buffer
+    pub overruns: u64,
+    /// Samples lost to overruns
+    pub dropped_samples: u64,
+    /// Stream errors reported by the audio backend, such as device xruns
+    pub dropouts: u64,
+}
+
+impl CaptureStats {
+    /// Whether the recording was captured without gaps
+    pub fn is_clean(&self) -> bool {
+        self.overruns == 0 && self.dropouts == 0
+    }
+}
+
+impl fmt::Display for CaptureStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} overrun(s) ({} samples dropped), {} dropout(s)",
+            self.overruns, self.dropped_samples, self.dropouts
+        )
+    }
+}
+
+/// Counters shared between the audio callback, the drain thread and the recorder
+#[derive(Default)]
+pub(crate) struct CaptureCounters {
+    overruns: AtomicU64,
+    dropped_samples: AtomicU64,
+    dropouts: AtomicU64,
+}
+
+impl CaptureCounters {
+    /// Count an error reported by the audio backend
+    pub fn record_dropout(&self) {
+        self.dropouts.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn reset(&self) {
+        self.overruns.store(0, Ordering::Relaxed);
+        self.dropped_samples.store(0, Ordering::Relaxed);
+        self.dropouts.store(0, Ordering::Relaxed);
+    }
+
+    pub fn snapshot(&self) -> CaptureStats {
+        CaptureStats {
+            overruns: self.overruns.load(Ordering::Relaxed),
+            dropped_samples: self.dropped_samples.load(Ordering::Relaxed),
+            dropouts: self.dropouts.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Called on the drain thread with each batch of samples moved out of the ring buffer
+pub(crate) type Observer = Box<dyn FnMut(&[f32]) + Send>;
+
+/// Where drained samples go: the recording, or a rolling pre-roll buffer
+/// while the microphone is kept open between recordings
+#[derive(Default)]
+pub(crate) struct CaptureControl {
+    recording: AtomicBool,
+    /// Samples kept while not recording
+    pre_roll: AtomicUsize,
+}
+
+impl CaptureControl {
+    /// Start or stop appending to the recording
+    ///
+    /// When recording starts, the buffered pre-roll is added first.
+    pub fn set_recording(&self, recording: bool) {
+        self.recording.store(recording, Ordering::Release);
+    }
+
+    pub fn set_pre_roll(&self, samples: usize) {
+        self.pre_roll.store(samples, Ordering::Release);
+    }
+}
+
+/// Create the ring buffer (holding `capacity` samples) and start draining it into `samples`
+pub(crate) fn capture(
+    capacity: usize,
+    channels: u16,
+    samples: Arc<Mutex<Vec<f32>>>,
+    counters: Arc<CaptureCounters>,
+    control: Arc<CaptureControl>,
+    observer: Observer,
+) -> (CaptureInput, CaptureDrain) {
+    let (producer, consumer) = RingBuffer::new(capacity.max(1));
+    let input = CaptureInput {
+        producer,
+        channels: channels.max(1) as usize,
+        counters: counters.clone(),
+    };
+    let worker = Worker {
+        consumer,
+        samples,
+        pre_roll: VecDeque::new(),
+        counters,
+        control,
+        observer,
+    };
+    (input, CaptureDrain::spawn(worker))
+}
+
+/// Writing end of the ring buffer, owned by the audio callback
+pub(crate) struct CaptureInput {
+    producer: Producer<f32>,
+    channels: usize,
+    counters: Arc<CaptureCounters>,
+}
+
+impl CaptureInput {
+    /// Copy one callback's worth of samples without blocking or allocating
+    pub fn write<T>(&mut self, data: &[T])
+    where
+        T: cpal::Sample,
+        f32: cpal::FromSample<T>,
+    {
+        // Only whole frames, so channels stay interleaved after a drop
+        let mut n = data.len().min(self.producer.slots());
+        n -= n % self.channels;
+        if let Ok(chunk) = self.producer.write_chunk_uninit(n) {
+            chunk.fill_from_iter(data[..n].iter().map(|&s| cpal::Sample::from_sample(s)));
+        }
+
+        if n < data.len() {
+            self.counters.overruns.fetch_add(1, Ordering::Relaxed);
+            self.counters
+                .dropped_samples
+                .fetch_add((data.len() - n) as u64, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Thread moving samples from the ring buffer into the recorder's buffer
+pub(crate) struct CaptureDrain {
+    stop: Arc<AtomicBool>,
+    /// Completed drain passes, for [`CaptureDrain::sync`]
+    passes: Arc<AtomicU64>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl CaptureDrain {
+    fn spawn(worker: Worker) -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+        let passes = Arc::new(AtomicU64::new(0));
+        let (stop_flag, pass_count) = (stop.clone(), passes.clone());
+        let handle = std::thread::spawn(move || worker.run(stop_flag, pass_count));
+        Self {
+            stop,
+            passes,
+            handle: Some(handle),
+        }
+    }
+
+    /// Wait until the drain thread has acted on every [`CaptureControl`]
+    /// change made before this call
+    pub fn sync(&self) {
+        // The pass in progress may have started before the change
+        let target = self.passes.load(Ordering::Acquire) + 2;
+        while self.passes.load(Ordering::Acquire) < target {
+            if let Some(handle) = &self.handle {
+                handle.thread().unpark();
+            }
+            std::thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    /// Move the remaining samples over and stop
+    ///
+    /// Call after the stream is dropped, so nothing is written afterwards.
+    pub fn finish(mut self) {
+        self.stop.store(true, Ordering::Release);
+        if let Some(handle) = self.handle.take() {
+            handle.thread().unpark();
+            let _ = handle.join();
+        }
+    }
+}
+
+impl Drop for CaptureDrain {
+    fn drop(&mut self) {
+        // Stop without waiting when the recorder goes away mid-recording
+        self.stop.store(true, Ordering::Release);
+        if let Some(handle) = &self.handle {
+            handle.thread().unpark();
+        }
+    }
+}
+
+struct Worker {
+    consumer: Consumer<f32>,
+    samples: Arc<Mutex<Vec<f32>>>,
+    /// Most recent audio while not recording
+    pre_roll: VecDeque<f32>,
+    counters: Arc<CaptureCounters>,
+    control: Arc<CaptureControl>,
+    observer: Observer,
+}
+
+impl Worker {
+    fn run(mut self, stop: Arc<AtomicBool>, passes: Arc<AtomicU64>) {
+        let mut reported = 0;
+
+        loop {
+            let stopping = stop.load(Ordering::Acquire);
+            self.drain();
+            passes.fetch_add(1, Ordering::Release);
+            if stopping {
+                break;
+            }
+
+            let stats = self.counters.snapshot();
+            if stats.overruns > reported {
+                reported = stats.overruns;
+                crate::verbose!(
+                    "Audio overrun: {} samples dropped so far",
+                    stats.dropped_samples
+                );
+            }
+            std::thread::park_timeout(DRAIN_INTERVAL);
+        }
+    }
+
+    fn drain(&mut self) {
+        let recording = self.control.recording.load(Ordering::Acquire);
+        if recording && !self.pre_roll.is_empty() {
+            self.samples.lock().unwrap().extend(self.pre_roll.drain(..));
+        }
+
+        let Ok(chunk) = self.consumer.read_chunk(self.consumer.slots()) else {
+            return;
+        };
+        if chunk.is_empty() {
+            return;
+        }
+
+        let (first, second) = chunk.as_slices();
+        (self.observer)(first);
+        (self.observer)(second);
+        if recording {
+            let mut samples = self.samples.lock().unwrap();
+            samples.extend_from_slice(first);
+            samples.extend_from_slice(second);
+        } else {
+            self.pre_roll.extend(first);
+            self.pre_roll.extend(second);
+            let keep = self.control.pre_roll.load(Ordering::Acquire);
+            let excess = self.pre_roll.len().saturating_sub(keep);
+            self.pre_roll.drain(..excess);
+        }
+        chunk.commit_all();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CALLBACK_LEN: usize = 512;
+
+    struct Capture {
+        input: CaptureInput,
+        drain: CaptureDrain,
+        samples: Arc<Mutex<Vec<f32>>>,
+        counters: Arc<CaptureCounters>,
+        control: Arc<CaptureControl>,
+    }
+
+    fn start(capacity: usize, channels: u16, recording: bool) -> Capture {
+        let samples = Arc::new(Mutex::new(Vec::new()));
+        let counters = Arc::new(CaptureCounters::default());
+        let control = Arc::new(CaptureControl::default());
+        control.set_recording(recording);
+        let (input, drain) = capture(
+            capacity,
+            channels,
+            samples.clone(),
+            counters.clone(),
+            control.clone(),
+            Box::new(|_| {}),
+        );
+        Capture {
+            input,
+            drain,
+            samples,
+            counters,
+            control,
+        }
+    }
+
+    /// Samples numbered 0, 1, 2, ... so gaps and reordering are visible
+    fn numbered(start: usize, len: usize) -> Vec<f32> {
+        (start..start + len).map(|i| i as f32).collect()
+    }
+
+    #[test]
+    fn concurrent_callbacks_arrive_in_order() {
+        let Capture {
+            mut input,
+            drain,
+            samples,
+            counters,
+            ..
+        } = start(1 << 16, 1, true);
+
+        // Callbacks at roughly real-time pace while the drain thread runs
+        let callbacks = 4000;
+        let producer = std::thread::spawn(move || {
+            for i in 0..callbacks {
+                input.write(&numbered(i * CALLBACK_LEN, CALLBACK_LEN));
+                std::thread::sleep(Duration::from_micros(500));
+            }
+        });
+        producer.join().unwrap();
+        drain.finish();
+
+        let captured = std::mem::take(&mut *samples.lock().unwrap());
+        assert_eq!(counters.snapshot(), CaptureStats::default());
+        assert_eq!(captured, numbered(0, callbacks * CALLBACK_LEN));
+    }
+
+    #[test]
+    fn full_ring_drops_samples_and_counts_overruns() {
+        let Capture {
+            mut input,
+            drain,
+            samples,
+            counters,
+            ..
+        } = start(4 * CALLBACK_LEN + 1, 2, true);
+
+        // Stall the drain thread so the callbacks fill the ring
+        let stall = samples.lock().unwrap();
+        std::thread::sleep(DRAIN_INTERVAL * 3);
+        let callbacks = 10;
+        for i in 0..callbacks {
+            input.write(&numbered(i * CALLBACK_LEN, CALLBACK_LEN));
+        }
+        drop(stall);
+        drop(input);
+        drain.finish();
+
+        let captured = std::mem::take(&mut *samples.lock().unwrap());
+        let stats = counters.snapshot();
+        assert!(stats.overruns > 0);
+        assert_eq!(stats.dropouts, 0);
+        assert_eq!(
+            captured.len() as u64 + stats.dropped_samples,
+            (callbacks * CALLBACK_LEN) as u64
+        );
+        // Whatever got through is still in order, in whole stereo frames
+        assert!(captured.windows(2).all(|w| w[0] < w[1]));
+        assert!(
+            captured
+                .chunks(2)
+                .all(|frame| (frame[0] as usize).is_multiple_of(2))
+        );
+    }
+
+    #[test]
+    fn integer_samples_are_converted() {
+        let Capture {
+            mut input,
+            drain,
+            samples,
+            ..
+        } = start(16, 1, true);
+
+        input.write(&[i16::MIN, 0, i16::MAX]);
+        drop(input);
+        drain.finish();
+
+        let captured = samples.lock().unwrap();
+        assert_eq!(captured.len(), 3);
+        assert_eq!(captured[0], -1.0);
+        assert_eq!(captured[1], 0.0);
+        assert!((captured[2] - 1.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn pre_roll_is_prepended_to_the_recording() {
+        let Capture {
+            mut input,
+            drain,
+            samples,
+            control,
+            ..
+        } = start(1 << 16, 1, false);
+        let pre_roll = 3 * CALLBACK_LEN;
+        control.set_pre_roll(pre_roll);
+
+        // Armed: only the most recent audio is kept
+        for i in 0..10 {
+            input.write(&numbered(i * CALLBACK_LEN, CALLBACK_LEN));
+        }
+        drain.sync();
+        assert!(samples.lock().unwrap().is_empty());
+
+        control.set_recording(true);
+        for i in 10..20 {
+            input.write(&numbered(i * CALLBACK_LEN, CALLBACK_LEN));
+        }
+        drain.sync();
+        control.set_recording(false);
+        drain.sync();
+
+        // Audio after the recording stopped goes back to the pre-roll
+        input.write(&numbered(20 * CALLBACK_LEN, CALLBACK_LEN));
+        drain.sync();
+
+        let captured = std::mem::take(&mut *samples.lock().unwrap());
+        assert_eq!(
+            captured,
+            numbered(10 * CALLBACK_LEN - pre_roll, 10 * CALLBACK_LEN + pre_roll)
+        );
+        drain.finish();
+    }
+}
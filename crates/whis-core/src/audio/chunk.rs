@@ -0,0 +1,189 @@
+//! Splitting long recordings into chunks at natural pauses.
+//!
+//! Cutting at a fixed interval slices words in half, so each boundary is
+//! moved to the quietest stretch near the target instead. When that stretch
+//! is silent the chunks don't need to overlap; otherwise (continuous speech
+//! or music) the next chunk starts a little earlier so no word is lost, and
+//! the transcripts are de-duplicated when merged.
+
+use std::ops::Range;
+
+use super::vad::{self, FRAME_MS};
+
+/// Overlap between chunks in seconds when no pause was found near a boundary
+pub(crate) const CHUNK_OVERLAP_SECS: usize = 2;
+/// How far from the target boundary to look for a pause, in seconds
+const SPLIT_SEARCH_SECS: usize = 10;
+/// Length of the quiet stretch compared when picking a split point
+const SPLIT_QUIET_MS: usize = 100;
+
+/// Samples belonging to one chunk
+pub(crate) struct ChunkSpan {
+    /// Range of interleaved samples
+    pub range: Range<usize>,
+    /// Whether the start repeats the end of the previous chunk
+    pub has_leading_overlap: bool,
+}
+
+/// Shortest chunk duration that still leaves room for overlap
+pub(crate) const MIN_CHUNK_SECS: usize = CHUNK_OVERLAP_SECS * 5;
+
+/// End of a chunk and start of the next one, in [`FRAME_MS`] frames
+pub(crate) struct Boundary {
+    /// First frame after the chunk
+    pub end: usize,
+    /// First frame of the next chunk (before `end` when overlapping)
+    pub next_start: usize,
+    /// Whether no pause was found, so the next chunk overlaps this one
+    pub overlap: bool,
+}
+
+/// Find where the chunk starting at the first frame should end
+///
+/// `levels` are frame levels from [`vad::frame_levels`]. Returns None when
+/// the audio fits in a single chunk of `chunk_secs`.
+pub(crate) fn next_boundary(levels: &[f32], chunk_secs: usize, threshold: f32) -> Option<Boundary> {
+    let chunk_frames = chunk_secs.max(MIN_CHUNK_SECS) * 1000 / FRAME_MS;
+    if levels.len() <= chunk_frames {
+        return None;
+    }
+
+    let window = (SPLIT_SEARCH_SECS * 1000 / FRAME_MS).min(chunk_frames / 10);
+    let quiet_frames = SPLIT_QUIET_MS / FRAME_MS;
+
+    // Mean level of the quiet stretch centred on a frame boundary
+    let stretch_level = |frame: usize| {
+        let stretch = &levels[frame.saturating_sub(quiet_frames / 2)
+            ..(frame + quiet_frames.div_ceil(2)).min(levels.len())];
+        stretch.iter().sum::<f32>() / stretch.len().max(1) as f32
+    };
+
+    // Search around a target early enough that the chunk never exceeds chunk_secs
+    let target = chunk_frames - window;
+    let (end, level) = (target - window..=target + window)
+        .map(|frame| (frame, stretch_level(frame)))
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .expect("search window is never empty");
+
+    if level < threshold {
+        crate::verbose!("Chunk boundary in a pause ({level:.1} dBFS)");
+        Some(Boundary {
+            end,
+            next_start: end,
+            overlap: false,
+        })
+    } else {
+        crate::verbose!("No pause near chunk boundary, overlapping");
+        Some(Boundary {
+            end,
+            next_start: end - CHUNK_OVERLAP_SECS * 1000 / FRAME_MS,
+            overlap: true,
+        })
+    }
+}
+
+/// Plan chunk boundaries for a recording
+///
+/// Chunks are at most `chunk_secs` long. `threshold_db` is the level below
+/// which audio counts as silence (None = adapt to the recording's noise floor).
+pub(crate) fn plan_chunks(
+    samples: &[f32],
+    sample_rate: u32,
+    channels: u16,
+    chunk_secs: usize,
+    threshold_db: Option<f32>,
+) -> Vec<ChunkSpan> {
+    let (frame_len, levels) = vad::frame_levels(samples, sample_rate, channels);
+    let threshold = threshold_db.unwrap_or_else(|| vad::adaptive_threshold(&levels));
+
+    let mut spans = Vec::new();
+    let mut start = 0;
+    let mut has_leading_overlap = false;
+
+    while let Some(boundary) = next_boundary(&levels[start..], chunk_secs, threshold) {
+        spans.push(ChunkSpan {
+            range: start * frame_len..(start + boundary.end) * frame_len,
+            has_leading_overlap,
+        });
+        start += boundary.next_start;
+        has_leading_overlap = boundary.overlap;
+    }
+
+    spans.push(ChunkSpan {
+        range: start * frame_len..samples.len(),
+        has_leading_overlap,
+    });
+
+    spans
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RATE: u32 = 16_000;
+
+    fn samples(ms: usize) -> usize {
+        ms * RATE as usize / 1000
+    }
+
+    /// Sine tone at about -20 dBFS
+    fn tone(ms: usize) -> Vec<f32> {
+        (0..samples(ms))
+            .map(|i| (i as f32 * 2.0 * std::f32::consts::PI * 220.0 / RATE as f32).sin() * 0.14)
+            .collect()
+    }
+
+    #[test]
+    fn cuts_in_a_pause_near_the_boundary() {
+        // The search window for 10 second chunks covers 8 to 10 seconds
+        let audio = [tone(8500), vec![0.0; samples(300)], tone(6200)].concat();
+        let spans = plan_chunks(&audio, RATE, 1, 10, Some(-40.0));
+
+        assert_eq!(spans.len(), 2);
+        let cut = spans[0].range.end;
+        assert!(
+            (samples(8500)..=samples(8800)).contains(&cut),
+            "cut at {cut}"
+        );
+        assert_eq!(spans[0].range.start, 0);
+        assert_eq!(spans[1].range, cut..audio.len());
+        assert!(!spans[0].has_leading_overlap);
+        assert!(!spans[1].has_leading_overlap);
+    }
+
+    #[test]
+    fn overlaps_when_there_is_no_pause() {
+        let audio = tone(25_000);
+        let spans = plan_chunks(&audio, RATE, 1, 10, Some(-40.0));
+
+        assert!(spans.len() > 2);
+        assert_eq!(spans[0].range.start, 0);
+        assert_eq!(spans.last().unwrap().range.end, audio.len());
+        assert!(!spans[0].has_leading_overlap);
+        for pair in spans.windows(2) {
+            assert!(pair[0].range.len() <= samples(10_000));
+            assert!(pair[0].range.len() >= samples(8000));
+            assert_eq!(
+                pair[1].range.start,
+                pair[0].range.end - samples(CHUNK_OVERLAP_SECS * 1000)
+            );
+            assert!(pair[1].has_leading_overlap);
+        }
+    }
+
+    #[test]
+    fn keeps_a_short_recording_or_tail_whole() {
+        let audio = tone(10_000);
+        let spans = plan_chunks(&audio, RATE, 1, 10, Some(-40.0));
+        assert_eq!(spans.len(), 1);
+        assert_eq!(spans[0].range, 0..audio.len());
+        assert!(!spans[0].has_leading_overlap);
+
+        // A tail far shorter than a chunk follows the last cut as it is
+        let audio = [tone(8500), vec![0.0; samples(300)], tone(1500)].concat();
+        let spans = plan_chunks(&audio, RATE, 1, 10, Some(-40.0));
+        assert_eq!(spans.len(), 2);
+        assert_eq!(spans[1].range.end, audio.len());
+    }
+}
@@ -0,0 +1,327 @@
+//! Decoding existing audio and video files into recordings.
+//!
+//! Files are decoded with symphonia (WAV, MP3, AAC in M4A/MP4, Vorbis in
+//! OGG, FLAC). Anything it can't read, such as Opus, is handed to FFmpeg
+//! when it is installed. Decoded samples spill to disk above the memory
+//! limit, like a long live recording, so hour-long files stay cheap.
+
+use anyhow::{Context, Result};
+use std::fs::File;
+use std::io::{Cursor, Read, Write};
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::sync::Arc;
+use symphonia::core::audio::SampleBuffer;
+use symphonia::core::codecs::{CODEC_TYPE_NULL, DecoderOptions};
+use symphonia::core::errors::Error as DecodeError;
+use symphonia::core::formats::FormatOptions;
+use symphonia::core::io::{MediaSource, MediaSourceStream};
+use symphonia::core::meta::MetadataOptions;
+use symphonia::core::probe::Hint;
+
+use super::encoder::spawn_ffmpeg;
+use super::spill::{self, SpillStore};
+use super::{RecordingData, UPLOAD_SAMPLE_RATE};
+use crate::error::WhisError;
+
+impl RecordingData {
+    /// Decode an audio or video file
+    ///
+    /// Decoded audio beyond `memory_limit` bytes is spilled to disk.
+    pub fn from_file(path: &Path, memory_limit: Option<usize>) -> Result<Self> {
+        let file =
+            File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+        let mut hint = Hint::new();
+        if let Some(extension) = path.extension().and_then(|e| e.to_str()) {
+            hint.with_extension(extension);
+        }
+
+        decode(Box::new(file), &hint, FfmpegInput::File(path), memory_limit)
+            .with_context(|| format!("Failed to decode {}", path.display()))
+    }
+
+    /// Decode an audio file held in memory, such as one read from stdin
+    pub fn from_bytes(data: Vec<u8>, memory_limit: Option<usize>) -> Result<Self> {
+        let data: Arc<[u8]> = data.into();
+
+        let source = Box::new(Cursor::new(data.clone()));
+        decode(source, &Hint::new(), FfmpegInput::Bytes(data), memory_limit)
+            .context("Failed to decode audio")
+    }
+}
+
+/// Collects decoded samples, spilling them to disk above the memory limit
+struct Sink {
+    samples: Vec<f32>,
+    spilled: Option<SpillStore>,
+    memory_limit: Option<usize>,
+}
+
+impl Sink {
+    fn new(memory_limit: Option<usize>) -> Self {
+        Self {
+            samples: Vec::new(),
+            spilled: None,
+            memory_limit: memory_limit.map(|bytes| (bytes / std::mem::size_of::<f32>()).max(1)),
+        }
+    }
+
+    fn push(&mut self, samples: &[f32]) -> Result<()> {
+        self.samples.extend_from_slice(samples);
+        if let Some(limit) = self.memory_limit
+            && self.samples.len() >= limit
+        {
+            let store = match &mut self.spilled {
+                Some(store) => store,
+                None => self
+                    .spilled
+                    .insert(SpillStore::create(&spill::default_root())?),
+            };
+            store.write(&self.samples)?;
+            self.samples.clear();
+        }
+        Ok(())
+    }
+
+    fn finish(self, sample_rate: u32, channels: u16) -> Result<RecordingData> {
+        let len = self.samples.len() + self.spilled.as_ref().map_or(0, SpillStore::len);
+        if len == 0 {
+            return Err(WhisError::EmptyAudio.into());
+        }
+        crate::verbose!(
+            "Decoded {:.1}s of audio: {sample_rate} Hz, {channels} channel(s)",
+            len as f64 / sample_rate as f64 / channels as f64
+        );
+
+        Ok(RecordingData {
+            samples: self.samples,
+            spilled: self.spilled,
+            sample_rate,
+            channels,
+        })
+    }
+}
+
+/// Decode with symphonia, falling back to FFmpeg for formats it can't read
+fn decode(
+    source: Box<dyn MediaSource>,
+    hint: &Hint,
+    fallback: FfmpegInput,
+    memory_limit: Option<usize>,
+) -> Result<RecordingData> {
+    decode_symphonia(source, hint, memory_limit).or_else(|e| {
+        crate::verbose!("Built-in decoder failed ({e:#}), trying FFmpeg");
+        decode_ffmpeg(fallback, memory_limit).map_err(|ffmpeg| {
+            crate::verbose!("FFmpeg fallback failed: {ffmpeg:#}");
+            e
+        })
+    })
+}
+
+/// Decode the first audio track with symphonia
+fn decode_symphonia(
+    source: Box<dyn MediaSource>,
+    hint: &Hint,
+    memory_limit: Option<usize>,
+) -> Result<RecordingData> {
+    let stream = MediaSourceStream::new(source, Default::default());
+    let probed = symphonia::default::get_probe()
+        .format(
+            hint,
+            stream,
+            &FormatOptions::default(),
+            &MetadataOptions::default(),
+        )
+        .context("Unrecognized audio format")?;
+    let mut format = probed.format;
+
+    // Video files have other tracks too; take the first one with audio
+    let track = format
+        .tracks()
+        .iter()
+        .find(|track| {
+            track.codec_params.codec != CODEC_TYPE_NULL && track.codec_params.sample_rate.is_some()
+        })
+        .context("No audio track found")?;
+    let track_id = track.id;
+    let mut decoder = symphonia::default::get_codecs()
+        .make(&track.codec_params, &DecoderOptions::default())
+        .context("Unsupported audio codec")?;
+
+    let mut sink = Sink::new(memory_limit);
+    let mut spec = None;
+    let mut buffer: Option<SampleBuffer<f32>> = None;
+
+    loop {
+        let packet = match format.next_packet() {
+            Ok(packet) => packet,
+            Err(DecodeError::IoError(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof => break,
+            Err(e) => return Err(e).context("Failed to read audio"),
+        };
+        if packet.track_id() != track_id {
+            continue;
+        }
+
+        let decoded = match decoder.decode(&packet) {
+            Ok(decoded) => decoded,
+            // A corrupt packet costs a few milliseconds, not the whole file
+            Err(DecodeError::DecodeError(e)) => {
+                crate::verbose!("Skipping undecodable packet: {e}");
+                continue;
+            }
+            Err(e) => return Err(e).context("Failed to decode audio"),
+        };
+
+        // Samples of another rate or layout can't be appended to the ones so
+        // far; FFmpeg converts the whole file to one instead
+        let packet_spec = *decoded.spec();
+        let first = *spec.get_or_insert(packet_spec);
+        anyhow::ensure!(
+            packet_spec == first,
+            "Audio format changes mid-stream ({} Hz, {} channel(s) to {} Hz, {} channel(s))",
+            first.rate,
+            first.channels.count(),
+            packet_spec.rate,
+            packet_spec.channels.count()
+        );
+
+        // The buffer holds interleaved samples, the decoded packet counts frames
+        let needed = decoded.capacity() * first.channels.count();
+        let samples = match &mut buffer {
+            Some(buffer) if buffer.capacity() >= needed => buffer,
+            _ => buffer.insert(SampleBuffer::new(decoded.capacity() as u64, first)),
+        };
+        samples.copy_interleaved_ref(decoded);
+        sink.push(samples.samples())?;
+    }
+
+    let spec = spec.ok_or(WhisError::EmptyAudio)?;
+    sink.finish(spec.rate, spec.channels.count() as u16)
+}
+
+enum FfmpegInput<'a> {
+    File(&'a Path),
+    Bytes(Arc<[u8]>),
+}
+
+/// Decode with an `ffmpeg` process, straight to 16 kHz mono as uploaded by default
+fn decode_ffmpeg(input: FfmpegInput, memory_limit: Option<usize>) -> Result<RecordingData> {
+    let rate = UPLOAD_SAMPLE_RATE.to_string();
+    let mut command = Command::new("ffmpeg");
+    command.args(["-hide_banner", "-loglevel", "error", "-i"]);
+    match &input {
+        FfmpegInput::File(path) => command.arg(path),
+        FfmpegInput::Bytes(_) => command.arg("pipe:0"),
+    };
+    command.args(["-vn", "-f", "f32le", "-ac", "1", "-ar", &rate, "pipe:1"]);
+    crate::verbose!("FFmpeg command: {command:?}");
+
+    let mut child = spawn_ffmpeg(
+        command
+            .stdin(match input {
+                FfmpegInput::File(_) => Stdio::null(),
+                FfmpegInput::Bytes(_) => Stdio::piped(),
+            })
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped()),
+    )?;
+
+    // Feed stdin from a separate thread so a full stdout pipe can't deadlock us
+    let writer = match input {
+        FfmpegInput::Bytes(data) => {
+            let mut stdin = child.stdin.take().context("Failed to open ffmpeg stdin")?;
+            Some(std::thread::spawn(move || stdin.write_all(&data)))
+        }
+        FfmpegInput::File(_) => None,
+    };
+
+    let mut stdout = child
+        .stdout
+        .take()
+        .context("Failed to open ffmpeg stdout")?;
+    let mut sink = Sink::new(memory_limit);
+    let mut bytes = vec![0u8; 64 * 1024];
+    let mut filled = 0;
+    loop {
+        let read = stdout
+            .read(&mut bytes[filled..])
+            .context("Failed to read ffmpeg output")?;
+        if read == 0 {
+            break;
+        }
+        filled += read;
+        let whole = filled - filled % 4;
+        let samples: Vec<f32> = bytes[..whole]
+            .chunks_exact(4)
+            .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .collect();
+        sink.push(&samples)?;
+        bytes.copy_within(whole..filled, 0);
+        filled -= whole;
+    }
+
+    let output = child
+        .wait_with_output()
+        .context("Failed to wait for ffmpeg")?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("FFmpeg decoding failed: {stderr}");
+    }
+    // ffmpeg stops reading once it has found the audio, so a broken pipe is fine
+    if let Some(writer) = writer
+        && writer.join().is_err()
+    {
+        anyhow::bail!("FFmpeg writer thread panicked");
+    }
+
+    sink.finish(UPLOAD_SAMPLE_RATE, 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wav(sample_rate: u32, channels: u16, frames: usize) -> Vec<u8> {
+        let spec = hound::WavSpec {
+            channels,
+            sample_rate,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut cursor = Cursor::new(Vec::new());
+        let mut writer = hound::WavWriter::new(&mut cursor, spec).unwrap();
+        for i in 0..frames * channels as usize {
+            writer.write_sample((i % 100) as i16 * 100).unwrap();
+        }
+        writer.finalize().unwrap();
+        cursor.into_inner()
+    }
+
+    #[test]
+    fn decodes_wav_bytes() {
+        let recording = RecordingData::from_bytes(wav(22_050, 2, 22_050), None).unwrap();
+        assert_eq!(recording.sample_rate(), 22_050);
+        assert_eq!(recording.channels(), 2);
+        assert_eq!(recording.samples().len(), 44_100);
+        assert!((recording.samples()[1] - 100.0 / 32_768.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn spills_above_memory_limit() {
+        let recording = RecordingData::from_bytes(wav(16_000, 1, 48_000), Some(40_000)).unwrap();
+        assert!(recording.is_spilled());
+        let spilled = recording.spilled.as_ref().map_or(0, SpillStore::len);
+        assert_eq!(spilled + recording.samples().len(), 48_000);
+    }
+
+    #[test]
+    fn empty_file_is_empty_audio() {
+        let Err(error) = RecordingData::from_bytes(wav(16_000, 1, 0), None) else {
+            panic!("decoded an empty file");
+        };
+        assert!(matches!(
+            WhisError::find(&error),
+            Some(WhisError::EmptyAudio)
+        ));
+    }
+}
@@ -0,0 +1,291 @@
+//! Input device discovery and selection.
+
+use anyhow::{Context, Result};
+use cpal::traits::{DeviceTrait, HostTrait};
+use serde::{Deserialize, Serialize};
+
+use crate::error::WhisError;
+
+/// What to do when the configured input device can't be found
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DeviceFallback {
+    /// Record from the system default device and report a warning
+    #[default]
+    Default,
+    /// Refuse to record
+    Error,
+}
+
+impl std::fmt::Display for DeviceFallback {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DeviceFallback::Default => write!(f, "default"),
+            DeviceFallback::Error => write!(f, "error"),
+        }
+    }
+}
+
+impl std::str::FromStr for DeviceFallback {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "default" => Ok(DeviceFallback::Default),
+            "error" => Ok(DeviceFallback::Error),
+            _ => Err(format!(
+                "Unknown device fallback: {s}. Use 'default' or 'error'"
+            )),
+        }
+    }
+}
+
+/// A stream configuration range supported by an input device
+#[derive(Debug, Clone, Serialize)]
+pub struct SupportedInputConfig {
+    pub channels: u16,
+    pub min_sample_rate: u32,
+    pub max_sample_rate: u32,
+    /// Sample format name (e.g., "f32", "i16")
+    pub sample_format: String,
+}
+
+/// Description of an available input device
+#[derive(Debug, Clone, Serialize)]
+pub struct InputDeviceInfo {
+    /// Position in the host's device list (usable as a selector)
+    pub index: usize,
+    pub name: String,
+    /// Whether this is the system default input device
+    pub is_default: bool,
+    /// Configuration used when recording from this device
+    pub default_config: Option<SupportedInputConfig>,
+    /// All supported configuration ranges
+    pub configs: Vec<SupportedInputConfig>,
+}
+
+/// Input device selected for recording
+pub(crate) struct SelectedDevice {
+    pub device: cpal::Device,
+    pub name: String,
+    /// Set when the configured device was missing and the default was used instead
+    pub fallback_warning: Option<String>,
+}
+
+fn device_name(device: &cpal::Device) -> String {
+    device.name().unwrap_or_else(|_| "<unknown>".to_string())
+}
+
+/// List input devices of the default audio host
+pub fn list_input_devices() -> Result<Vec<InputDeviceInfo>> {
+    let host = cpal::default_host();
+    let default_name = host.default_input_device().map(|d| device_name(&d));
+
+    let devices = host
+        .input_devices()
+        .context("Failed to enumerate input devices")?;
+
+    Ok(devices
+        .enumerate()
+        .map(|(index, device)| {
+            let name = device_name(&device);
+            let default_config =
+                device
+                    .default_input_config()
+                    .ok()
+                    .map(|config| SupportedInputConfig {
+                        channels: config.channels(),
+                        min_sample_rate: config.sample_rate().0,
+                        max_sample_rate: config.sample_rate().0,
+                        sample_format: config.sample_format().to_string(),
+                    });
+            let configs = device
+                .supported_input_configs()
+                .map(|configs| {
+                    configs
+                        .map(|range| SupportedInputConfig {
+                            channels: range.channels(),
+                            min_sample_rate: range.min_sample_rate().0,
+                            max_sample_rate: range.max_sample_rate().0,
+                            sample_format: range.sample_format().to_string(),
+                        })
+                        .collect()
+                })
+                .unwrap_or_default();
+
+            InputDeviceInfo {
+                index,
+                is_default: default_name.as_deref() == Some(name.as_str()),
+                name,
+                default_config,
+                configs,
+            }
+        })
+        .collect())
+}
+
+/// Find a device by name
+///
+/// Indices aren't accepted here: they shift when devices are plugged in or
+/// out, so [`resolve_input_device`] turns them into names when configured.
+fn find_device(host: &cpal::Host, selector: &str) -> Result<Option<cpal::Device>> {
+    let devices: Vec<cpal::Device> = host
+        .input_devices()
+        .context("Failed to enumerate input devices")?
+        .collect();
+    let names: Vec<String> = devices.iter().map(device_name).collect();
+    Ok(match_device(&names, selector)?.and_then(|pos| devices.into_iter().nth(pos)))
+}
+
+/// Position of the device named by `selector`: an exact name, or else a
+/// case-insensitive substring that matches exactly one device
+fn match_device(names: &[String], selector: &str) -> Result<Option<usize>> {
+    if let Some(pos) = names.iter().position(|name| name == selector) {
+        return Ok(Some(pos));
+    }
+
+    let needle = selector.to_lowercase();
+    let matches: Vec<usize> = names
+        .iter()
+        .enumerate()
+        .filter(|(_, name)| name.to_lowercase().contains(&needle))
+        .map(|(i, _)| i)
+        .collect();
+    match matches.as_slice() {
+        [pos] => Ok(Some(*pos)),
+        [] => Ok(None),
+        _ => anyhow::bail!(
+            "Input device '{selector}' is ambiguous, it matches: {}",
+            matches
+                .iter()
+                .map(|&i| names[i].as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    }
+}
+
+/// Name of the connected device that an index or name from `whis devices`
+/// refers to, to be stored as the configured device
+///
+/// Returns None when no connected device has that name, and fails for an
+/// index past the end of the list.
+pub fn resolve_input_device(selector: &str) -> Result<Option<String>> {
+    let names: Vec<String> = list_input_devices()?
+        .into_iter()
+        .map(|device| device.name)
+        .collect();
+    resolve_selector(&names, selector)
+}
+
+fn resolve_selector(names: &[String], selector: &str) -> Result<Option<String>> {
+    // A device may be named like a number, so names win over indices
+    if names.iter().any(|name| name == selector) {
+        return Ok(Some(selector.to_string()));
+    }
+    if let Ok(index) = selector.parse::<usize>() {
+        return match names.get(index) {
+            Some(name) => Ok(Some(name.clone())),
+            None => Err(WhisError::NoInputDevice {
+                requested: Some(selector.to_string()),
+            }
+            .into()),
+        };
+    }
+    Ok(match_device(names, selector)?.map(|pos| names[pos].clone()))
+}
+
+/// Resolve the device to record from
+///
+/// `selector` is a device name; None means the system default.
+pub(crate) fn select_input_device(
+    host: &cpal::Host,
+    selector: Option<&str>,
+    fallback: DeviceFallback,
+) -> Result<SelectedDevice> {
+    let default_device = || {
+        host.default_input_device()
+            .ok_or(WhisError::NoInputDevice { requested: None })
+    };
+
+    let Some(selector) = selector.map(str::trim).filter(|s| !s.is_empty()) else {
+        let device = default_device()?;
+        return Ok(SelectedDevice {
+            name: device_name(&device),
+            device,
+            fallback_warning: None,
+        });
+    };
+
+    if let Some(device) = find_device(host, selector)? {
+        return Ok(SelectedDevice {
+            name: device_name(&device),
+            device,
+            fallback_warning: None,
+        });
+    }
+
+    match fallback {
+        DeviceFallback::Error => Err(WhisError::NoInputDevice {
+            requested: Some(selector.to_string()),
+        }
+        .into()),
+        DeviceFallback::Default => {
+            let device = default_device()?;
+            let name = device_name(&device);
+            let warning = format!(
+                "Input device '{selector}' not found, recording from default device '{name}'"
+            );
+            crate::verbose!("{warning}");
+            Ok(SelectedDevice {
+                device,
+                name,
+                fallback_warning: Some(warning),
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names() -> Vec<String> {
+        [
+            "default",
+            "USB Audio Device",
+            "USB Headset",
+            "HD Webcam",
+            "2",
+        ]
+        .map(String::from)
+        .to_vec()
+    }
+
+    #[test]
+    fn matches_devices_by_name_only() {
+        let names = names();
+        assert_eq!(match_device(&names, "HD Webcam").unwrap(), Some(3));
+        assert_eq!(match_device(&names, "webcam").unwrap(), Some(3));
+        assert!(match_device(&names, "usb").is_err());
+        // A number is a name like any other, never a position
+        assert_eq!(match_device(&names, "3").unwrap(), None);
+        assert_eq!(match_device(&names, "2").unwrap(), Some(4));
+    }
+
+    #[test]
+    fn resolves_indices_to_names_when_configured() {
+        let names = names();
+        assert_eq!(
+            resolve_selector(&names, "1").unwrap().as_deref(),
+            Some("USB Audio Device")
+        );
+        assert_eq!(resolve_selector(&names, "2").unwrap().as_deref(), Some("2"));
+        assert!(resolve_selector(&names, "9").is_err());
+        assert_eq!(
+            resolve_selector(&names, "headset").unwrap().as_deref(),
+            Some("USB Headset")
+        );
+        assert_eq!(resolve_selector(&names, "Bluetooth").unwrap(), None);
+    }
+}
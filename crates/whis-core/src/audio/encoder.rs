@@ -0,0 +1,473 @@
+//! In-process audio encoders.
+//!
+//! Recordings are encoded in memory without touching the temp directory.
+//! FLAC and WAV are always available, Opus/OGG is built with the `opus`
+//! feature, and FFmpeg is only used as a fallback for formats that have no
+//! native encoder in this build (MP3, or Opus without the feature).
+
+use anyhow::{Context, Result};
+use bytes::Bytes;
+use serde::{Deserialize, Serialize};
+use std::io::{Cursor, Read, Write};
+use std::process::{Child, Command, Stdio};
+
+use super::flac;
+use crate::error::WhisError;
+
+/// Container/codec used for audio sent to transcription providers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AudioFormat {
+    /// Lossless FLAC (pure Rust, default)
+    #[default]
+    Flac,
+    /// Uncompressed 16-bit PCM WAV
+    Wav,
+    /// Opus in an OGG container
+    Opus,
+    /// MP3 (requires FFmpeg)
+    Mp3,
+}
+
+impl AudioFormat {
+    /// File extension used when uploading (without the dot)
+    pub fn extension(&self) -> &'static str {
+        match self {
+            AudioFormat::Flac => "flac",
+            AudioFormat::Wav => "wav",
+            AudioFormat::Opus => "ogg",
+            AudioFormat::Mp3 => "mp3",
+        }
+    }
+
+    /// MIME type used when uploading
+    pub fn mime_type(&self) -> &'static str {
+        match self {
+            AudioFormat::Flac => "audio/flac",
+            AudioFormat::Wav => "audio/wav",
+            AudioFormat::Opus => "audio/ogg",
+            AudioFormat::Mp3 => "audio/mpeg",
+        }
+    }
+
+    /// Whether encoding this format in the current build shells out to FFmpeg
+    pub fn requires_ffmpeg(&self) -> bool {
+        match self {
+            AudioFormat::Flac | AudioFormat::Wav => false,
+            AudioFormat::Opus => !cfg!(feature = "opus"),
+            AudioFormat::Mp3 => true,
+        }
+    }
+}
+
+impl std::fmt::Display for AudioFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AudioFormat::Flac => write!(f, "flac"),
+            AudioFormat::Wav => write!(f, "wav"),
+            AudioFormat::Opus => write!(f, "opus"),
+            AudioFormat::Mp3 => write!(f, "mp3"),
+        }
+    }
+}
+
+impl std::str::FromStr for AudioFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "flac" => Ok(AudioFormat::Flac),
+            "wav" => Ok(AudioFormat::Wav),
+            "opus" | "ogg" => Ok(AudioFormat::Opus),
+            "mp3" => Ok(AudioFormat::Mp3),
+            _ => Err(format!(
+                "Unknown audio format: {s}. Use 'flac', 'wav', 'opus', or 'mp3'"
+            )),
+        }
+    }
+}
+
+/// Encoded audio together with its format
+///
+/// The data is reference-counted, so clones (such as for retrying a request)
+/// share it.
+#[derive(Debug, Clone)]
+pub struct EncodedAudio {
+    pub data: Bytes,
+    pub format: AudioFormat,
+}
+
+impl EncodedAudio {
+    pub fn new(data: impl Into<Bytes>, format: AudioFormat) -> Self {
+        Self {
+            data: data.into(),
+            format,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+}
+
+/// Encodes interleaved f32 samples into a compressed container
+pub trait AudioEncoder: Send + Sync {
+    /// Format produced by this encoder
+    fn format(&self) -> AudioFormat;
+
+    /// Encode interleaved samples (range -1.0..=1.0)
+    fn encode(&self, samples: &[f32], sample_rate: u32, channels: u16) -> Result<Vec<u8>>;
+}
+
+/// Get the best available encoder for a format
+pub fn encoder_for(format: AudioFormat) -> Box<dyn AudioEncoder> {
+    match format {
+        AudioFormat::Flac => Box::new(FlacEncoder),
+        AudioFormat::Wav => Box::new(WavEncoder),
+        #[cfg(feature = "opus")]
+        AudioFormat::Opus => Box::new(OpusEncoder),
+        #[cfg(not(feature = "opus"))]
+        AudioFormat::Opus => Box::new(FfmpegEncoder::new(AudioFormat::Opus)),
+        AudioFormat::Mp3 => Box::new(FfmpegEncoder::new(AudioFormat::Mp3)),
+    }
+}
+
+/// Start an `ffmpeg` command, reporting a missing binary as [`WhisError::EncoderMissing`]
+pub(crate) fn spawn_ffmpeg(command: &mut Command) -> Result<Child> {
+    command.spawn().map_err(|e| match e.kind() {
+        std::io::ErrorKind::NotFound => WhisError::EncoderMissing { program: "ffmpeg" }.into(),
+        _ => anyhow::Error::new(e).context("Failed to execute ffmpeg"),
+    })
+}
+
+/// Convert f32 samples to 16-bit PCM
+pub(crate) fn to_i16(samples: &[f32]) -> Vec<i16> {
+    samples
+        .iter()
+        .map(|&s| {
+            let clamped = s.clamp(-1.0, 1.0);
+            (clamped * i16::MAX as f32) as i16
+        })
+        .collect()
+}
+
+/// Lossless FLAC encoder (pure Rust)
+pub struct FlacEncoder;
+
+impl AudioEncoder for FlacEncoder {
+    fn format(&self) -> AudioFormat {
+        AudioFormat::Flac
+    }
+
+    fn encode(&self, samples: &[f32], sample_rate: u32, channels: u16) -> Result<Vec<u8>> {
+        if channels == 0 || channels > 8 {
+            anyhow::bail!("FLAC supports 1 to 8 channels, got {channels}");
+        }
+        Ok(flac::encode(&to_i16(samples), sample_rate, channels))
+    }
+}
+
+/// 16-bit PCM WAV encoder
+pub struct WavEncoder;
+
+impl AudioEncoder for WavEncoder {
+    fn format(&self) -> AudioFormat {
+        AudioFormat::Wav
+    }
+
+    fn encode(&self, samples: &[f32], sample_rate: u32, channels: u16) -> Result<Vec<u8>> {
+        let spec = hound::WavSpec {
+            channels,
+            sample_rate,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+
+        let mut cursor = Cursor::new(Vec::with_capacity(samples.len() * 2 + 44));
+        {
+            let mut writer = hound::WavWriter::new(&mut cursor, spec)?;
+            for sample in to_i16(samples) {
+                writer.write_sample(sample)?;
+            }
+            writer.finalize()?;
+        }
+
+        Ok(cursor.into_inner())
+    }
+}
+
+/// Opus-in-OGG encoder (libopus via audiopus)
+#[cfg(feature = "opus")]
+pub struct OpusEncoder;
+
+#[cfg(feature = "opus")]
+impl OpusEncoder {
+    /// Opus always uses 48 kHz granule positions
+    const GRANULE_RATE: u32 = 48_000;
+    /// Frame duration in milliseconds
+    const FRAME_MS: u32 = 20;
+    /// Target bitrate (plenty for speech)
+    const BITRATE: i32 = 32_000;
+
+    /// Lowest sample rate supported by libopus that keeps the full input bandwidth
+    fn opus_rate(sample_rate: u32) -> audiopus::SampleRate {
+        use audiopus::SampleRate;
+        match sample_rate {
+            0..=8_000 => SampleRate::Hz8000,
+            8_001..=12_000 => SampleRate::Hz12000,
+            12_001..=16_000 => SampleRate::Hz16000,
+            16_001..=24_000 => SampleRate::Hz24000,
+            _ => SampleRate::Hz48000,
+        }
+    }
+
+    fn head_packet(channels: u8, pre_skip: u16, input_rate: u32) -> Vec<u8> {
+        let mut head = Vec::with_capacity(19);
+        head.extend_from_slice(b"OpusHead");
+        head.push(1); // version
+        head.push(channels);
+        head.extend_from_slice(&pre_skip.to_le_bytes());
+        head.extend_from_slice(&input_rate.to_le_bytes());
+        head.extend_from_slice(&0i16.to_le_bytes()); // output gain
+        head.push(0); // channel mapping family (mono/stereo)
+        head
+    }
+
+    fn tags_packet() -> Vec<u8> {
+        let vendor = concat!("whis ", env!("CARGO_PKG_VERSION"));
+        let mut tags = Vec::with_capacity(16 + vendor.len());
+        tags.extend_from_slice(b"OpusTags");
+        tags.extend_from_slice(&(vendor.len() as u32).to_le_bytes());
+        tags.extend_from_slice(vendor.as_bytes());
+        tags.extend_from_slice(&0u32.to_le_bytes()); // no user comments
+        tags
+    }
+}
+
+#[cfg(feature = "opus")]
+impl AudioEncoder for OpusEncoder {
+    fn format(&self) -> AudioFormat {
+        AudioFormat::Opus
+    }
+
+    fn encode(&self, samples: &[f32], sample_rate: u32, channels: u16) -> Result<Vec<u8>> {
+        use audiopus::coder::Encoder;
+        use audiopus::{Application, Bitrate, Channels};
+        use ogg::writing::{PacketWriteEndInfo, PacketWriter};
+
+        // Opus only supports mono and stereo here; downmix anything wider
+        let (samples, channels) = if channels > 2 {
+            let mono: Vec<f32> = samples
+                .chunks(channels as usize)
+                .map(|frame| frame.iter().sum::<f32>() / frame.len() as f32)
+                .collect();
+            (mono, 1u16)
+        } else {
+            (samples.to_vec(), channels.max(1))
+        };
+        let ch = channels as usize;
+
+        let rate = Self::opus_rate(sample_rate);
+        let rate_hz = rate as i32 as u32;
+        let samples = super::resample(&samples, channels, sample_rate, rate_hz)?;
+
+        let opus_channels = if ch == 2 {
+            Channels::Stereo
+        } else {
+            Channels::Mono
+        };
+        let mut encoder = Encoder::new(rate, opus_channels, Application::Voip)
+            .context("Failed to create Opus encoder")?;
+        encoder
+            .set_bitrate(Bitrate::BitsPerSecond(Self::BITRATE))
+            .context("Failed to set Opus bitrate")?;
+
+        let granule_scale = (Self::GRANULE_RATE / rate_hz) as u64;
+        let pre_skip = encoder.lookahead().unwrap_or(0) as u64 * granule_scale;
+        let frame_len = (rate_hz * Self::FRAME_MS / 1000) as usize * ch;
+
+        let serial = 0x7768_6973; // "whis"
+        let mut writer = PacketWriter::new(Vec::new());
+        writer.write_packet(
+            Self::head_packet(ch as u8, pre_skip as u16, sample_rate).into_boxed_slice(),
+            serial,
+            PacketWriteEndInfo::EndPage,
+            0,
+        )?;
+        writer.write_packet(
+            Self::tags_packet().into_boxed_slice(),
+            serial,
+            PacketWriteEndInfo::EndPage,
+            0,
+        )?;
+
+        let total_frames = samples.len().div_ceil(frame_len).max(1);
+        let mut frame = vec![0f32; frame_len];
+        let mut packet = vec![0u8; 4000];
+        let mut granule = pre_skip;
+        for i in 0..total_frames {
+            let start = i * frame_len;
+            let end = (start + frame_len).min(samples.len());
+            frame.fill(0.0);
+            frame[..end.saturating_sub(start)].copy_from_slice(&samples[start.min(end)..end]);
+
+            let len = encoder
+                .encode_float(&frame, &mut packet)
+                .context("Opus encoding failed")?;
+
+            let is_last = i + 1 == total_frames;
+            granule += if is_last {
+                ((end - start) / ch) as u64 * granule_scale
+            } else {
+                (frame_len / ch) as u64 * granule_scale
+            };
+            let info = if is_last {
+                PacketWriteEndInfo::EndStream
+            } else {
+                PacketWriteEndInfo::NormalPacket
+            };
+            writer.write_packet(
+                packet[..len].to_vec().into_boxed_slice(),
+                serial,
+                info,
+                granule,
+            )?;
+        }
+
+        Ok(writer.into_inner())
+    }
+}
+
+/// Fallback encoder that pipes raw PCM through an `ffmpeg` process
+pub struct FfmpegEncoder {
+    format: AudioFormat,
+}
+
+impl FfmpegEncoder {
+    pub fn new(format: AudioFormat) -> Self {
+        Self { format }
+    }
+
+    fn output_args(&self) -> &'static [&'static str] {
+        match self.format {
+            AudioFormat::Mp3 => &["-codec:a", "libmp3lame", "-b:a", "128k", "-f", "mp3"],
+            AudioFormat::Opus => &["-codec:a", "libopus", "-b:a", "32k", "-f", "ogg"],
+            AudioFormat::Flac => &["-codec:a", "flac", "-f", "flac"],
+            AudioFormat::Wav => &["-codec:a", "pcm_s16le", "-f", "wav"],
+        }
+    }
+}
+
+impl AudioEncoder for FfmpegEncoder {
+    fn format(&self) -> AudioFormat {
+        self.format
+    }
+
+    fn encode(&self, samples: &[f32], sample_rate: u32, channels: u16) -> Result<Vec<u8>> {
+        let rate = sample_rate.to_string();
+        let channel_count = channels.to_string();
+        let mut args = vec![
+            "-hide_banner",
+            "-loglevel",
+            "error",
+            "-f",
+            "s16le",
+            "-ar",
+            &rate,
+            "-ac",
+            &channel_count,
+            "-i",
+            "pipe:0",
+        ];
+        args.extend_from_slice(self.output_args());
+        args.push("pipe:1");
+        crate::verbose!("FFmpeg command: ffmpeg {}", args.join(" "));
+
+        let mut child = spawn_ffmpeg(
+            Command::new("ffmpeg")
+                .args(&args)
+                .stdin(Stdio::piped())
+                .stdout(Stdio::piped())
+                .stderr(Stdio::piped()),
+        )?;
+
+        // Feed stdin from a separate thread so a full stdout pipe can't deadlock us
+        let pcm: Vec<u8> = to_i16(samples)
+            .into_iter()
+            .flat_map(|s| s.to_le_bytes())
+            .collect();
+        let mut stdin = child.stdin.take().context("Failed to open ffmpeg stdin")?;
+        let writer = std::thread::spawn(move || stdin.write_all(&pcm));
+
+        let mut encoded = Vec::new();
+        child
+            .stdout
+            .take()
+            .context("Failed to open ffmpeg stdout")?
+            .read_to_end(&mut encoded)
+            .context("Failed to read ffmpeg output")?;
+
+        let output = child
+            .wait_with_output()
+            .context("Failed to wait for ffmpeg")?;
+        let write_result = writer.join();
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            crate::verbose!("FFmpeg failed: {stderr}");
+            anyhow::bail!("FFmpeg conversion failed: {stderr}");
+        }
+        match write_result {
+            Ok(result) => result.context("Failed to write audio to ffmpeg")?,
+            Err(_) => anyhow::bail!("FFmpeg writer thread panicked"),
+        }
+
+        Ok(encoded)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encodes_wav_with_the_builtin_encoder() {
+        let encoder = encoder_for(AudioFormat::Wav);
+        assert_eq!(encoder.format(), AudioFormat::Wav);
+
+        let samples = [0.0, 0.5, -0.5, 1.0, -1.0, 2.0];
+        let wav = encoder.encode(&samples, 16_000, 2).unwrap();
+        let mut reader = hound::WavReader::new(Cursor::new(wav)).unwrap();
+        assert_eq!(reader.spec().sample_rate, 16_000);
+        assert_eq!(reader.spec().channels, 2);
+        let decoded: Vec<i16> = reader.samples().map(Result::unwrap).collect();
+        assert_eq!(decoded, to_i16(&samples));
+        assert_eq!(decoded[5], i16::MAX);
+    }
+
+    #[test]
+    fn falls_back_to_ffmpeg_without_a_native_encoder() {
+        for format in [
+            AudioFormat::Flac,
+            AudioFormat::Wav,
+            AudioFormat::Opus,
+            AudioFormat::Mp3,
+        ] {
+            assert_eq!(encoder_for(format).format(), format);
+        }
+        assert!(AudioFormat::Mp3.requires_ffmpeg());
+        assert!(!AudioFormat::Flac.requires_ffmpeg());
+        assert_eq!(AudioFormat::Opus.requires_ffmpeg(), !cfg!(feature = "opus"));
+
+        // A missing binary is reported as such, with an install hint
+        let error = spawn_ffmpeg(&mut Command::new("whis-test-missing-ffmpeg")).unwrap_err();
+        assert!(matches!(
+            WhisError::find(&error),
+            Some(WhisError::EncoderMissing { program: "ffmpeg" })
+        ));
+    }
+}
@@ -0,0 +1,499 @@
+//! Minimal pure-Rust FLAC encoder.
+//!
+//! Produces a valid 16-bit FLAC stream using fixed linear predictors and
+//! partitioned Rice coding. It trades the last few percent of compression
+//! (no LPC, no stereo decorrelation) for a small, dependency-free implementation.
+
+/// Samples per channel in each frame
+const BLOCK_SIZE: usize = 4096;
+/// Bits per sample written to the stream
+const BITS_PER_SAMPLE: u32 = 16;
+/// Highest fixed predictor order defined by the format
+const MAX_FIXED_ORDER: usize = 4;
+/// Highest partition order searched for residual coding
+const MAX_PARTITION_ORDER: u32 = 6;
+/// Highest Rice parameter for the 4-bit coding method (15 is the escape code)
+const MAX_RICE_PARAM: u32 = 14;
+
+/// Encode interleaved 16-bit samples as a FLAC stream
+pub fn encode(samples: &[i16], sample_rate: u32, channels: u16) -> Vec<u8> {
+    let channels = channels.clamp(1, 8) as usize;
+    let total_frames = samples.len() / channels;
+
+    let mut out = BitWriter::with_capacity(samples.len() + 64);
+    out.write_bytes(b"fLaC");
+    write_stream_info(&mut out, sample_rate, channels, total_frames);
+
+    let mut channel_buf = vec![Vec::with_capacity(BLOCK_SIZE); channels];
+    for (frame_number, block) in samples.chunks(BLOCK_SIZE * channels).enumerate() {
+        let block_len = block.len() / channels;
+        if block_len == 0 {
+            break;
+        }
+        for (ch, buf) in channel_buf.iter_mut().enumerate() {
+            buf.clear();
+            buf.extend((0..block_len).map(|i| block[i * channels + ch] as i32));
+        }
+        write_frame(&mut out, frame_number as u64, &channel_buf, block_len);
+    }
+
+    out.into_bytes()
+}
+
+/// Write the mandatory STREAMINFO metadata block (marked as the last block)
+fn write_stream_info(out: &mut BitWriter, sample_rate: u32, channels: usize, total_frames: usize) {
+    let block_size = total_frames.clamp(16, BLOCK_SIZE) as u64;
+
+    // Metadata block header: last-block flag, type 0 (STREAMINFO), length 34
+    out.write(1, 1);
+    out.write(0, 7);
+    out.write(34, 24);
+
+    out.write(block_size, 16); // min block size
+    out.write(block_size, 16); // max block size
+    out.write(0, 24); // min frame size (unknown)
+    out.write(0, 24); // max frame size (unknown)
+    out.write(sample_rate as u64, 20);
+    out.write(channels as u64 - 1, 3);
+    out.write(BITS_PER_SAMPLE as u64 - 1, 5);
+    out.write(total_frames as u64, 36);
+    // MD5 signature of the unencoded audio (zero = not computed)
+    out.write(0, 64);
+    out.write(0, 64);
+}
+
+fn write_frame(out: &mut BitWriter, frame_number: u64, channels: &[Vec<i32>], block_len: usize) {
+    let mut frame = BitWriter::with_capacity(block_len * channels.len() * 2);
+
+    // Sync code + reserved bit + fixed-blocksize strategy
+    frame.write(0b1111_1111_1111_1000, 16);
+    let blocksize_code = if block_len == BLOCK_SIZE {
+        0b1100
+    } else {
+        0b0111
+    };
+    frame.write(blocksize_code, 4);
+    frame.write(0b0000, 4); // sample rate: taken from STREAMINFO
+    frame.write(channels.len() as u64 - 1, 4); // independent channels
+    frame.write(0b100, 3); // 16 bits per sample
+    frame.write(0, 1);
+    write_utf8_number(&mut frame, frame_number);
+    if blocksize_code == 0b0111 {
+        frame.write(block_len as u64 - 1, 16);
+    }
+    let crc = crc8(frame.bytes());
+    frame.write(crc as u64, 8);
+
+    for samples in channels {
+        write_subframe(&mut frame, samples);
+    }
+
+    frame.align();
+    let crc = crc16(frame.bytes());
+    frame.write(crc as u64, 16);
+
+    out.write_bytes(frame.bytes());
+}
+
+fn write_subframe(out: &mut BitWriter, samples: &[i32]) {
+    let bps = BITS_PER_SAMPLE;
+
+    if samples.iter().all(|&s| s == samples[0]) {
+        out.write(0b0000_0000, 8); // CONSTANT, no wasted bits
+        out.write_signed(samples[0] as i64, bps);
+        return;
+    }
+
+    let verbatim_bits = samples.len() as u64 * bps as u64;
+    let order = best_fixed_order(samples);
+    let residual = fixed_residual(samples, order);
+    let coding = best_residual_coding(&residual, order, samples.len());
+    let fixed_bits = order as u64 * bps as u64 + coding.bits;
+
+    if fixed_bits >= verbatim_bits {
+        out.write(0b0000_0010, 8); // VERBATIM
+        for &s in samples {
+            out.write_signed(s as i64, bps);
+        }
+        return;
+    }
+
+    out.write(0b0001_0000 | (order as u64) << 1, 8); // FIXED + order
+    for &s in &samples[..order] {
+        out.write_signed(s as i64, bps);
+    }
+    write_residual(out, &residual, &coding);
+}
+
+/// Pick the fixed predictor order with the smallest total absolute residual
+fn best_fixed_order(samples: &[i32]) -> usize {
+    let max_order = MAX_FIXED_ORDER.min(samples.len().saturating_sub(1));
+    (0..=max_order)
+        .min_by_key(|&order| {
+            fixed_residual(samples, order)
+                .iter()
+                .map(|r| r.unsigned_abs())
+                .sum::<u64>()
+        })
+        .unwrap_or(0)
+}
+
+/// Residual of the fixed predictor (excluding the `order` warm-up samples)
+fn fixed_residual(samples: &[i32], order: usize) -> Vec<i64> {
+    let s = |i: usize| samples[i] as i64;
+    (order..samples.len())
+        .map(|i| match order {
+            0 => s(i),
+            1 => s(i) - s(i - 1),
+            2 => s(i) - 2 * s(i - 1) + s(i - 2),
+            3 => s(i) - 3 * s(i - 1) + 3 * s(i - 2) - s(i - 3),
+            _ => s(i) - 4 * s(i - 1) + 6 * s(i - 2) - 4 * s(i - 3) + s(i - 4),
+        })
+        .collect()
+}
+
+struct ResidualCoding {
+    /// Fixed predictor order (number of warm-up samples)
+    order: usize,
+    partition_order: u32,
+    params: Vec<u32>,
+    /// Total size of the residual section in bits
+    bits: u64,
+}
+
+/// Choose the partition order and per-partition Rice parameters
+fn best_residual_coding(residual: &[i64], order: usize, block_len: usize) -> ResidualCoding {
+    let folded: Vec<u64> = residual.iter().map(|&r| fold(r)).collect();
+    let mut best: Option<ResidualCoding> = None;
+
+    for partition_order in 0..=MAX_PARTITION_ORDER {
+        let partitions = 1usize << partition_order;
+        if !block_len.is_multiple_of(partitions) || block_len / partitions <= order {
+            break;
+        }
+        let partition_len = block_len / partitions;
+
+        let mut bits = 2 + 4; // coding method + partition order
+        let mut params = Vec::with_capacity(partitions);
+        let mut start = 0;
+        for p in 0..partitions {
+            let len = if p == 0 {
+                partition_len - order
+            } else {
+                partition_len
+            };
+            let values = &folded[start..start + len];
+            start += len;
+
+            let (param, partition_bits) = best_rice_param(values);
+            params.push(param);
+            bits += 4 + partition_bits;
+        }
+
+        if best.as_ref().is_none_or(|b| bits < b.bits) {
+            best = Some(ResidualCoding {
+                order,
+                partition_order,
+                params,
+                bits,
+            });
+        }
+    }
+
+    best.expect("partition order 0 is always valid")
+}
+
+/// Find the Rice parameter that minimizes the encoded size of a partition
+fn best_rice_param(values: &[u64]) -> (u32, u64) {
+    if values.is_empty() {
+        return (0, 0);
+    }
+
+    let sum: u64 = values.iter().sum();
+    let mean = sum / values.len() as u64;
+    let estimate = if mean == 0 {
+        0
+    } else {
+        63 - mean.leading_zeros()
+    }
+    .min(MAX_RICE_PARAM);
+
+    let cost = |k: u32| -> u64 {
+        values.len() as u64 * (k as u64 + 1) + values.iter().map(|&v| v >> k).sum::<u64>()
+    };
+
+    (estimate.saturating_sub(1)..=(estimate + 1).min(MAX_RICE_PARAM))
+        .map(|k| (k, cost(k)))
+        .min_by_key(|&(_, bits)| bits)
+        .unwrap_or((MAX_RICE_PARAM, cost(MAX_RICE_PARAM)))
+}
+
+fn write_residual(out: &mut BitWriter, residual: &[i64], coding: &ResidualCoding) {
+    out.write(0b00, 2); // Rice coding with 4-bit parameters
+    out.write(coding.partition_order as u64, 4);
+
+    // The first partition is shortened by the predictor's warm-up samples
+    let partition_len = (residual.len() + coding.order) >> coding.partition_order;
+    let mut start = 0;
+    for (p, &param) in coding.params.iter().enumerate() {
+        let len = if p == 0 {
+            partition_len - coding.order
+        } else {
+            partition_len
+        };
+        out.write(param as u64, 4);
+        for &r in &residual[start..start + len] {
+            let v = fold(r);
+            out.write_unary(v >> param);
+            out.write(v & ((1u64 << param) - 1), param);
+        }
+        start += len;
+    }
+}
+
+/// Map signed residuals to unsigned values (0, -1, 1, -2, ... -> 0, 1, 2, 3, ...)
+fn fold(value: i64) -> u64 {
+    ((value << 1) ^ (value >> 63)) as u64
+}
+
+/// Write a frame number using FLAC's extended UTF-8 style coding
+fn write_utf8_number(out: &mut BitWriter, value: u64) {
+    if value < 0x80 {
+        out.write(value, 8);
+        return;
+    }
+
+    // Number of continuation bytes needed (each carries 6 bits)
+    let extra = match value {
+        0..=0x7FF => 1,
+        0x800..=0xFFFF => 2,
+        0x1_0000..=0x1F_FFFF => 3,
+        0x20_0000..=0x3FF_FFFF => 4,
+        0x400_0000..=0x7FFF_FFFF => 5,
+        _ => 6,
+    };
+    let prefix = (0xFF00u64 >> (extra + 1)) & 0xFF;
+    out.write(prefix | (value >> (6 * extra)), 8);
+    for i in (0..extra).rev() {
+        out.write(0x80 | ((value >> (6 * i)) & 0x3F), 8);
+    }
+}
+
+/// CRC-8 with polynomial x^8 + x^2 + x + 1 (frame header checksum)
+fn crc8(data: &[u8]) -> u8 {
+    data.iter().fold(0u8, |mut crc, &byte| {
+        crc ^= byte;
+        for _ in 0..8 {
+            crc = if crc & 0x80 != 0 {
+                (crc << 1) ^ 0x07
+            } else {
+                crc << 1
+            };
+        }
+        crc
+    })
+}
+
+/// CRC-16 with polynomial x^16 + x^15 + x^2 + 1 (frame footer checksum)
+fn crc16(data: &[u8]) -> u16 {
+    data.iter().fold(0u16, |mut crc, &byte| {
+        crc ^= (byte as u16) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x8005
+            } else {
+                crc << 1
+            };
+        }
+        crc
+    })
+}
+
+/// MSB-first bit writer
+struct BitWriter {
+    bytes: Vec<u8>,
+    acc: u64,
+    pending: u32,
+}
+
+impl BitWriter {
+    fn with_capacity(capacity: usize) -> Self {
+        Self {
+            bytes: Vec::with_capacity(capacity),
+            acc: 0,
+            pending: 0,
+        }
+    }
+
+    /// Write the lowest `bits` bits of `value`
+    fn write(&mut self, value: u64, bits: u32) {
+        if bits > 32 {
+            self.write(value >> 32, bits - 32);
+            self.write(value & 0xFFFF_FFFF, 32);
+            return;
+        }
+        if bits == 0 {
+            return;
+        }
+        self.acc = (self.acc << bits) | (value & ((1u64 << bits) - 1));
+        self.pending += bits;
+        while self.pending >= 8 {
+            self.pending -= 8;
+            self.bytes.push((self.acc >> self.pending) as u8);
+        }
+    }
+
+    /// Write a two's complement value in `bits` bits
+    fn write_signed(&mut self, value: i64, bits: u32) {
+        self.write(value as u64, bits);
+    }
+
+    /// Write `zeros` zero bits followed by a one bit
+    fn write_unary(&mut self, mut zeros: u64) {
+        while zeros >= 32 {
+            self.write(0, 32);
+            zeros -= 32;
+        }
+        self.write(1, zeros as u32 + 1);
+    }
+
+    fn write_bytes(&mut self, bytes: &[u8]) {
+        if self.pending == 0 {
+            self.bytes.extend_from_slice(bytes);
+        } else {
+            for &byte in bytes {
+                self.write(byte as u64, 8);
+            }
+        }
+    }
+
+    /// Pad with zero bits up to the next byte boundary
+    fn align(&mut self) {
+        if self.pending > 0 {
+            self.write(0, 8 - self.pending);
+        }
+    }
+
+    /// Bytes written so far (complete bytes only)
+    fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    fn into_bytes(mut self) -> Vec<u8> {
+        self.align();
+        self.bytes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use symphonia::core::audio::SampleBuffer;
+    use symphonia::core::codecs::DecoderOptions;
+    use symphonia::core::formats::FormatOptions;
+    use symphonia::core::io::MediaSourceStream;
+    use symphonia::core::meta::MetadataOptions;
+    use symphonia::core::probe::Hint;
+
+    /// Decode a FLAC stream with symphonia into interleaved samples
+    fn decode(data: Vec<u8>) -> (Vec<i16>, u32, u16) {
+        let stream =
+            MediaSourceStream::new(Box::new(std::io::Cursor::new(data)), Default::default());
+        let mut hint = Hint::new();
+        hint.with_extension("flac");
+        let mut format = symphonia::default::get_probe()
+            .format(
+                &hint,
+                stream,
+                &FormatOptions::default(),
+                &MetadataOptions::default(),
+            )
+            .unwrap()
+            .format;
+        let params = format.default_track().unwrap().codec_params.clone();
+        let mut decoder = symphonia::default::get_codecs()
+            .make(&params, &DecoderOptions { verify: true })
+            .unwrap();
+
+        let mut samples = Vec::new();
+        while let Ok(packet) = format.next_packet() {
+            let decoded = decoder.decode(&packet).unwrap();
+            let mut buffer = SampleBuffer::<i16>::new(decoded.capacity() as u64, *decoded.spec());
+            buffer.copy_interleaved_ref(decoded);
+            samples.extend_from_slice(buffer.samples());
+        }
+        let channels = params.channels.unwrap().count() as u16;
+        (samples, params.sample_rate.unwrap(), channels)
+    }
+
+    fn assert_round_trip(samples: &[i16], sample_rate: u32, channels: u16) {
+        let (decoded, decoded_rate, decoded_channels) =
+            decode(encode(samples, sample_rate, channels));
+        assert_eq!(decoded_rate, sample_rate);
+        assert_eq!(decoded_channels, channels);
+        assert_eq!(decoded.len(), samples.len());
+        assert!(decoded == samples, "decoded samples differ");
+    }
+
+    fn sine(frames: usize) -> Vec<i16> {
+        (0..frames)
+            .map(|i| ((i as f32 * 0.05).sin() * 12_000.0) as i16)
+            .collect()
+    }
+
+    /// Full-scale pseudo-random samples that no predictor can compress
+    fn noise(frames: usize) -> Vec<i16> {
+        let mut state = 0x2545_f491_u32;
+        (0..frames)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                state as i16
+            })
+            .collect()
+    }
+
+    /// The subframe type byte chosen for `samples`
+    fn subframe_type(samples: &[i16]) -> u8 {
+        let samples: Vec<i32> = samples.iter().map(|&s| s as i32).collect();
+        let mut out = BitWriter::with_capacity(samples.len() * 2);
+        write_subframe(&mut out, &samples);
+        out.bytes()[0]
+    }
+
+    #[test]
+    fn round_trips_with_a_partial_last_block() {
+        assert_round_trip(&sine(BLOCK_SIZE * 2 + 1234), 16_000, 1);
+    }
+
+    #[test]
+    fn round_trips_constant_and_verbatim_subframes() {
+        let silence = vec![-7; BLOCK_SIZE];
+        assert_eq!(subframe_type(&silence), 0b0000_0000);
+        let noise = noise(BLOCK_SIZE);
+        assert_eq!(subframe_type(&noise), 0b0000_0010);
+        assert_eq!(subframe_type(&sine(BLOCK_SIZE)) & 0b0111_0000, 0b0001_0000);
+
+        let mixed: Vec<i16> = [silence, noise, sine(BLOCK_SIZE)].concat();
+        assert_round_trip(&mixed, 16_000, 1);
+    }
+
+    #[test]
+    fn round_trips_stereo() {
+        // Left and right differ, and the right one is constant in the second block
+        let left = sine(BLOCK_SIZE + 500);
+        let right = [noise(BLOCK_SIZE), vec![42; 500]].concat();
+        let interleaved: Vec<i16> = left
+            .iter()
+            .zip(&right)
+            .flat_map(|(&l, &r)| [l, r])
+            .collect();
+        assert_round_trip(&interleaved, 44_100, 2);
+    }
+
+    #[test]
+    fn round_trips_a_single_sample() {
+        assert_round_trip(&[1234], 16_000, 1);
+    }
+}
@@ -0,0 +1,164 @@
+//! Live input level metering.
+//!
+//! The capture drain thread feeds every sample it moves through a
+//! [`LevelMeter`], which publishes the RMS and peak level of each short
+//! window on a watch channel. Subscribers always see the latest level and
+//! never hold up the recording.
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::watch;
+
+/// How much audio each level update covers
+const WINDOW_MS: usize = 50;
+/// Lowest level reported, standing in for digital silence
+const MIN_LEVEL_DB: f32 = -100.0;
+/// Peak level below which the input counts as near-silent
+const NEAR_SILENCE_DB: f32 = -60.0;
+/// How long the input can stay near-silent before a warning is raised
+const SILENCE_WARNING_SECS: f32 = 5.0;
+
+/// Receives the latest input level while a recording runs
+pub type LevelReceiver = watch::Receiver<InputLevel>;
+
+/// Level of the most recent stretch of captured audio
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct InputLevel {
+    /// RMS level in dBFS
+    pub rms_db: f32,
+    /// Peak level in dBFS
+    pub peak_db: f32,
+    /// How long the input has been near-silent, in seconds (0 while sound is picked up)
+    pub silent_secs: f32,
+    /// Set once the input has been near-silent for several seconds, which
+    /// usually means a muted or wrong microphone
+    pub silence_warning: bool,
+}
+
+impl Default for InputLevel {
+    fn default() -> Self {
+        Self {
+            rms_db: MIN_LEVEL_DB,
+            peak_db: MIN_LEVEL_DB,
+            silent_secs: 0.0,
+            silence_warning: false,
+        }
+    }
+}
+
+impl InputLevel {
+    /// RMS level scaled to 0.0..=1.0 for drawing a meter (-60 dBFS and below is empty)
+    pub fn meter(&self) -> f32 {
+        ((self.rms_db - NEAR_SILENCE_DB) / -NEAR_SILENCE_DB).clamp(0.0, 1.0)
+    }
+}
+
+fn to_db(amplitude: f32) -> f32 {
+    (20.0 * amplitude.max(1e-5).log10()).max(MIN_LEVEL_DB)
+}
+
+/// Accumulates samples and publishes one [`InputLevel`] per window
+pub(crate) struct LevelMeter {
+    sender: watch::Sender<InputLevel>,
+    /// Interleaved samples per window
+    window: usize,
+    /// Interleaved samples per second
+    rate: f32,
+    sum_squares: f64,
+    peak: f32,
+    count: usize,
+    silent_samples: usize,
+}
+
+impl LevelMeter {
+    pub fn new(sender: watch::Sender<InputLevel>, sample_rate: u32, channels: u16) -> Self {
+        let rate = sample_rate as usize * channels.max(1) as usize;
+        sender.send_replace(InputLevel::default());
+
+        Self {
+            sender,
+            window: (rate * WINDOW_MS / 1000).max(1),
+            rate: rate as f32,
+            sum_squares: 0.0,
+            peak: 0.0,
+            count: 0,
+            silent_samples: 0,
+        }
+    }
+
+    pub fn push(&mut self, samples: &[f32]) {
+        for &sample in samples {
+            self.sum_squares += (sample * sample) as f64;
+            self.peak = self.peak.max(sample.abs());
+            self.count += 1;
+            if self.count == self.window {
+                self.publish();
+            }
+        }
+    }
+
+    fn publish(&mut self) {
+        let rms = (self.sum_squares / self.count as f64).sqrt() as f32;
+        let peak_db = to_db(self.peak);
+        if peak_db < NEAR_SILENCE_DB {
+            self.silent_samples += self.count;
+        } else {
+            self.silent_samples = 0;
+        }
+        let silent_secs = self.silent_samples as f32 / self.rate;
+
+        self.sender.send_replace(InputLevel {
+            rms_db: to_db(rms),
+            peak_db,
+            silent_secs,
+            silence_warning: silent_secs >= SILENCE_WARNING_SECS,
+        });
+        self.sum_squares = 0.0;
+        self.peak = 0.0;
+        self.count = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn meter(sample_rate: u32) -> (LevelMeter, LevelReceiver) {
+        let (sender, receiver) = watch::channel(InputLevel::default());
+        (LevelMeter::new(sender, sample_rate, 1), receiver)
+    }
+
+    #[test]
+    fn sine_levels() {
+        let (mut meter, levels) = meter(16_000);
+        let sine: Vec<f32> = (0..16_000)
+            .map(|i| 0.5 * (2.0 * std::f32::consts::PI * 440.0 * i as f32 / 16_000.0).sin())
+            .collect();
+        meter.push(&sine);
+
+        let level = *levels.borrow();
+        // A sine's RMS is 3 dB below its peak
+        assert!((level.peak_db - -6.0).abs() < 0.1, "peak {}", level.peak_db);
+        assert!((level.rms_db - -9.0).abs() < 0.1, "rms {}", level.rms_db);
+        assert_eq!(level.silent_secs, 0.0);
+    }
+
+    #[test]
+    fn long_silence_raises_warning() {
+        let (mut meter, levels) = meter(16_000);
+        let second = vec![0.0; 16_000];
+        for _ in 0..4 {
+            meter.push(&second);
+        }
+        assert!(!levels.borrow().silence_warning);
+        assert_eq!(levels.borrow().rms_db, MIN_LEVEL_DB);
+
+        meter.push(&second);
+        assert!(levels.borrow().silence_warning);
+
+        // Any sound resets the silence timer
+        meter.push(&vec![0.1; 800]);
+        let level = *levels.borrow();
+        assert!(!level.silence_warning);
+        assert_eq!(level.silent_secs, 0.0);
+    }
+}
@@ -0,0 +1,801 @@
+use anyhow::{Context, Result};
+use bytes::Bytes;
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::error::WhisError;
+use crate::verbose;
+
+mod auto_stop;
+mod capture;
+mod chunk;
+mod decode;
+mod device;
+mod encoder;
+mod flac;
+mod level;
+mod resample;
+mod spill;
+mod stream;
+mod vad;
+
+pub use auto_stop::{AutoStopOptions, AutoStopReason, AutoStopReceiver};
+pub use capture::CaptureStats;
+pub(crate) use chunk::CHUNK_OVERLAP_SECS;
+pub use device::{
+    list_input_devices, resolve_input_device, DeviceFallback, InputDeviceInfo, SupportedInputConfig,
+};
+pub use encoder::{
+    encoder_for, AudioEncoder, AudioFormat, EncodedAudio, FfmpegEncoder, FlacEncoder, WavEncoder,
+};
+#[cfg(feature = "opus")]
+pub use encoder::OpusEncoder;
+pub use level::{InputLevel, LevelReceiver};
+pub use resample::{downmix_to_mono, resample};
+use resample::ResampleStream;
+pub use stream::ChunkReceiver;
+pub use vad::{detect_speech, trim_silence, VadOptions};
+
+/// Default sample rate for audio sent to providers (what Whisper models use internally)
+pub const UPLOAD_SAMPLE_RATE: u32 = 16_000;
+
+/// Default size above which recordings are split into chunks
+pub const DEFAULT_CHUNK_THRESHOLD_MB: u32 = 20;
+/// Default maximum duration of each chunk in seconds
+pub const DEFAULT_CHUNK_DURATION_SECS: u32 = 300; // 5 minutes
+
+/// A chunk of audio data ready for transcription
+#[derive(Clone)]
+pub struct AudioChunk {
+    /// Encoded audio data
+    pub data: Bytes,
+    /// Format of `data`
+    pub format: AudioFormat,
+    /// Chunk index (0-based, for ordering)
+    pub index: usize,
+    /// Whether this chunk has overlap from the previous chunk
+    pub has_leading_overlap: bool,
+    /// Where this chunk starts in the recording, in seconds, for placing its
+    /// timestamps (exact only without VAD, which shortens pauses)
+    pub start_secs: f64,
+    /// Whether the chunk's timestamps, offset by `start_secs`, line up with
+    /// the other chunks' (false when silence was trimmed from this chunk alone)
+    pub exact_timing: bool,
+}
+
+/// Output of a completed recording - either a single file or multiple chunks
+pub enum RecordingOutput {
+    /// Small file that can be transcribed directly
+    Single(EncodedAudio),
+    /// Large file split into chunks for parallel transcription
+    Chunked(Vec<AudioChunk>),
+    /// Voice activity detection found no speech, so there is nothing to transcribe
+    NoSpeech,
+}
+
+/// Options controlling how a recording is converted and encoded
+#[derive(Debug, Clone, Copy)]
+pub struct FinalizeOptions {
+    /// Output format for the encoded audio
+    pub format: AudioFormat,
+    /// Resample to this rate before encoding (None = keep the device rate)
+    pub sample_rate: Option<u32>,
+    /// Downmix to a single channel before encoding
+    pub mono: bool,
+    /// Trim silence and skip recordings without speech (None = keep everything)
+    pub vad: Option<VadOptions>,
+    /// Encoded recordings larger than this are split into chunks
+    pub chunk_threshold_bytes: usize,
+    /// Maximum duration of each chunk in seconds
+    pub chunk_duration_secs: usize,
+}
+
+impl Default for FinalizeOptions {
+    fn default() -> Self {
+        Self {
+            format: AudioFormat::default(),
+            sample_rate: Some(UPLOAD_SAMPLE_RATE),
+            mono: true,
+            vad: Some(VadOptions::default()),
+            chunk_threshold_bytes: DEFAULT_CHUNK_THRESHOLD_MB as usize * 1024 * 1024,
+            chunk_duration_secs: DEFAULT_CHUNK_DURATION_SECS as usize,
+        }
+    }
+}
+
+impl FinalizeOptions {
+    /// Build finalize options from user settings
+    ///
+    /// The local provider decodes the audio again, so it gets lossless audio
+    /// at the rate whisper.cpp uses instead of the configured format.
+    pub fn from_settings(settings: &crate::Settings) -> Self {
+        let options = Self {
+            format: settings.audio_format,
+            sample_rate: Some(settings.audio_sample_rate).filter(|&rate| rate > 0),
+            mono: settings.audio_mono,
+            vad: settings.vad_enabled.then(|| VadOptions {
+                threshold_db: settings.vad_threshold_db,
+                ..VadOptions::default()
+            }),
+            chunk_threshold_bytes: settings.chunk_threshold_mb.max(1) as usize * 1024 * 1024,
+            chunk_duration_secs: settings.chunk_duration_secs as usize,
+        };
+        match settings.provider {
+            crate::TranscriptionProvider::Local => Self {
+                format: AudioFormat::Wav,
+                sample_rate: Some(crate::provider::local::WHISPER_SAMPLE_RATE),
+                mono: true,
+                ..options
+            },
+            _ => options,
+        }
+    }
+
+    /// Convert a recording and trim its silence, if enabled
+    ///
+    /// The result is held in memory, ready for in-process transcription.
+    /// Returns None when the recording contains no speech.
+    pub fn prepare(&self, recording: RecordingData) -> Result<Option<RecordingData>> {
+        let recording = recording.convert(self.sample_rate, self.mono)?;
+        Ok(match &self.vad {
+            Some(vad) => recording.trim_silence(vad),
+            None => Some(recording),
+        })
+    }
+}
+
+/// Recording data extracted from AudioRecorder after stopping.
+/// This struct is Send-safe (unlike AudioRecorder on macOS where cpal::Stream isn't Send).
+///
+/// Long recordings may be partly stored on disk (see
+/// [`AudioRecorder::set_memory_limit`]); [`RecordingData::convert`] and
+/// [`FinalizeOptions::prepare`] load them into memory, while
+/// [`RecordingData::finalize_with`] encodes them a chunk at a time.
+pub struct RecordingData {
+    /// Samples held in memory (after the spilled ones, if any)
+    samples: Vec<f32>,
+    /// Earlier samples spilled to disk during recording
+    spilled: Option<spill::SpillStore>,
+    sample_rate: u32,
+    channels: u16,
+}
+
+pub struct AudioRecorder {
+    samples: Arc<Mutex<Vec<f32>>>,
+    sample_rate: u32,
+    channels: u16,
+    stream: Option<cpal::Stream>,
+    /// Moves samples from the audio callback's ring buffer into `samples`
+    capture: Option<capture::CaptureDrain>,
+    capture_counters: Arc<capture::CaptureCounters>,
+    capture_control: Arc<capture::CaptureControl>,
+    /// Whether a recording is running (the stream may also be open while armed)
+    recording: bool,
+    /// Audio kept from before each recording; Some keeps the microphone open
+    pre_roll: Option<Duration>,
+    /// Latest input level, published while the stream is open
+    levels: tokio::sync::watch::Sender<InputLevel>,
+    auto_stop: AutoStopOptions,
+    auto_stop_monitor: Arc<Mutex<Option<auto_stop::AutoStopMonitor>>>,
+    /// Set once the current recording should stop on its own; replaced for
+    /// every recording so subscribers never see a later recording's reason
+    auto_stop_reason: tokio::sync::watch::Sender<Option<AutoStopReason>>,
+    /// Device index or name to record from (None = system default)
+    input_device: Option<String>,
+    device_fallback: DeviceFallback,
+    device_name: Option<String>,
+    fallback_warning: Option<String>,
+    /// Options and channel for the next recording, set by `stream_chunks`
+    stream_target: Option<(FinalizeOptions, stream::ChunkSender)>,
+    streamer: Option<stream::ChunkStreamer>,
+    /// Buffered samples allowed in memory before spilling to disk (None = unlimited)
+    memory_limit: Option<usize>,
+    spiller: Option<spill::SpillWorker>,
+}
+
+impl AudioRecorder {
+    pub fn new() -> Result<Self> {
+        Ok(AudioRecorder {
+            samples: Arc::new(Mutex::new(Vec::new())),
+            sample_rate: 44100, // Default sample rate
+            channels: 1,        // Default channels
+            stream: None,
+            capture: None,
+            capture_counters: Arc::new(capture::CaptureCounters::default()),
+            capture_control: Arc::new(capture::CaptureControl::default()),
+            recording: false,
+            pre_roll: None,
+            levels: tokio::sync::watch::Sender::new(InputLevel::default()),
+            auto_stop: AutoStopOptions::default(),
+            auto_stop_monitor: Arc::new(Mutex::new(None)),
+            auto_stop_reason: tokio::sync::watch::Sender::new(None),
+            input_device: None,
+            device_fallback: DeviceFallback::default(),
+            device_name: None,
+            fallback_warning: None,
+            stream_target: None,
+            streamer: None,
+            memory_limit: None,
+            spiller: None,
+        })
+    }
+
+    /// Create a recorder using the input device configured in settings
+    pub fn from_settings(settings: &crate::Settings) -> Result<Self> {
+        let mut recorder = Self::new()?;
+        recorder.set_input_device(settings.input_device.clone(), settings.input_device_fallback);
+        recorder.set_memory_limit(settings.recording_memory_limit());
+        recorder.set_auto_stop(AutoStopOptions::from_settings(settings));
+        Ok(recorder)
+    }
+
+    /// Choose the input device (a name from [`list_input_devices`]) for the
+    /// next recording, and what to do if it is missing
+    pub fn set_input_device(&mut self, device: Option<String>, fallback: DeviceFallback) {
+        self.input_device = device;
+        self.device_fallback = fallback;
+    }
+
+    /// Cap the memory used by buffered audio (None = unlimited)
+    ///
+    /// Once a recording exceeds `bytes`, older audio is moved to segment files
+    /// in the cache directory, which are deleted with the [`RecordingData`].
+    /// Streaming recordings keep chunks small enough to stay under the limit.
+    pub fn set_memory_limit(&mut self, bytes: Option<usize>) {
+        self.memory_limit = bytes.map(|bytes| bytes / std::mem::size_of::<f32>());
+    }
+
+    /// Limits after which the next recording should stop on its own
+    ///
+    /// The recorder doesn't stop by itself; it reports the reason through
+    /// [`Self::auto_stop_reason`] and [`Self::subscribe_auto_stop`] so the
+    /// caller can stop and transcribe as if the user had asked.
+    pub fn set_auto_stop(&mut self, options: AutoStopOptions) {
+        self.auto_stop = options;
+    }
+
+    /// Why the current recording should stop, once a limit is reached
+    pub fn auto_stop_reason(&self) -> Option<AutoStopReason> {
+        *self.auto_stop_reason.borrow()
+    }
+
+    /// Watch for the current recording reaching an auto-stop limit
+    ///
+    /// Call after [`Self::start_recording`]; the receiver closes when the
+    /// recording stops.
+    pub fn subscribe_auto_stop(&self) -> AutoStopReceiver {
+        self.auto_stop_reason.subscribe()
+    }
+
+    /// Keep the microphone open between recordings, buffering the last `pre_roll`
+    ///
+    /// The buffered audio is prepended to the next recording, so the first
+    /// word isn't lost while the device starts up. The microphone stays open
+    /// after each recording until [`Self::disarm`] is called or the recorder
+    /// is dropped, so callers must show that it is on.
+    pub fn arm(&mut self, pre_roll: Duration) -> Result<()> {
+        self.pre_roll = Some(pre_roll);
+        if self.stream.is_none() {
+            self.open_stream()?;
+        }
+        self.capture_control.set_pre_roll(self.pre_roll_samples());
+        crate::verbose!("Microphone armed with {}ms pre-roll", pre_roll.as_millis());
+        Ok(())
+    }
+
+    /// Stop keeping the microphone open (after the current recording, if any)
+    pub fn disarm(&mut self) {
+        self.pre_roll = None;
+        self.capture_control.set_pre_roll(0);
+        if !self.recording {
+            self.close_stream();
+        }
+    }
+
+    /// Whether the microphone is kept open between recordings
+    pub fn is_armed(&self) -> bool {
+        self.pre_roll.is_some() && self.stream.is_some()
+    }
+
+    fn pre_roll_samples(&self) -> usize {
+        let secs = self.pre_roll.unwrap_or_default().as_secs_f64();
+        (secs * self.sample_rate as f64) as usize * self.channels as usize
+    }
+
+    /// Name of the device used by the current or last recording
+    pub fn device_name(&self) -> Option<&str> {
+        self.device_name.as_deref()
+    }
+
+    /// Warning set when the configured device was missing and the default
+    /// device was used instead
+    pub fn fallback_warning(&self) -> Option<&str> {
+        self.fallback_warning.as_deref()
+    }
+
+    /// Overruns and dropouts counted during the current or last recording
+    pub fn capture_stats(&self) -> CaptureStats {
+        self.capture_counters.snapshot()
+    }
+
+    /// Watch the input level of this recorder's recordings
+    ///
+    /// A new level arrives every 50ms while recording; it is reset to
+    /// silence when the recording stops.
+    pub fn subscribe_levels(&self) -> LevelReceiver {
+        self.levels.subscribe()
+    }
+
+    /// Emit encoded chunks while the next recording is running
+    ///
+    /// Call before [`Self::start_recording`] and end the recording with
+    /// [`Self::stop_streaming`]. Each chunk is converted, trimmed and encoded
+    /// with `options` as soon as it fills, so it can be transcribed while
+    /// recording continues. Chunks without speech are skipped, so a recording
+    /// without speech produces no chunks at all.
+    pub fn stream_chunks(&mut self, options: FinalizeOptions) -> ChunkReceiver {
+        let (sender, receiver) = tokio::sync::mpsc::unbounded_channel();
+        self.stream_target = Some((options, sender));
+        receiver
+    }
+
+    pub fn start_recording(&mut self) -> Result<()> {
+        if self.stream.is_none() {
+            self.open_stream()?;
+        }
+
+        self.samples.lock().unwrap().clear();
+        self.capture_counters.reset();
+        self.auto_stop_reason = tokio::sync::watch::Sender::new(None);
+        *self.auto_stop_monitor.lock().unwrap() = Some(auto_stop::AutoStopMonitor::new(
+            self.auto_stop,
+            self.auto_stop_reason.clone(),
+            self.sample_rate,
+            self.channels,
+        ));
+        // Any buffered pre-roll goes in first
+        self.capture_control.set_recording(true);
+        self.recording = true;
+
+        if let Some((options, sender)) = self.stream_target.take() {
+            let chunker =
+                stream::Chunker::new(self.sample_rate, self.channels, options, self.memory_limit);
+            self.streamer = Some(stream::ChunkStreamer::spawn(
+                self.samples.clone(),
+                chunker,
+                sender,
+            ));
+        } else if let Some(limit) = self.memory_limit {
+            self.spiller = Some(spill::SpillWorker::spawn(self.samples.clone(), limit));
+        }
+
+        Ok(())
+    }
+
+    /// Open the input device and start capturing (into the pre-roll until recording starts)
+    fn open_stream(&mut self) -> Result<()> {
+        let host = cpal::default_host();
+
+        if verbose::is_verbose() {
+            crate::verbose!("Audio host: {:?}", host.id());
+
+            // List all available input devices
+            if let Ok(devices) = host.input_devices() {
+                crate::verbose!("Available input devices:");
+                for (i, dev) in devices.enumerate() {
+                    let name = dev.name().unwrap_or_else(|_| "<unknown>".to_string());
+                    crate::verbose!("  [{i}] {name}");
+                }
+            }
+        }
+
+        let selected = device::select_input_device(
+            &host,
+            self.input_device.as_deref(),
+            self.device_fallback,
+        )?;
+        let device = selected.device;
+        crate::verbose!("Selected input device: {}", selected.name);
+        self.device_name = Some(selected.name);
+        self.fallback_warning = selected.fallback_warning;
+
+        let config = device
+            .default_input_config()
+            .context("Failed to get default input config")?;
+
+        self.sample_rate = config.sample_rate().0;
+        self.channels = config.channels();
+
+        crate::verbose!("Audio config: {} Hz, {} channel(s), format: {:?}",
+            self.sample_rate, self.channels, config.sample_format());
+
+        let capacity = self.sample_rate as usize * self.channels as usize * capture::RING_SECS;
+        let mut meter =
+            level::LevelMeter::new(self.levels.clone(), self.sample_rate, self.channels);
+        let auto_stop = self.auto_stop_monitor.clone();
+        self.capture_control.set_recording(false);
+        self.capture_control.set_pre_roll(self.pre_roll_samples());
+        let (input, drain) = capture::capture(
+            capacity,
+            self.channels,
+            self.samples.clone(),
+            self.capture_counters.clone(),
+            self.capture_control.clone(),
+            Box::new(move |samples| {
+                meter.push(samples);
+                if let Some(auto_stop) = auto_stop.lock().unwrap().as_mut() {
+                    auto_stop.push(samples);
+                }
+            }),
+        );
+        self.capture = Some(drain);
+
+        let stream = match config.sample_format() {
+            cpal::SampleFormat::F32 => {
+                self.build_stream::<f32>(&device, &config.into(), input)?
+            }
+            cpal::SampleFormat::I16 => {
+                self.build_stream::<i16>(&device, &config.into(), input)?
+            }
+            cpal::SampleFormat::U16 => {
+                self.build_stream::<u16>(&device, &config.into(), input)?
+            }
+            _ => anyhow::bail!("Unsupported sample format"),
+        };
+
+        stream.play()?;
+        crate::verbose!("Audio stream started");
+
+        // Store stream to keep it alive; dropping it will release the microphone
+        self.stream = Some(stream);
+
+        Ok(())
+    }
+
+    fn build_stream<T>(
+        &self,
+        device: &cpal::Device,
+        config: &cpal::StreamConfig,
+        mut input: capture::CaptureInput,
+    ) -> Result<cpal::Stream>
+    where
+        T: cpal::Sample + cpal::SizedSample,
+        f32: cpal::FromSample<T>,
+    {
+        let counters = self.capture_counters.clone();
+        let err_fn = move |err| {
+            counters.record_dropout();
+            eprintln!("Error in audio stream: {err}");
+        };
+
+        // Runs on the real-time audio thread, so it must not lock or allocate
+        let stream = device.build_input_stream(
+            config,
+            move |data: &[T], _: &cpal::InputCallbackInfo| input.write(data),
+            err_fn,
+            None,
+        )?;
+
+        Ok(stream)
+    }
+
+    /// Stop a recording started after [`Self::stream_chunks`]
+    ///
+    /// The remaining audio is encoded as the final chunk in the background,
+    /// after which the chunk receiver is closed.
+    pub fn stop_streaming(&mut self) -> Result<()> {
+        crate::verbose!("Stopping audio stream...");
+        self.end_capture();
+        self.streamer
+            .take()
+            .context("Recording is not streaming")?
+            .finish();
+        Ok(())
+    }
+
+    /// Stop recording and return the recording data.
+    /// The stream is dropped here, making the returned RecordingData Send-safe.
+    pub fn stop_recording(&mut self) -> Result<RecordingData> {
+        if self.streamer.is_some() {
+            anyhow::bail!("Recording is streaming; stop it with stop_streaming");
+        }
+        crate::verbose!("Stopping audio stream...");
+
+        self.end_capture();
+        let spilled = self.spiller.take().and_then(spill::SpillWorker::finish);
+
+        // Take ownership of samples and clear the buffer
+        let samples: Vec<f32> = {
+            let mut guard = self.samples.lock().unwrap();
+            std::mem::take(&mut *guard)
+        };
+
+        let total = samples.len() + spilled.as_ref().map_or(0, spill::SpillStore::len);
+        if total == 0 {
+            crate::verbose!("No audio samples captured!");
+            return Err(WhisError::EmptyAudio.into());
+        }
+
+        let duration_secs = total as f32 / self.sample_rate as f32 / self.channels as f32;
+        crate::verbose!("Captured {total} samples ({duration_secs:.2}s of audio)");
+
+        Ok(RecordingData {
+            samples,
+            spilled,
+            sample_rate: self.sample_rate,
+            channels: self.channels,
+        })
+    }
+
+    /// Move the samples still in the ring buffer into the recording and stop
+    /// adding to it; the microphone stays open if armed
+    fn end_capture(&mut self) {
+        self.recording = false;
+        *self.auto_stop_monitor.lock().unwrap() = None;
+        // Close the receivers of this recording's auto-stop reason
+        self.auto_stop_reason = tokio::sync::watch::Sender::new(None);
+
+        if self.pre_roll.is_some() {
+            self.capture_control.set_recording(false);
+            if let Some(drain) = &self.capture {
+                drain.sync();
+            }
+        } else {
+            self.close_stream();
+        }
+        crate::verbose!("Capture: {}", self.capture_stats());
+    }
+
+    fn close_stream(&mut self) {
+        // Drop the stream first to release the microphone
+        self.stream = None;
+        if let Some(drain) = self.capture.take() {
+            drain.finish();
+        }
+        self.levels.send_replace(InputLevel::default());
+    }
+
+    /// Stop recording and finalize in one step (convenience method for single-threaded use).
+    pub fn finalize_recording(&mut self) -> Result<RecordingOutput> {
+        self.stop_recording()?.finalize()
+    }
+}
+
+impl RecordingData {
+    /// Raw interleaved f32 samples held in memory
+    ///
+    /// This is the whole recording unless part of it was spilled to disk;
+    /// [`RecordingData::convert`] always returns a recording fully in memory.
+    pub fn samples(&self) -> &[f32] {
+        &self.samples
+    }
+
+    /// Whether part of the recording is stored on disk
+    pub fn is_spilled(&self) -> bool {
+        self.spilled.is_some()
+    }
+
+    /// Sample rate in Hz
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    /// Number of interleaved channels
+    pub fn channels(&self) -> u16 {
+        self.channels
+    }
+
+    /// Downmix and/or resample the recording.
+    ///
+    /// Speech models work on 16 kHz mono, so converting before upload shrinks
+    /// the payload several times over without affecting accuracy.
+    pub fn convert(mut self, sample_rate: Option<u32>, mono: bool) -> Result<RecordingData> {
+        let channels = if mono && self.channels > 1 {
+            1
+        } else {
+            self.channels
+        };
+        let target_rate = sample_rate.unwrap_or(self.sample_rate);
+        let mut resampler = if target_rate != self.sample_rate {
+            crate::verbose!("Resampling {} Hz -> {target_rate} Hz", self.sample_rate);
+            Some(ResampleStream::new(channels, self.sample_rate, target_rate)?)
+        } else {
+            None
+        };
+
+        // Spilled segments go through the same resampler one at a time, so
+        // only the converted audio is held in full and the joins are seamless
+        let mut samples = Vec::new();
+        let mut convert_part = |part: Vec<f32>| -> Result<()> {
+            let part = if channels != self.channels {
+                downmix_to_mono(&part, self.channels)
+            } else {
+                part
+            };
+            let part = match resampler.as_mut() {
+                Some(resampler) => resampler.push(&part)?,
+                None => part,
+            };
+            if samples.is_empty() {
+                samples = part;
+            } else {
+                samples.extend(part);
+            }
+            Ok(())
+        };
+
+        if let Some(spilled) = self.spilled.take() {
+            for segment in spilled.segments() {
+                convert_part(segment?)?;
+            }
+        }
+        convert_part(std::mem::take(&mut self.samples))?;
+        if let Some(resampler) = resampler {
+            samples.extend(resampler.finish()?);
+        }
+
+        Ok(RecordingData {
+            samples,
+            spilled: None,
+            sample_rate: target_rate,
+            channels,
+        })
+    }
+
+    /// Trim silence at both ends and shorten long pauses.
+    ///
+    /// Returns None when no speech was detected. Expects a recording that is
+    /// fully in memory (as returned by [`RecordingData::convert`]).
+    fn trim_silence(self, options: &VadOptions) -> Option<RecordingData> {
+        debug_assert!(self.spilled.is_none(), "trim_silence on a spilled recording");
+        let samples = trim_silence(&self.samples, self.sample_rate, self.channels, options)?;
+        let secs = |len: usize| len as f32 / self.sample_rate as f32 / self.channels as f32;
+        crate::verbose!(
+            "Trimmed silence: {:.2}s -> {:.2}s",
+            secs(self.samples.len()),
+            secs(samples.len())
+        );
+
+        Some(RecordingData { samples, ..self })
+    }
+
+    /// Finalize the recording by encoding samples with the default options.
+    /// This is Send-safe and can be called from spawn_blocking.
+    pub fn finalize(self) -> Result<RecordingOutput> {
+        self.finalize_with(&FinalizeOptions::default())
+    }
+
+    /// Finalize the recording by encoding samples in the requested format.
+    pub fn finalize_with(self, options: &FinalizeOptions) -> Result<RecordingOutput> {
+        if self.spilled.is_some() {
+            return self.finalize_spilled(options);
+        }
+
+        let Some(recording) = options.prepare(self)? else {
+            crate::verbose!("No speech detected");
+            return Ok(RecordingOutput::NoSpeech);
+        };
+        crate::verbose!(
+            "Upload audio: {} Hz, {} channel(s)",
+            recording.sample_rate,
+            recording.channels
+        );
+        recording.encode_all(options)
+    }
+
+    /// Encode a recording that was spilled to disk, one chunk at a time
+    fn finalize_spilled(mut self, options: &FinalizeOptions) -> Result<RecordingOutput> {
+        let spilled = self.spilled.take().context("Recording is not spilled")?;
+        let mut chunker = stream::Chunker::new(self.sample_rate, self.channels, *options, None);
+
+        let mut chunks = Vec::new();
+        for segment in spilled.segments() {
+            chunker.push(segment?);
+            chunks.extend(chunker.take_ready()?);
+        }
+        chunker.push(self.samples);
+        chunks.extend(chunker.finish()?);
+
+        if chunks.is_empty() {
+            crate::verbose!("No speech detected");
+            return Ok(RecordingOutput::NoSpeech);
+        }
+        Ok(RecordingOutput::Chunked(chunks))
+    }
+
+    /// Convert, trim and encode one chunk of a streaming recording
+    ///
+    /// Returns None when the chunk contains no speech.
+    fn encode_chunk(
+        self,
+        options: &FinalizeOptions,
+        index: usize,
+        has_leading_overlap: bool,
+        start_secs: f64,
+    ) -> Result<Option<AudioChunk>> {
+        let Some(recording) = options.prepare(self)? else {
+            return Ok(None);
+        };
+
+        let encoder = encoder_for(options.format);
+        let data = recording.encode(encoder.as_ref(), &recording.samples, &format!("chunk{index}"))?;
+        Ok(Some(AudioChunk {
+            data: data.into(),
+            format: options.format,
+            index,
+            has_leading_overlap,
+            start_secs,
+            exact_timing: options.vad.is_none(),
+        }))
+    }
+
+    /// Encode already converted samples, splitting into chunks if needed
+    fn encode_all(self, options: &FinalizeOptions) -> Result<RecordingOutput> {
+        let encoder = encoder_for(options.format);
+
+        // Try to encode the entire recording first
+        let data = self.encode(encoder.as_ref(), &self.samples, "main")?;
+
+        // If at or under threshold, return as single file (fast path)
+        if data.len() <= options.chunk_threshold_bytes {
+            return Ok(RecordingOutput::Single(EncodedAudio::new(data, options.format)));
+        }
+
+        // File is too large - need to chunk it
+        let samples_per_second = self.sample_rate as usize * self.channels as usize;
+
+        // Lossless formats are much larger than MP3; shorten chunks so each
+        // one still fits under the threshold
+        let total_secs = (self.samples.len() / samples_per_second).max(1);
+        let bytes_per_sec = (data.len() / total_secs).max(1);
+        let max_chunk_secs = options.chunk_duration_secs.max(chunk::MIN_CHUNK_SECS);
+        let chunk_secs = (options.chunk_threshold_bytes * 9 / 10 / bytes_per_sec)
+            .clamp(chunk::MIN_CHUNK_SECS, max_chunk_secs);
+        drop(data);
+
+        crate::verbose!("Splitting recording into chunks of up to {chunk_secs}s");
+        let spans = chunk::plan_chunks(
+            &self.samples,
+            self.sample_rate,
+            self.channels,
+            chunk_secs,
+            options.vad.and_then(|vad| vad.threshold_db),
+        );
+
+        let mut chunks = Vec::with_capacity(spans.len());
+        for (index, span) in spans.into_iter().enumerate() {
+            let start_secs = span.range.start as f64 / samples_per_second as f64;
+            let data = self.encode(
+                encoder.as_ref(),
+                &self.samples[span.range],
+                &format!("chunk{index}"),
+            )?;
+
+            chunks.push(AudioChunk {
+                data: data.into(),
+                format: options.format,
+                index,
+                has_leading_overlap: span.has_leading_overlap,
+                start_secs,
+                // Silence was trimmed before splitting, so chunks share one timeline
+                exact_timing: true,
+            });
+        }
+
+        Ok(RecordingOutput::Chunked(chunks))
+    }
+
+    /// Encode raw f32 samples in memory
+    fn encode(&self, encoder: &dyn AudioEncoder, samples: &[f32], label: &str) -> Result<Vec<u8>> {
+        let format = encoder.format();
+        crate::verbose!("Encoding {} samples as {format} ({label})", samples.len());
+
+        let data = encoder
+            .encode(samples, self.sample_rate, self.channels)
+            .with_context(|| format!("Failed to encode audio as {format}"))?;
+        crate::verbose!("Encoded {format}: {} bytes", data.len());
+
+        Ok(data)
+    }
+}
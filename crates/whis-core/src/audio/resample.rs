@@ -0,0 +1,336 @@
+//! Channel downmix and sample rate conversion.
+//!
+//! Resampling uses rubato's FFT-based synchronous resampler, which applies a
+//! proper anti-aliasing filter (unlike naive linear interpolation) and is fast
+//! enough to convert long recordings in one pass.
+
+use anyhow::{Context, Result};
+use rubato::{FftFixedIn, Resampler};
+
+/// Input frames per resampler call
+const RESAMPLE_CHUNK_FRAMES: usize = 1024;
+/// FFT sub-chunks per call (trades latency for speed; latency is irrelevant offline)
+const RESAMPLE_SUB_CHUNKS: usize = 2;
+
+/// Average interleaved channels into a single mono channel
+pub fn downmix_to_mono(samples: &[f32], channels: u16) -> Vec<f32> {
+    let channels = channels.max(1) as usize;
+    if channels == 1 {
+        return samples.to_vec();
+    }
+    samples
+        .chunks_exact(channels)
+        .map(|frame| frame.iter().sum::<f32>() / channels as f32)
+        .collect()
+}
+
+/// Resample interleaved samples from one rate to another
+///
+/// The output has exactly `frames * to / from` frames (rounded), with the
+/// resampler's filter delay removed so timing is preserved.
+pub fn resample(samples: &[f32], channels: u16, from: u32, to: u32) -> Result<Vec<f32>> {
+    if from == to || samples.is_empty() {
+        return Ok(samples.to_vec());
+    }
+
+    let mut stream = ResampleStream::new(channels, from, to)?;
+    let mut output = stream.push(samples)?;
+    output.extend(stream.finish()?);
+    Ok(output)
+}
+
+/// Resampler fed one piece of a recording at a time
+///
+/// The output is the same as resampling all the pieces joined together, so
+/// pieces can be converted without holding the whole input in memory.
+pub(crate) struct ResampleStream {
+    resampler: FftFixedIn<f32>,
+    from: u32,
+    to: u32,
+    /// Input frames not yet processed, per channel
+    pending: Vec<Vec<f32>>,
+    buffer: Vec<Vec<f32>>,
+    /// Input frames pushed so far
+    frames_in: usize,
+    /// Output frames produced so far, including the filter delay
+    frames_out: usize,
+    /// Output frames still to drop at the start
+    delay_left: usize,
+    /// Output frames returned so far
+    emitted: usize,
+}
+
+impl ResampleStream {
+    pub fn new(channels: u16, from: u32, to: u32) -> Result<Self> {
+        if from == 0 || to == 0 {
+            anyhow::bail!("Invalid sample rate conversion: {from} Hz -> {to} Hz");
+        }
+        let channels = channels.max(1) as usize;
+
+        let resampler = FftFixedIn::<f32>::new(
+            from as usize,
+            to as usize,
+            RESAMPLE_CHUNK_FRAMES,
+            RESAMPLE_SUB_CHUNKS,
+            channels,
+        )
+        .context("Failed to create resampler")?;
+        let delay = resampler.output_delay();
+        let buffer = resampler.output_buffer_allocate(true);
+
+        Ok(Self {
+            resampler,
+            from,
+            to,
+            pending: vec![Vec::new(); channels],
+            buffer,
+            frames_in: 0,
+            frames_out: 0,
+            delay_left: delay,
+            emitted: 0,
+        })
+    }
+
+    /// Resample the next piece, returning the interleaved output ready so far
+    pub fn push(&mut self, samples: &[f32]) -> Result<Vec<f32>> {
+        let channels = self.pending.len();
+        for frame in samples.chunks_exact(channels) {
+            for (pending, &sample) in self.pending.iter_mut().zip(frame) {
+                pending.push(sample);
+            }
+        }
+        self.frames_in += samples.len() / channels;
+
+        let mut output = Vec::new();
+        let mut pos = 0;
+        while pos + RESAMPLE_CHUNK_FRAMES <= self.pending[0].len() {
+            let chunk: Vec<&[f32]> = self
+                .pending
+                .iter()
+                .map(|ch| &ch[pos..pos + RESAMPLE_CHUNK_FRAMES])
+                .collect();
+            let (_, len) = self
+                .resampler
+                .process_into_buffer(&chunk, &mut self.buffer, None)
+                .context("Resampling failed")?;
+            self.append(len, usize::MAX, &mut output);
+            pos += RESAMPLE_CHUNK_FRAMES;
+        }
+        for pending in &mut self.pending {
+            pending.drain(..pos);
+        }
+
+        Ok(output)
+    }
+
+    /// Resample the rest of the input, returning the remaining output
+    pub fn finish(mut self) -> Result<Vec<f32>> {
+        let expected = ((self.frames_in as u64 * self.to as u64 + self.from as u64 / 2)
+            / self.from as u64) as usize;
+        let total = expected + self.resampler.output_delay();
+
+        // Feed the remaining frames, then zeros until the delayed tail is flushed
+        let mut output = Vec::new();
+        let mut remaining = !self.pending[0].is_empty();
+        while self.frames_out < total {
+            let (_, len) = if remaining {
+                remaining = false;
+                self.resampler.process_partial_into_buffer(
+                    Some(&self.pending),
+                    &mut self.buffer,
+                    None,
+                )
+            } else {
+                self.resampler.process_partial_into_buffer(
+                    None::<&[Vec<f32>]>,
+                    &mut self.buffer,
+                    None,
+                )
+            }
+            .context("Resampling failed")?;
+            self.append(len, expected, &mut output);
+        }
+
+        Ok(output)
+    }
+
+    /// Interleave `len` frames from the buffer into `output`, dropping the
+    /// filter delay and anything past `limit` output frames
+    fn append(&mut self, len: usize, limit: usize, output: &mut Vec<f32>) {
+        self.frames_out += len;
+        let skip = self.delay_left.min(len);
+        self.delay_left -= skip;
+        let take = (len - skip).min(limit.saturating_sub(self.emitted));
+        for i in skip..skip + take {
+            output.extend(self.buffer.iter().map(|ch| ch[i]));
+        }
+        self.emitted += take;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::PI;
+
+    fn sine(freq: f32, sample_rate: u32, secs: f32, amplitude: f32) -> Vec<f32> {
+        let frames = (sample_rate as f32 * secs) as usize;
+        (0..frames)
+            .map(|i| amplitude * (2.0 * PI * freq * i as f32 / sample_rate as f32).sin())
+            .collect()
+    }
+
+    fn interleave(left: &[f32], right: &[f32]) -> Vec<f32> {
+        left.iter().zip(right).flat_map(|(&l, &r)| [l, r]).collect()
+    }
+
+    /// Estimate the frequency of a mono signal from rising zero crossings,
+    /// ignoring the edges where the resampler's filter settles
+    fn estimate_frequency(samples: &[f32], sample_rate: u32) -> f32 {
+        let margin = samples.len() / 10;
+        let body = &samples[margin..samples.len() - margin];
+        let crossings: Vec<usize> = body
+            .windows(2)
+            .enumerate()
+            .filter(|(_, w)| w[0] < 0.0 && w[1] >= 0.0)
+            .map(|(i, _)| i)
+            .collect();
+        let periods = (crossings.len() - 1) as f32;
+        let span = (crossings[crossings.len() - 1] - crossings[0]) as f32;
+        periods * sample_rate as f32 / span
+    }
+
+    fn rms(samples: &[f32]) -> f32 {
+        let margin = samples.len() / 10;
+        let body = &samples[margin..samples.len() - margin];
+        (body.iter().map(|s| s * s).sum::<f32>() / body.len() as f32).sqrt()
+    }
+
+    #[test]
+    fn downsampling_preserves_pitch_and_duration() {
+        for (from, freq) in [(48_000, 440.0), (44_100, 1000.0), (22_050, 3000.0)] {
+            let input = sine(freq, from, 2.0, 0.5);
+            let output = resample(&input, 1, from, 16_000).unwrap();
+
+            assert_eq!(output.len(), 32_000, "{from} Hz duration");
+            let estimated = estimate_frequency(&output, 16_000);
+            assert!(
+                (estimated - freq).abs() < freq * 0.005,
+                "{from} Hz: expected {freq} Hz, got {estimated} Hz"
+            );
+            let level = rms(&output);
+            assert!(
+                (level - 0.5 / 2f32.sqrt()).abs() < 0.02,
+                "{from} Hz: unexpected level {level}"
+            );
+        }
+    }
+
+    #[test]
+    fn upsampling_preserves_pitch_and_duration() {
+        let input = sine(440.0, 8_000, 1.5, 0.5);
+        let output = resample(&input, 1, 8_000, 16_000).unwrap();
+
+        assert_eq!(output.len(), 24_000);
+        let estimated = estimate_frequency(&output, 16_000);
+        assert!((estimated - 440.0).abs() < 2.0, "got {estimated} Hz");
+    }
+
+    #[test]
+    fn frequencies_above_target_nyquist_are_filtered() {
+        // 12 kHz can't be represented at 16 kHz and must not alias down to 4 kHz
+        let input = sine(12_000.0, 48_000, 1.0, 0.5);
+        let output = resample(&input, 1, 48_000, 16_000).unwrap();
+
+        assert_eq!(output.len(), 16_000);
+        assert!(rms(&output) < 0.01, "aliasing level {}", rms(&output));
+    }
+
+    #[test]
+    fn stereo_resampling_keeps_channels_separate() {
+        let left = sine(440.0, 48_000, 1.0, 0.5);
+        let right = sine(880.0, 48_000, 1.0, 0.5);
+        let output = resample(&interleave(&left, &right), 2, 48_000, 16_000).unwrap();
+
+        assert_eq!(output.len(), 32_000);
+        let out_left: Vec<f32> = output.iter().step_by(2).copied().collect();
+        let out_right: Vec<f32> = output.iter().skip(1).step_by(2).copied().collect();
+        assert!((estimate_frequency(&out_left, 16_000) - 440.0).abs() < 2.0);
+        assert!((estimate_frequency(&out_right, 16_000) - 880.0).abs() < 4.0);
+    }
+
+    #[test]
+    fn downmix_averages_channels() {
+        let left = sine(440.0, 16_000, 1.0, 0.5);
+        let stereo = interleave(&left, &left);
+        let mono = downmix_to_mono(&stereo, 2);
+
+        assert_eq!(mono.len(), left.len());
+        assert!(mono.iter().zip(&left).all(|(a, b)| (a - b).abs() < 1e-6));
+
+        // Opposite-phase channels cancel out
+        let inverted: Vec<f32> = left.iter().map(|s| -s).collect();
+        let cancelled = downmix_to_mono(&interleave(&left, &inverted), 2);
+        assert!(cancelled.iter().all(|s| s.abs() < 1e-6));
+    }
+
+    #[test]
+    fn recording_converts_to_16k_mono() {
+        let left = sine(440.0, 48_000, 3.0, 0.5);
+        let right = sine(440.0, 48_000, 3.0, 0.5);
+        let recording = super::super::RecordingData {
+            samples: interleave(&left, &right),
+            spilled: None,
+            sample_rate: 48_000,
+            channels: 2,
+        };
+
+        let converted = recording.convert(Some(16_000), true).unwrap();
+        assert_eq!(converted.sample_rate(), 16_000);
+        assert_eq!(converted.channels(), 1);
+        assert_eq!(converted.samples().len(), 48_000);
+        let estimated = estimate_frequency(converted.samples(), 16_000);
+        assert!((estimated - 440.0).abs() < 2.0, "got {estimated} Hz");
+    }
+
+    #[test]
+    fn spilled_recording_converts_like_one_in_memory() {
+        let left = sine(440.0, 48_000, 3.0, 0.5);
+        let right = sine(880.0, 48_000, 3.0, 0.5);
+        let stereo = interleave(&left, &right);
+        let recording = |samples: &[f32], spilled| super::super::RecordingData {
+            samples: samples.to_vec(),
+            spilled,
+            sample_rate: 48_000,
+            channels: 2,
+        };
+
+        // Segments that don't line up with the resampler's input chunks
+        let split = 48_000 * 2 * 2;
+        let mut store = super::super::spill::SpillStore::create(&std::env::temp_dir()).unwrap();
+        for segment in stereo[..split].chunks(7_001 * 2) {
+            store.write(segment).unwrap();
+        }
+        let spilled = recording(&stereo[split..], Some(store));
+        let in_memory = recording(&stereo, None);
+
+        let spilled = spilled.convert(Some(16_000), true).unwrap();
+        let in_memory = in_memory.convert(Some(16_000), true).unwrap();
+        assert_eq!(spilled.samples().len(), 48_000);
+        assert_eq!(spilled.samples(), in_memory.samples());
+    }
+
+    #[test]
+    fn same_rate_is_passthrough() {
+        let input = sine(440.0, 16_000, 0.5, 0.5);
+        assert_eq!(resample(&input, 1, 16_000, 16_000).unwrap(), input);
+        assert!(resample(&[], 1, 48_000, 16_000).unwrap().is_empty());
+    }
+
+    #[test]
+    fn short_input_keeps_expected_length() {
+        let input = sine(440.0, 48_000, 0.01, 0.5);
+        let output = resample(&input, 1, 48_000, 16_000).unwrap();
+        assert_eq!(output.len(), 160);
+    }
+}
@@ -0,0 +1,293 @@
+//! Spilling long recordings to disk.
+//!
+//! A worker thread watches the recorder's sample buffer and, once it grows
+//! past the configured memory ceiling, moves its contents into a raw f32
+//! segment file in the cache directory. Segments are deleted when the
+//! recording is dropped.
+
+use anyhow::{Context, Result};
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+/// How often the worker checks the buffer size
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Where recordings are spilled to, under the cache dir
+pub(crate) fn default_root() -> PathBuf {
+    dirs::cache_dir()
+        .unwrap_or_else(std::env::temp_dir)
+        .join("whis")
+        .join("recordings")
+}
+
+/// Earlier part of a recording, stored as raw f32 segment files
+pub(crate) struct SpillStore {
+    dir: PathBuf,
+    segments: Vec<PathBuf>,
+    len: usize,
+}
+
+impl SpillStore {
+    /// Create an empty store in a fresh directory under `root`
+    pub fn create(root: &Path) -> Result<Self> {
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos();
+        let dir = root.join(format!("{}-{nanos}", std::process::id()));
+        fs::create_dir_all(&dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+        crate::verbose!("Spilling recording to {}", dir.display());
+
+        Ok(Self {
+            dir,
+            segments: Vec::new(),
+            len: 0,
+        })
+    }
+
+    /// Append samples as a new segment
+    pub fn write(&mut self, samples: &[f32]) -> Result<()> {
+        let path = self
+            .dir
+            .join(format!("segment-{}.f32", self.segments.len()));
+        let file =
+            File::create(&path).with_context(|| format!("Failed to create {}", path.display()))?;
+        let mut writer = BufWriter::new(file);
+        for sample in samples {
+            writer.write_all(&sample.to_le_bytes())?;
+        }
+        writer
+            .flush()
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+
+        crate::verbose!(
+            "Spilled {} samples to {}",
+            samples.len(),
+            path.file_name().unwrap_or_default().to_string_lossy()
+        );
+        self.segments.push(path);
+        self.len += samples.len();
+        Ok(())
+    }
+
+    /// Total number of samples on disk
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Read the segments back in order, one at a time
+    pub fn segments(&self) -> impl Iterator<Item = Result<Vec<f32>>> + '_ {
+        self.segments.iter().map(|path| {
+            let file =
+                File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+            let mut bytes = Vec::new();
+            BufReader::new(file)
+                .read_to_end(&mut bytes)
+                .with_context(|| format!("Failed to read {}", path.display()))?;
+            Ok(bytes
+                .chunks_exact(4)
+                .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+                .collect())
+        })
+    }
+}
+
+impl Drop for SpillStore {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// Background worker keeping the recorder's buffer under a memory ceiling
+pub(crate) struct SpillWorker {
+    stop: Arc<AtomicBool>,
+    handle: JoinHandle<Option<SpillStore>>,
+}
+
+impl SpillWorker {
+    /// Start watching `samples`, spilling once it holds `limit` samples
+    pub fn spawn(samples: Arc<Mutex<Vec<f32>>>, limit: usize) -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+        let stop_flag = stop.clone();
+        let handle =
+            std::thread::spawn(move || run(samples, limit.max(1), stop_flag, &default_root()));
+        Self { stop, handle }
+    }
+
+    /// Stop the worker and return what it spilled (None = everything is in memory)
+    pub fn finish(self) -> Option<SpillStore> {
+        self.stop.store(true, Ordering::Release);
+        self.handle.thread().unpark();
+        self.handle.join().unwrap_or(None)
+    }
+}
+
+/// Spill into a store under `root` until stopped or spilling fails
+fn run(
+    samples: Arc<Mutex<Vec<f32>>>,
+    limit: usize,
+    stop: Arc<AtomicBool>,
+    root: &Path,
+) -> Option<SpillStore> {
+    let mut store: Option<SpillStore> = None;
+
+    while !stop.load(Ordering::Acquire) {
+        if samples.lock().unwrap().len() >= limit {
+            // Swap out rather than copy so the audio callback is blocked only briefly
+            let taken = std::mem::take(&mut *samples.lock().unwrap());
+            let result = match store.as_mut() {
+                Some(store) => store.write(&taken),
+                None => SpillStore::create(root).and_then(|mut new| {
+                    new.write(&taken)?;
+                    store = Some(new);
+                    Ok(())
+                }),
+            };
+
+            if let Err(e) = result {
+                // Keep recording in memory rather than losing audio
+                eprintln!("Warning: failed to spill recording to disk, keeping it in memory: {e}");
+                samples.lock().unwrap().splice(0..0, taken);
+                break;
+            }
+        }
+        std::thread::park_timeout(POLL_INTERVAL);
+    }
+
+    store
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{AudioFormat, FinalizeOptions, RecordingData, RecordingOutput, VadOptions};
+    use super::*;
+
+    /// Scratch directory removed when dropped
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let dir = std::env::temp_dir().join(format!("whis-{name}-{}", std::process::id()));
+            fs::create_dir_all(&dir).unwrap();
+            Self(dir)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn ramp(range: std::ops::Range<usize>) -> Vec<f32> {
+        range.map(|i| i as f32 / 1000.0).collect()
+    }
+
+    #[test]
+    fn store_reads_back_what_was_written() {
+        let root = TempDir::new("spill-store");
+        let mut store = SpillStore::create(&root.0).unwrap();
+        store.write(&ramp(0..1000)).unwrap();
+        store.write(&[]).unwrap();
+        store.write(&ramp(1000..1500)).unwrap();
+
+        assert_eq!(store.len(), 1500);
+        let segments: Vec<_> = store.segments().map(Result::unwrap).collect();
+        assert_eq!(segments, [ramp(0..1000), Vec::new(), ramp(1000..1500)]);
+
+        let dir = store.dir.clone();
+        assert!(dir.exists());
+        drop(store);
+        assert!(!dir.exists());
+    }
+
+    #[test]
+    fn worker_keeps_samples_in_memory_when_spilling_fails() {
+        // A file where the store's directory should go makes spilling fail
+        let root = TempDir::new("spill-fail");
+        let blocker = root.0.join("recordings");
+        fs::write(&blocker, b"").unwrap();
+
+        let samples = Arc::new(Mutex::new(ramp(0..100)));
+        let stop = Arc::new(AtomicBool::new(false));
+        let store = run(samples.clone(), 10, stop, &blocker);
+
+        assert!(store.is_none());
+        assert_eq!(*samples.lock().unwrap(), ramp(0..100));
+    }
+
+    #[test]
+    fn spilled_recording_is_chunked_in_order() {
+        let root = TempDir::new("spill-finalize");
+        // 25 seconds of a 250 Hz tone, the first 20 of them on disk
+        let tone: Vec<f32> = (0..16_000 * 25)
+            .map(|i| (std::f32::consts::TAU * (i % 64) as f32 / 64.0).sin() * 0.14)
+            .collect();
+        let mut store = SpillStore::create(&root.0).unwrap();
+        for segment in tone[..16_000 * 20].chunks(16_000 * 4) {
+            store.write(segment).unwrap();
+        }
+        let recording = RecordingData {
+            samples: tone[16_000 * 20..].to_vec(),
+            spilled: Some(store),
+            sample_rate: 16_000,
+            channels: 1,
+        };
+
+        let options = FinalizeOptions {
+            format: AudioFormat::Wav,
+            sample_rate: None,
+            mono: true,
+            vad: Some(VadOptions {
+                threshold_db: Some(-40.0),
+                ..VadOptions::default()
+            }),
+            chunk_threshold_bytes: 1024,
+            chunk_duration_secs: 10,
+        };
+        let RecordingOutput::Chunked(chunks) = recording.finalize_with(&options).unwrap() else {
+            panic!("expected chunks");
+        };
+
+        // Chunks cover the whole recording in order, each overlapping the last
+        assert!(chunks.len() >= 3);
+        let mut end = 0.0;
+        for (i, chunk) in chunks.iter().enumerate() {
+            let reader = hound::WavReader::new(std::io::Cursor::new(&chunk.data[..])).unwrap();
+            assert_eq!(chunk.index, i);
+            assert_eq!(chunk.has_leading_overlap, i > 0);
+            if i > 0 {
+                assert!((end - chunk.start_secs - 2.0_f64).abs() < 1e-6);
+            }
+            end = chunk.start_secs + reader.duration() as f64 / 16_000.0;
+        }
+        assert!((end - 25.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn silent_spilled_recording_has_no_speech() {
+        let root = TempDir::new("spill-silent");
+        let mut store = SpillStore::create(&root.0).unwrap();
+        store.write(&vec![0.0; 16_000 * 15]).unwrap();
+        let recording = RecordingData {
+            samples: vec![0.0; 16_000 * 5],
+            spilled: Some(store),
+            sample_rate: 16_000,
+            channels: 1,
+        };
+
+        let options = FinalizeOptions {
+            chunk_duration_secs: 10,
+            ..FinalizeOptions::default()
+        };
+        assert!(matches!(
+            recording.finalize_with(&options).unwrap(),
+            RecordingOutput::NoSpeech
+        ));
+    }
+}
@@ -0,0 +1,330 @@
+//! Encoding chunks while a recording is still running.
+//!
+//! A worker thread moves captured samples out of the recorder's buffer,
+//! and whenever a full chunk has accumulated it cuts it at a pause, encodes
+//! it and sends it on. Transcription of early chunks can then run while the
+//! user is still talking, so stopping only leaves the last chunk to process.
+
+use anyhow::Result;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+use super::chunk::{self, MIN_CHUNK_SECS};
+use super::vad;
+use super::{AudioChunk, FinalizeOptions, RecordingData};
+use crate::error::WhisError;
+
+/// Encoded chunks of a streaming recording, in order, closed after the last one
+pub type ChunkReceiver = mpsc::UnboundedReceiver<Result<AudioChunk>>;
+pub(crate) type ChunkSender = mpsc::UnboundedSender<Result<AudioChunk>>;
+
+/// How often the worker collects new samples
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Background worker emitting chunks for one recording
+pub(crate) struct ChunkStreamer {
+    stop: Arc<AtomicBool>,
+    handle: JoinHandle<()>,
+}
+
+impl ChunkStreamer {
+    /// Start emitting chunks from the samples the recorder appends to `samples`
+    pub fn spawn(samples: Arc<Mutex<Vec<f32>>>, chunker: Chunker, sender: ChunkSender) -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+        let worker = Worker {
+            samples,
+            chunker,
+            sender,
+            stop: stop.clone(),
+        };
+        let handle = std::thread::spawn(move || worker.run());
+        Self { stop, handle }
+    }
+
+    /// Encode the remaining audio as the final chunk and close the stream
+    ///
+    /// Returns immediately; the final chunk is encoded in the background.
+    pub fn finish(self) {
+        drop(self);
+    }
+}
+
+impl Drop for ChunkStreamer {
+    fn drop(&mut self) {
+        // Never leave the worker polling a recorder that no longer exists
+        self.stop.store(true, Ordering::Release);
+        self.handle.thread().unpark();
+    }
+}
+
+struct Worker {
+    samples: Arc<Mutex<Vec<f32>>>,
+    chunker: Chunker,
+    sender: ChunkSender,
+    stop: Arc<AtomicBool>,
+}
+
+impl Worker {
+    fn run(mut self) {
+        let mut captured = false;
+
+        loop {
+            let stopping = self.stop.load(Ordering::Acquire);
+            // Swap out rather than copy so the audio callback is blocked only briefly
+            let samples = std::mem::take(&mut *self.samples.lock().unwrap());
+            captured |= !samples.is_empty();
+            self.chunker.push(samples);
+            if stopping {
+                break;
+            }
+
+            if !self.chunker.is_full() {
+                std::thread::park_timeout(POLL_INTERVAL);
+                continue;
+            }
+            match self.chunker.take_ready() {
+                Ok(chunks) => {
+                    if !chunks.into_iter().all(|chunk| send(&self.sender, chunk)) {
+                        return;
+                    }
+                }
+                Err(e) => {
+                    let _ = self.sender.send(Err(e));
+                    return;
+                }
+            }
+            std::thread::park_timeout(POLL_INTERVAL);
+        }
+
+        if !captured {
+            let _ = self.sender.send(Err(WhisError::EmptyAudio.into()));
+            return;
+        }
+        match self.chunker.finish() {
+            Ok(chunks) => {
+                let sent = chunks.len();
+                if chunks.into_iter().all(|chunk| send(&self.sender, chunk)) {
+                    crate::verbose!("Streaming finished ({sent} final chunk(s))");
+                }
+            }
+            Err(e) => {
+                let _ = self.sender.send(Err(e));
+            }
+        }
+    }
+}
+
+/// Send one chunk; returns false when nobody is waiting for the transcript anymore
+fn send(sender: &ChunkSender, chunk: AudioChunk) -> bool {
+    crate::verbose!("Chunk {} ready: {} bytes", chunk.index, chunk.data.len());
+    sender.send(Ok(chunk)).is_ok()
+}
+
+/// Cuts a growing recording into encoded chunks at pauses
+///
+/// Used both while recording and when finalizing a recording that was
+/// spilled to disk, so neither ever holds more than about one chunk.
+pub(crate) struct Chunker {
+    sample_rate: u32,
+    channels: u16,
+    options: FinalizeOptions,
+    chunk_secs: usize,
+    pending: Vec<f32>,
+    /// Samples before `pending`, already cut into chunks
+    consumed: usize,
+    index: usize,
+    has_leading_overlap: bool,
+}
+
+impl Chunker {
+    /// `memory_limit` (in samples) further shortens chunks so the pending audio stays under it
+    pub fn new(
+        sample_rate: u32,
+        channels: u16,
+        options: FinalizeOptions,
+        memory_limit: Option<usize>,
+    ) -> Self {
+        let rate = options.sample_rate.unwrap_or(sample_rate) as usize;
+        let upload_channels = if options.mono { 1 } else { channels as usize };
+        // Unlike finalize we can't measure the encoded size up front, so use
+        // 16-bit PCM as an upper bound for every format
+        let bytes_per_sec = (rate * upload_channels * 2).max(1);
+        let mut max_secs = options.chunk_duration_secs;
+        if let Some(limit) = memory_limit {
+            max_secs = max_secs.min(limit / (sample_rate as usize * channels as usize).max(1));
+        }
+        let chunk_secs = (options.chunk_threshold_bytes * 9 / 10 / bytes_per_sec)
+            .clamp(MIN_CHUNK_SECS, max_secs.max(MIN_CHUNK_SECS));
+        crate::verbose!("Chunks of up to {chunk_secs}s");
+
+        Self {
+            sample_rate,
+            channels,
+            options,
+            chunk_secs,
+            pending: Vec::new(),
+            consumed: 0,
+            index: 0,
+            has_leading_overlap: false,
+        }
+    }
+
+    /// Append recorded samples
+    pub fn push(&mut self, mut samples: Vec<f32>) {
+        if self.pending.is_empty() {
+            self.pending = samples;
+        } else {
+            self.pending.append(&mut samples);
+        }
+    }
+
+    /// Whether enough audio is pending to cut at least one chunk
+    pub fn is_full(&self) -> bool {
+        self.pending.len() > self.chunk_secs * self.sample_rate as usize * self.channels as usize
+    }
+
+    /// Cut and encode every complete chunk (chunks without speech are skipped)
+    pub fn take_ready(&mut self) -> Result<Vec<AudioChunk>> {
+        let mut chunks = Vec::new();
+
+        while self.is_full() {
+            let (frame_len, levels) =
+                vad::frame_levels(&self.pending, self.sample_rate, self.channels);
+            let threshold = self
+                .options
+                .vad
+                .and_then(|vad| vad.threshold_db)
+                .unwrap_or_else(|| vad::adaptive_threshold(&levels));
+            let Some(boundary) = chunk::next_boundary(&levels, self.chunk_secs, threshold) else {
+                break;
+            };
+
+            let samples = self.pending[..boundary.end * frame_len].to_vec();
+            let start = self.consumed;
+            self.pending.drain(..boundary.next_start * frame_len);
+            self.consumed += boundary.next_start * frame_len;
+            chunks.extend(self.encode(samples, start)?);
+            self.has_leading_overlap = boundary.overlap;
+        }
+
+        Ok(chunks)
+    }
+
+    /// Cut the remaining complete chunks and encode the rest as the last one
+    pub fn finish(mut self) -> Result<Vec<AudioChunk>> {
+        let mut chunks = self.take_ready()?;
+        if !self.pending.is_empty() {
+            let samples = std::mem::take(&mut self.pending);
+            let start = self.consumed;
+            chunks.extend(self.encode(samples, start)?);
+        }
+        Ok(chunks)
+    }
+
+    /// `start` is the position of the chunk's first sample in the recording
+    fn encode(&mut self, samples: Vec<f32>, start: usize) -> Result<Option<AudioChunk>> {
+        let start_secs = start as f64 / (self.sample_rate as f64 * self.channels as f64);
+        let recording = RecordingData {
+            samples,
+            spilled: None,
+            sample_rate: self.sample_rate,
+            channels: self.channels,
+        };
+
+        let chunk = recording.encode_chunk(
+            &self.options,
+            self.index,
+            self.has_leading_overlap,
+            start_secs,
+        )?;
+        match &chunk {
+            Some(_) => self.index += 1,
+            None => crate::verbose!("Skipping chunk without speech"),
+        }
+        Ok(chunk)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{AudioFormat, VadOptions};
+    use super::*;
+
+    const RATE: u32 = 16_000;
+
+    /// 250 Hz tone at about -20 dBFS; every 20 ms frame holds five whole
+    /// periods, so all frames have the same level
+    fn tone(secs: f64) -> Vec<f32> {
+        let period = (RATE / 250) as usize;
+        (0..(secs * RATE as f64) as usize)
+            .map(|i| (std::f32::consts::TAU * (i % period) as f32 / period as f32).sin() * 0.14)
+            .collect()
+    }
+
+    /// Chunker for 10 second chunks; the tone always counts as speech
+    fn chunker(min_speech_ms: u32) -> Chunker {
+        let vad = VadOptions {
+            threshold_db: Some(-40.0),
+            min_speech_ms,
+            ..VadOptions::default()
+        };
+        let options = FinalizeOptions {
+            format: AudioFormat::Wav,
+            sample_rate: None,
+            mono: true,
+            vad: Some(vad),
+            chunk_threshold_bytes: usize::MAX / 10,
+            chunk_duration_secs: 10,
+        };
+        Chunker::new(RATE, 1, options, None)
+    }
+
+    /// Feed `samples` in recorder-sized pieces, collecting chunks as they become ready
+    fn run(mut chunker: Chunker, samples: Vec<f32>) -> Vec<AudioChunk> {
+        let mut chunks = Vec::new();
+        for piece in samples.chunks(RATE as usize / 2) {
+            chunker.push(piece.to_vec());
+            chunks.extend(chunker.take_ready().unwrap());
+        }
+        chunks.extend(chunker.finish().unwrap());
+        chunks
+    }
+
+    fn duration_secs(chunk: &AudioChunk) -> f64 {
+        let reader = hound::WavReader::new(std::io::Cursor::new(&chunk.data[..])).unwrap();
+        reader.duration() as f64 / reader.spec().sample_rate as f64
+    }
+
+    #[test]
+    fn emits_contiguous_chunks_with_overlap() {
+        let chunks = run(chunker(150), tone(25.0));
+
+        assert!(chunks.len() >= 3);
+        assert_eq!(chunks[0].start_secs, 0.0);
+        assert!(!chunks[0].has_leading_overlap);
+        for (i, pair) in chunks.windows(2).enumerate() {
+            assert_eq!(pair[1].index, i + 1);
+            assert!(pair[1].has_leading_overlap);
+            let overlap = pair[0].start_secs + duration_secs(&pair[0]) - pair[1].start_secs;
+            assert!((overlap - chunk::CHUNK_OVERLAP_SECS as f64).abs() < 1e-6);
+        }
+
+        // finish() flushes the tail up to the end of the recording
+        let last = chunks.last().unwrap();
+        assert!((last.start_secs + duration_secs(last) - 25.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn keeps_the_overlap_flag_after_a_skipped_chunk() {
+        // Only the final chunk holds enough continuous "speech" to count
+        let chunks = run(chunker(9000), tone(15.5));
+
+        assert_eq!(chunks.len(), 1);
+        assert_eq!(chunks[0].index, 0);
+        assert!(chunks[0].has_leading_overlap);
+        assert!(chunks[0].start_secs > 0.0);
+    }
+}
@@ -0,0 +1,293 @@
+//! Energy-based voice activity detection.
+//!
+//! Frames are classified as voiced when their RMS level is above a threshold.
+//! The threshold is either fixed or derived from the recording's own noise
+//! floor, so the same settings work for quiet rooms and noisy laptops alike.
+//! Short voiced bursts (clicks, key presses) are ignored.
+
+use std::ops::Range;
+
+/// Analysis frame length
+pub(crate) const FRAME_MS: usize = 20;
+/// Voiced frames closer than this are treated as one utterance
+const HANGOVER_MS: usize = 300;
+/// Adaptive threshold sits this far above the estimated noise floor
+const NOISE_MARGIN_DB: f32 = 12.0;
+/// In a noisy room the margin shrinks down to this, rather than the
+/// threshold climbing past quiet speech
+const MIN_NOISE_MARGIN_DB: f32 = 6.0;
+/// Bounds for the adaptive threshold (the upper one is exceeded only to keep
+/// [`MIN_NOISE_MARGIN_DB`] above a very loud noise floor)
+const MIN_ADAPTIVE_THRESHOLD_DB: f32 = -60.0;
+const MAX_ADAPTIVE_THRESHOLD_DB: f32 = -40.0;
+/// Percentile of frame levels used as the noise floor estimate
+const NOISE_FLOOR_PERCENTILE: f32 = 0.1;
+
+/// Voice activity detection parameters
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VadOptions {
+    /// Level in dBFS above which a frame counts as speech (None = adaptive)
+    pub threshold_db: Option<f32>,
+    /// Minimum voiced duration for an utterance to count as speech
+    pub min_speech_ms: u32,
+    /// Silence kept before and after each utterance
+    pub padding_ms: u32,
+    /// Longer pauses between utterances are shortened to this
+    pub max_pause_ms: u32,
+}
+
+impl Default for VadOptions {
+    fn default() -> Self {
+        Self {
+            threshold_db: None,
+            min_speech_ms: 150,
+            padding_ms: 250,
+            max_pause_ms: 1000,
+        }
+    }
+}
+
+/// RMS level of a frame in dBFS
+fn level_db(frame: &[f32]) -> f32 {
+    let mean_square = frame.iter().map(|s| s * s).sum::<f32>() / frame.len().max(1) as f32;
+    10.0 * mean_square.max(1e-12).log10()
+}
+
+/// Split interleaved samples into [`FRAME_MS`] frames and measure each one
+///
+/// Returns the frame length in interleaved samples and the level of every frame.
+pub(crate) fn frame_levels(samples: &[f32], sample_rate: u32, channels: u16) -> (usize, Vec<f32>) {
+    let channels = channels.max(1) as usize;
+    let frame_len = (sample_rate as usize * FRAME_MS / 1000).max(1) * channels;
+    (frame_len, samples.chunks(frame_len).map(level_db).collect())
+}
+
+/// Speech threshold derived from the quietest frames of a recording
+pub(crate) fn adaptive_threshold(levels: &[f32]) -> f32 {
+    let mut sorted = levels.to_vec();
+    sorted.sort_by(f32::total_cmp);
+    let noise_floor = sorted
+        .get(((sorted.len().max(1) - 1) as f32 * NOISE_FLOOR_PERCENTILE) as usize)
+        .copied()
+        .unwrap_or(MIN_ADAPTIVE_THRESHOLD_DB);
+    (noise_floor + NOISE_MARGIN_DB)
+        .clamp(MIN_ADAPTIVE_THRESHOLD_DB, MAX_ADAPTIVE_THRESHOLD_DB)
+        .max(noise_floor + MIN_NOISE_MARGIN_DB)
+}
+
+/// Find the utterances in a recording
+///
+/// Returns ranges of interleaved sample indices (always aligned to whole
+/// channel frames), without padding. An empty result means no speech.
+pub fn detect_speech(
+    samples: &[f32],
+    sample_rate: u32,
+    channels: u16,
+    options: &VadOptions,
+) -> Vec<Range<usize>> {
+    let (frame_len, levels) = frame_levels(samples, sample_rate, channels);
+    if levels.is_empty() {
+        return Vec::new();
+    }
+
+    let threshold = options
+        .threshold_db
+        .unwrap_or_else(|| adaptive_threshold(&levels));
+    crate::verbose!("VAD threshold: {threshold:.1} dBFS");
+
+    let hangover_frames = HANGOVER_MS / FRAME_MS;
+    let min_voiced_frames = (options.min_speech_ms as usize).div_ceil(FRAME_MS).max(1);
+
+    // Group voiced frames into utterances, bridging gaps up to the hangover
+    let mut utterances: Vec<(Range<usize>, usize)> = Vec::new();
+    for (i, _) in levels.iter().enumerate().filter(|(_, l)| **l > threshold) {
+        match utterances.last_mut() {
+            Some((frames, voiced)) if i - frames.end <= hangover_frames => {
+                frames.end = i + 1;
+                *voiced += 1;
+            }
+            _ => utterances.push((i..i + 1, 1)),
+        }
+    }
+
+    utterances
+        .into_iter()
+        .filter(|(_, voiced)| *voiced >= min_voiced_frames)
+        .map(|(frames, _)| frames.start * frame_len..(frames.end * frame_len).min(samples.len()))
+        .collect()
+}
+
+/// Keep only the speech in a recording
+///
+/// Silence before the first and after the last utterance is trimmed (leaving
+/// some padding), and pauses between utterances are shortened to at most
+/// `max_pause_ms`. Returns None when no speech was detected.
+pub fn trim_silence(
+    samples: &[f32],
+    sample_rate: u32,
+    channels: u16,
+    options: &VadOptions,
+) -> Option<Vec<f32>> {
+    let speech = detect_speech(samples, sample_rate, channels, options);
+    if speech.is_empty() {
+        return None;
+    }
+
+    let ms_to_samples =
+        |ms: u32| ms as usize * sample_rate as usize / 1000 * channels.max(1) as usize;
+    let padding = ms_to_samples(options.padding_ms);
+    let max_pause = ms_to_samples(options.max_pause_ms).max(2 * padding);
+
+    // Pad each utterance and merge those that now touch
+    let mut regions: Vec<Range<usize>> = Vec::with_capacity(speech.len());
+    for range in speech {
+        let start = range.start.saturating_sub(padding);
+        let end = (range.end + padding).min(samples.len());
+        match regions.last_mut() {
+            Some(last) if start <= last.end => last.end = end,
+            _ => regions.push(start..end),
+        }
+    }
+
+    let mut output = Vec::with_capacity(regions.iter().map(|r| r.len()).sum());
+    let mut previous_end = None;
+    for region in regions {
+        // Shorten long pauses to max_pause, keeping equal silence on each side
+        if let Some(previous_end) = previous_end {
+            let gap = region.start - previous_end;
+            if gap <= max_pause - 2 * padding {
+                output.extend_from_slice(&samples[previous_end..region.start]);
+            } else {
+                let extra = (max_pause - 2 * padding) / 2;
+                let extra = extra - extra % channels.max(1) as usize;
+                output.extend_from_slice(&samples[previous_end..previous_end + extra]);
+                output.extend_from_slice(&samples[region.start - extra..region.start]);
+            }
+        }
+        output.extend_from_slice(&samples[region.clone()]);
+        previous_end = Some(region.end);
+    }
+
+    Some(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RATE: u32 = 16_000;
+
+    fn samples(ms: usize) -> usize {
+        ms * RATE as usize / 1000
+    }
+
+    /// Amplitude of a signal with the given RMS level; `peak_to_rms` is
+    /// sqrt(2) for a sine and sqrt(3) for uniform noise
+    fn amplitude(level_db: f32, peak_to_rms: f32) -> f32 {
+        10f32.powf(level_db / 20.0) * peak_to_rms
+    }
+
+    fn tone(ms: usize, level_db: f32) -> Vec<f32> {
+        let amplitude = amplitude(level_db, std::f32::consts::SQRT_2);
+        (0..samples(ms))
+            .map(|i| {
+                (i as f32 * 2.0 * std::f32::consts::PI * 220.0 / RATE as f32).sin() * amplitude
+            })
+            .collect()
+    }
+
+    /// Deterministic uniform noise
+    fn noise(ms: usize, level_db: f32) -> Vec<f32> {
+        let amplitude = amplitude(level_db, 3f32.sqrt());
+        let mut state = 0x9e37_79b9_u32;
+        (0..samples(ms))
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                (state as f32 / u32::MAX as f32 * 2.0 - 1.0) * amplitude
+            })
+            .collect()
+    }
+
+    fn mix(a: &[f32], b: &[f32]) -> Vec<f32> {
+        a.iter().zip(b).map(|(a, b)| a + b).collect()
+    }
+
+    #[test]
+    fn finds_no_speech_in_silence_or_noise() {
+        let options = VadOptions::default();
+        let silence = vec![0.0; samples(3000)];
+        assert!(detect_speech(&silence, RATE, 1, &options).is_empty());
+        assert!(trim_silence(&silence, RATE, 1, &options).is_none());
+
+        let noise = noise(3000, -45.0);
+        assert!(detect_speech(&noise, RATE, 1, &options).is_empty());
+    }
+
+    #[test]
+    fn finds_tone_bursts_and_ignores_clicks() {
+        let recording = [
+            vec![0.0; samples(1000)],
+            tone(500, -20.0),
+            vec![0.0; samples(1000)],
+            // Shorter than min_speech_ms
+            tone(60, -10.0),
+            vec![0.0; samples(1000)],
+            tone(800, -20.0),
+            vec![0.0; samples(1000)],
+        ]
+        .concat();
+
+        let speech = detect_speech(&recording, RATE, 1, &VadOptions::default());
+        let speech_ms: Vec<_> = speech
+            .iter()
+            .map(|range| range.start * 1000 / RATE as usize..range.end * 1000 / RATE as usize)
+            .collect();
+        assert_eq!(speech_ms, [1000..1500, 3560..4360]);
+    }
+
+    #[test]
+    fn keeps_quiet_speech_in_a_noisy_room() {
+        let background = noise(4000, -45.0);
+        let speech = [
+            vec![0.0; samples(1500)],
+            tone(1000, -38.0),
+            vec![0.0; samples(1500)],
+        ]
+        .concat();
+        let recording = mix(&background, &speech);
+
+        let found = detect_speech(&recording, RATE, 1, &VadOptions::default());
+        assert_eq!(found.len(), 1);
+        assert!(found[0].start.abs_diff(samples(1500)) <= samples(FRAME_MS));
+        assert!(found[0].end.abs_diff(samples(2500)) <= samples(FRAME_MS));
+    }
+
+    #[test]
+    fn collapses_long_pauses() {
+        let options = VadOptions::default();
+        let recording = [
+            vec![0.0; samples(1000)],
+            tone(500, -20.0),
+            vec![0.0; samples(5000)],
+            tone(500, -20.0),
+            vec![0.0; samples(1000)],
+        ]
+        .concat();
+
+        // Padding before and after, the pause shortened to max_pause_ms
+        let trimmed = trim_silence(&recording, RATE, 1, &options).unwrap();
+        let padding = options.padding_ms as usize;
+        let expected = padding + 500 + options.max_pause_ms as usize + 500 + padding;
+        assert_eq!(trimmed.len(), samples(expected));
+
+        // Short pauses are kept as they are
+        let options = VadOptions {
+            max_pause_ms: 10_000,
+            ..options
+        };
+        let trimmed = trim_silence(&recording, RATE, 1, &options).unwrap();
+        assert_eq!(trimmed.len(), samples(padding + 500 + 5000 + 500 + padding));
+    }
+}
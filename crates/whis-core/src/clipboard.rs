@@ -3,6 +3,7 @@ use arboard::Clipboard;
 use std::io::Write;
 use std::process::{Command, Stdio};
 
+use crate::error::WhisError;
 use crate::verbose;
 
 /// Check if running inside a Flatpak sandbox
@@ -77,7 +78,19 @@ fn copy_via_xclip(text: &str) -> Result<()> {
     Ok(())
 }
 
+/// Copy `text` to the system clipboard
+///
+/// Failures are reported as [`WhisError::Clipboard`].
 pub fn copy_to_clipboard(text: &str) -> Result<()> {
+    copy(text).map_err(|e| {
+        WhisError::Clipboard {
+            message: format!("{e:#}"),
+        }
+        .into()
+    })
+}
+
+fn copy(text: &str) -> Result<()> {
     crate::verbose!("Copying to clipboard: {} chars", text.len());
     crate::verbose!("Session type: {}", session_type());
     crate::verbose!("Is Flatpak: {}", is_flatpak());
@@ -8,6 +8,10 @@ pub enum TranscriptionProvider {
     #[default]
     OpenAI,
     Mistral,
+    /// Any server speaking the OpenAI `/v1/audio/transcriptions` protocol
+    Custom,
+    /// Offline whisper.cpp model (requires the `local-whisper` feature)
+    Local,
 }
 
 impl fmt::Display for TranscriptionProvider {
@@ -15,6 +19,8 @@ impl fmt::Display for TranscriptionProvider {
         match self {
             TranscriptionProvider::OpenAI => write!(f, "openai"),
             TranscriptionProvider::Mistral => write!(f, "mistral"),
+            TranscriptionProvider::Custom => write!(f, "custom"),
+            TranscriptionProvider::Local => write!(f, "local"),
         }
     }
 }
@@ -26,7 +32,12 @@ impl std::str::FromStr for TranscriptionProvider {
         match s.to_lowercase().as_str() {
             "openai" => Ok(TranscriptionProvider::OpenAI),
             "mistral" => Ok(TranscriptionProvider::Mistral),
-            _ => Err(format!("Unknown provider: {}. Use 'openai' or 'mistral'", s)),
+            "custom" => Ok(TranscriptionProvider::Custom),
+            "local" => Ok(TranscriptionProvider::Local),
+            _ => Err(format!(
+                "Unknown provider: {}. Use 'openai', 'mistral', 'custom' or 'local'",
+                s
+            )),
         }
     }
 }
@@ -0,0 +1,327 @@
+//! Failures that front-ends handle specially.
+//!
+//! Functions in this crate return [`anyhow::Result`], and most errors are only
+//! meant to be shown. The ones a user can do something about, or that a
+//! caller may want to retry, are raised as a [`WhisError`] at their source;
+//! context added on the way up doesn't hide it from [`WhisError::find`].
+
+use reqwest::StatusCode;
+use reqwest::header::HeaderMap;
+use std::time::Duration;
+
+/// A failure with a known cause
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum WhisError {
+    /// There is no microphone, or the configured one isn't connected
+    NoInputDevice {
+        /// The configured device, if one was asked for
+        requested: Option<String>,
+    },
+    /// An external program needed to encode or decode audio isn't installed
+    EncoderMissing { program: &'static str },
+    /// The API key was rejected (HTTP 401 or 403)
+    Auth {
+        provider: String,
+        status: StatusCode,
+        message: String,
+    },
+    /// Too many requests, or the account is out of credit (HTTP 429)
+    RateLimited {
+        provider: String,
+        message: String,
+        /// How long the server asked to wait before trying again
+        retry_after: Option<Duration>,
+        /// The quota is used up, so waiting won't help
+        quota_exhausted: bool,
+    },
+    /// The provider's server failed or timed out (HTTP 408 or 5xx)
+    ProviderUnavailable {
+        provider: String,
+        status: StatusCode,
+        message: String,
+        retry_after: Option<Duration>,
+    },
+    /// The provider refused the request, e.g. an unsupported or oversized file
+    ProviderRejected {
+        provider: String,
+        status: StatusCode,
+        message: String,
+    },
+    /// The request never got an answer (no connection, timeout, dropped upload)
+    Network {
+        provider: String,
+        source: reqwest::Error,
+    },
+    /// Translation was asked for, but the provider only transcribes
+    TranslationUnsupported { provider: String },
+    /// Nothing was recorded, or the file has no audio
+    EmptyAudio,
+    /// The transcript couldn't be copied to the clipboard
+    Clipboard { message: String },
+    /// The user cancelled the recording or transcription
+    Cancelled,
+}
+
+impl WhisError {
+    /// Classify an error response from a transcription API
+    pub(crate) fn from_response(
+        provider: &str,
+        status: StatusCode,
+        headers: &HeaderMap,
+        message: String,
+    ) -> Self {
+        let provider = provider.to_string();
+        match status {
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => WhisError::Auth {
+                provider,
+                status,
+                message,
+            },
+            StatusCode::TOO_MANY_REQUESTS => WhisError::RateLimited {
+                provider,
+                // OpenAI also answers 429 when the account is out of credit
+                quota_exhausted: message.contains("insufficient_quota"),
+                retry_after: retry_after(headers),
+                message,
+            },
+            StatusCode::NOT_IMPLEMENTED | StatusCode::HTTP_VERSION_NOT_SUPPORTED => {
+                WhisError::ProviderRejected {
+                    provider,
+                    status,
+                    message,
+                }
+            }
+            status if status == StatusCode::REQUEST_TIMEOUT || status.is_server_error() => {
+                WhisError::ProviderUnavailable {
+                    provider,
+                    status,
+                    message,
+                    retry_after: retry_after(headers),
+                }
+            }
+            status => WhisError::ProviderRejected {
+                provider,
+                status,
+                message,
+            },
+        }
+    }
+
+    /// The first [`WhisError`] in an error's chain of causes
+    pub fn find(error: &anyhow::Error) -> Option<&WhisError> {
+        error.chain().find_map(|cause| cause.downcast_ref())
+    }
+
+    /// Whether the same request may succeed later
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            WhisError::RateLimited {
+                quota_exhausted, ..
+            } => !quota_exhausted,
+            WhisError::ProviderUnavailable { .. } | WhisError::Network { .. } => true,
+            _ => false,
+        }
+    }
+
+    /// How long the server asked to wait before trying again
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            WhisError::RateLimited { retry_after, .. }
+            | WhisError::ProviderUnavailable { retry_after, .. } => *retry_after,
+            _ => None,
+        }
+    }
+
+    /// Short stable name for the kind of failure, e.g. for a UI to match on
+    pub fn kind(&self) -> &'static str {
+        match self {
+            WhisError::NoInputDevice { .. } => "no_input_device",
+            WhisError::EncoderMissing { .. } => "encoder_missing",
+            WhisError::Auth { .. } => "auth",
+            WhisError::RateLimited { .. } => "rate_limited",
+            WhisError::ProviderUnavailable { .. } => "provider_unavailable",
+            WhisError::ProviderRejected { .. } => "provider_rejected",
+            WhisError::Network { .. } => "network",
+            WhisError::TranslationUnsupported { .. } => "translation_unsupported",
+            WhisError::EmptyAudio => "empty_audio",
+            WhisError::Clipboard { .. } => "clipboard",
+            WhisError::Cancelled => "cancelled",
+        }
+    }
+
+    /// What the user can do about it, if anything
+    pub fn hint(&self) -> Option<&'static str> {
+        match self {
+            WhisError::NoInputDevice { requested: None } => {
+                Some("Connect a microphone and check that it isn't disabled.")
+            }
+            WhisError::NoInputDevice { requested: Some(_) } => {
+                Some("Connect the device or choose another input device.")
+            }
+            WhisError::EncoderMissing { .. } => {
+                Some("Install FFmpeg and make sure it is on your PATH.")
+            }
+            WhisError::Auth { .. } => Some("Check that your API key is correct and still valid."),
+            WhisError::RateLimited {
+                quota_exhausted: true,
+                ..
+            } => Some("Your account is out of credit; check your provider's billing page."),
+            WhisError::RateLimited { .. } => Some("Wait a moment and try again."),
+            WhisError::ProviderUnavailable { .. } => {
+                Some("The provider is having trouble; try again later or switch providers.")
+            }
+            WhisError::Network { .. } => Some("Check your internet connection."),
+            WhisError::TranslationUnsupported { .. } => Some(
+                "Translate with OpenAI, a local model or a custom server that serves /audio/translations.",
+            ),
+            WhisError::EmptyAudio => {
+                Some("Check that the microphone is not muted, or that the file contains sound.")
+            }
+            WhisError::ProviderRejected { .. }
+            | WhisError::Clipboard { .. }
+            | WhisError::Cancelled => None,
+        }
+    }
+}
+
+impl std::fmt::Display for WhisError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WhisError::NoInputDevice { requested: None } => write!(f, "No input device available"),
+            WhisError::NoInputDevice {
+                requested: Some(name),
+            } => write!(
+                f,
+                "Input device '{name}' not found. Run `whis devices` to list available devices"
+            ),
+            WhisError::EncoderMissing { program } => write!(
+                f,
+                "Failed to execute {program}. Make sure {program} is installed."
+            ),
+            WhisError::Auth {
+                provider,
+                status,
+                message,
+            }
+            | WhisError::ProviderUnavailable {
+                provider,
+                status,
+                message,
+                ..
+            }
+            | WhisError::ProviderRejected {
+                provider,
+                status,
+                message,
+            } => write!(f, "{provider} API error ({status}): {message}"),
+            WhisError::RateLimited {
+                provider, message, ..
+            } => write!(
+                f,
+                "{provider} API error ({}): {message}",
+                StatusCode::TOO_MANY_REQUESTS
+            ),
+            WhisError::Network { provider, .. } => {
+                write!(f, "Failed to send request to {provider} API")
+            }
+            WhisError::TranslationUnsupported { provider } => {
+                write!(f, "{provider} can't translate, only transcribe")
+            }
+            WhisError::EmptyAudio => write!(f, "No audio data"),
+            WhisError::Clipboard { message } => write!(f, "Failed to copy to clipboard: {message}"),
+            WhisError::Cancelled => write!(f, "Cancelled"),
+        }
+    }
+}
+
+impl std::error::Error for WhisError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            WhisError::Network { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+/// Read `retry-after-ms` (sent by OpenAI) or `Retry-After` in seconds
+///
+/// The HTTP-date form of `Retry-After` is ignored in favour of the backoff.
+fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let header = |name: &str| {
+        headers
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.trim().parse::<f64>().ok())
+            .filter(|value| value.is_finite() && *value >= 0.0)
+    };
+    header("retry-after-ms")
+        .map(|millis| Duration::from_secs_f64(millis / 1000.0))
+        .or_else(|| header("retry-after").map(Duration::from_secs_f64))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+    use reqwest::header::HeaderValue;
+
+    fn response(status: StatusCode, message: &str) -> WhisError {
+        WhisError::from_response("Test", status, &HeaderMap::new(), message.to_string())
+    }
+
+    #[test]
+    fn classifies_responses() {
+        let auth = response(StatusCode::UNAUTHORIZED, "bad key");
+        assert_eq!(auth.kind(), "auth");
+        assert!(!auth.is_retryable());
+        assert_eq!(
+            auth.to_string(),
+            "Test API error (401 Unauthorized): bad key"
+        );
+
+        assert!(response(StatusCode::TOO_MANY_REQUESTS, "slow down").is_retryable());
+        let quota = response(
+            StatusCode::TOO_MANY_REQUESTS,
+            r#"{"code":"insufficient_quota"}"#,
+        );
+        assert!(!quota.is_retryable());
+
+        assert!(response(StatusCode::BAD_GATEWAY, "").is_retryable());
+        assert!(response(StatusCode::REQUEST_TIMEOUT, "").is_retryable());
+        assert!(!response(StatusCode::NOT_IMPLEMENTED, "").is_retryable());
+        assert_eq!(
+            response(StatusCode::PAYLOAD_TOO_LARGE, "").kind(),
+            "provider_rejected"
+        );
+    }
+
+    #[test]
+    fn reads_retry_after() {
+        let mut headers = HeaderMap::new();
+        headers.insert("retry-after", HeaderValue::from_static("2"));
+        assert_eq!(retry_after(&headers), Some(Duration::from_secs(2)));
+        headers.insert("retry-after-ms", HeaderValue::from_static("1500"));
+        assert_eq!(retry_after(&headers), Some(Duration::from_millis(1500)));
+
+        let error = WhisError::from_response(
+            "Test",
+            StatusCode::SERVICE_UNAVAILABLE,
+            &headers,
+            String::new(),
+        );
+        assert_eq!(error.retry_after(), Some(Duration::from_millis(1500)));
+    }
+
+    #[test]
+    fn found_behind_context() {
+        let error = anyhow::Error::from(WhisError::EmptyAudio).context("Failed to decode a.wav");
+        assert!(matches!(
+            WhisError::find(&error),
+            Some(WhisError::EmptyAudio)
+        ));
+
+        let plain: anyhow::Result<()> = Err(anyhow::anyhow!("other")).context("outer");
+        assert!(WhisError::find(&plain.unwrap_err()).is_none());
+    }
+}
@@ -0,0 +1,366 @@
+//! Rendering transcripts as plain text, subtitles or JSON.
+//!
+//! Subtitles are built from the transcript's segments, split into captions
+//! that respect a line length, a line count and a maximum duration. Word
+//! timestamps place the splits exactly when the backend returned them;
+//! otherwise a segment's time is shared out by the length of its words.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+
+use crate::transcript::{Segment, Transcript, Word};
+
+/// Pause after which a new caption starts even if the current one has room
+const MAX_CAPTION_GAP_SECS: f64 = 1.0;
+/// Shortest time a caption stays on screen
+const MIN_CAPTION_SECS: f64 = 0.5;
+
+/// Output format for a transcript
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TranscriptFormat {
+    /// Plain text (default)
+    #[default]
+    Text,
+    /// SubRip subtitles
+    Srt,
+    /// WebVTT subtitles
+    Vtt,
+    /// The full [`Transcript`] with segments and words
+    Json,
+}
+
+impl TranscriptFormat {
+    /// File extension for this format (without the dot)
+    pub fn extension(&self) -> &'static str {
+        match self {
+            TranscriptFormat::Text => "txt",
+            TranscriptFormat::Srt => "srt",
+            TranscriptFormat::Vtt => "vtt",
+            TranscriptFormat::Json => "json",
+        }
+    }
+
+    /// Whether this format carries timestamps
+    pub fn is_timed(&self) -> bool {
+        !matches!(self, TranscriptFormat::Text)
+    }
+}
+
+impl std::fmt::Display for TranscriptFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TranscriptFormat::Text => write!(f, "text"),
+            TranscriptFormat::Srt => write!(f, "srt"),
+            TranscriptFormat::Vtt => write!(f, "vtt"),
+            TranscriptFormat::Json => write!(f, "json"),
+        }
+    }
+}
+
+impl std::str::FromStr for TranscriptFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "text" | "txt" => Ok(TranscriptFormat::Text),
+            "srt" => Ok(TranscriptFormat::Srt),
+            "vtt" | "webvtt" => Ok(TranscriptFormat::Vtt),
+            "json" => Ok(TranscriptFormat::Json),
+            _ => Err(format!(
+                "Unknown transcript format: {s}. Use 'text', 'srt', 'vtt', or 'json'"
+            )),
+        }
+    }
+}
+
+/// Limits for splitting subtitles into captions
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CaptionOptions {
+    /// Characters per line
+    pub max_line_chars: usize,
+    /// Lines per caption
+    pub max_lines: usize,
+    /// Longest time a single caption stays on screen
+    pub max_duration_secs: f64,
+}
+
+impl Default for CaptionOptions {
+    fn default() -> Self {
+        Self {
+            max_line_chars: 42,
+            max_lines: 2,
+            max_duration_secs: 7.0,
+        }
+    }
+}
+
+/// A single subtitle: its lines and when it is shown
+#[derive(Debug, Clone, PartialEq)]
+pub struct Caption {
+    pub start: f64,
+    pub end: f64,
+    pub lines: Vec<String>,
+}
+
+/// Render a transcript in the given format, ending with a newline
+///
+/// Fails for subtitle formats when the transcript has no timestamps.
+pub fn render(
+    transcript: &Transcript,
+    format: TranscriptFormat,
+    options: &CaptionOptions,
+) -> Result<String> {
+    match format {
+        TranscriptFormat::Text => Ok(format!("{}\n", transcript.text.trim())),
+        TranscriptFormat::Json => Ok(format!("{}\n", serde_json::to_string_pretty(transcript)?)),
+        TranscriptFormat::Srt | TranscriptFormat::Vtt => {
+            let captions = captions(transcript, options);
+            anyhow::ensure!(
+                !captions.is_empty() || transcript.text.trim().is_empty(),
+                "The transcript has no timestamps, so it can't be written as {format}"
+            );
+            Ok(if format == TranscriptFormat::Srt {
+                render_srt(&captions)
+            } else {
+                render_vtt(&captions)
+            })
+        }
+    }
+}
+
+/// Render captions as SubRip
+pub fn render_srt(captions: &[Caption]) -> String {
+    let mut output = String::new();
+    for (i, caption) in captions.iter().enumerate() {
+        let _ = writeln!(
+            output,
+            "{}\n{} --> {}\n{}\n",
+            i + 1,
+            timestamp(caption.start, ','),
+            timestamp(caption.end, ','),
+            caption.lines.join("\n")
+        );
+    }
+    output
+}
+
+/// Render captions as WebVTT
+pub fn render_vtt(captions: &[Caption]) -> String {
+    let mut output = String::from("WEBVTT\n\n");
+    for caption in captions {
+        let _ = writeln!(
+            output,
+            "{} --> {}\n{}\n",
+            timestamp(caption.start, '.'),
+            timestamp(caption.end, '.'),
+            caption.lines.join("\n")
+        );
+    }
+    output
+}
+
+/// `hh:mm:ss` plus milliseconds after `separator`
+fn timestamp(secs: f64, separator: char) -> String {
+    let millis = (secs.max(0.0) * 1000.0).round() as u64;
+    format!(
+        "{:02}:{:02}:{:02}{separator}{:03}",
+        millis / 3_600_000,
+        millis / 60_000 % 60,
+        millis / 1000 % 60,
+        millis % 1000
+    )
+}
+
+/// Split a transcript's segments into captions within the limits
+pub fn captions(transcript: &Transcript, options: &CaptionOptions) -> Vec<Caption> {
+    let max_line_chars = options.max_line_chars.max(1);
+    let max_lines = options.max_lines.max(1);
+
+    let mut captions = Vec::new();
+    let mut current: Vec<Word> = Vec::new();
+    for segment in &transcript.segments {
+        for word in timed_words(segment, &transcript.words) {
+            if let (Some(first), Some(last)) = (current.first(), current.last()) {
+                let mut candidate: Vec<&str> = current.iter().map(|w| w.word.as_str()).collect();
+                candidate.push(&word.word);
+                if word.start - last.end >= MAX_CAPTION_GAP_SECS
+                    || word.end - first.start > options.max_duration_secs
+                    || wrap(&candidate, max_line_chars).len() > max_lines
+                {
+                    captions.push(caption(&current, max_line_chars));
+                    current.clear();
+                }
+            }
+            current.push(word);
+        }
+    }
+    if !current.is_empty() {
+        captions.push(caption(&current, max_line_chars));
+    }
+
+    // Keep every caption readable without overlapping the next one
+    for i in 0..captions.len() {
+        let next_start = captions.get(i + 1).map_or(f64::INFINITY, |next| next.start);
+        let caption = &mut captions[i];
+        caption.end = caption
+            .end
+            .max(caption.start + MIN_CAPTION_SECS)
+            .min(next_start.max(caption.start));
+    }
+    captions
+}
+
+fn caption(words: &[Word], max_line_chars: usize) -> Caption {
+    let text: Vec<&str> = words.iter().map(|word| word.word.as_str()).collect();
+    Caption {
+        start: words[0].start,
+        end: words[words.len() - 1].end,
+        lines: wrap(&text, max_line_chars),
+    }
+}
+
+/// Greedily fill lines of at most `max_chars` (a longer word gets its own line)
+fn wrap(words: &[&str], max_chars: usize) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for word in words {
+        match lines.last_mut() {
+            Some(line) if line.chars().count() + 1 + word.chars().count() <= max_chars => {
+                line.push(' ');
+                line.push_str(word);
+            }
+            _ => lines.push(word.to_string()),
+        }
+    }
+    lines
+}
+
+/// The words of a segment with their times
+///
+/// Uses the backend's word timestamps when they line up with the segment's
+/// words (backend words often lack punctuation, so the segment's spelling is
+/// kept); otherwise interpolates by word length.
+fn timed_words(segment: &Segment, words: &[Word]) -> Vec<Word> {
+    let tokens: Vec<&str> = segment.text.split_whitespace().collect();
+    let within: Vec<&Word> = words
+        .iter()
+        .filter(|word| (segment.start..segment.end).contains(&((word.start + word.end) / 2.0)))
+        .collect();
+
+    if !tokens.is_empty() && within.len() == tokens.len() {
+        return tokens
+            .iter()
+            .zip(within)
+            .map(|(token, word)| Word {
+                start: word.start,
+                end: word.end,
+                word: token.to_string(),
+            })
+            .collect();
+    }
+
+    let total_chars: usize = tokens.iter().map(|token| token.chars().count() + 1).sum();
+    let secs_per_char = (segment.end - segment.start).max(0.0) / total_chars.max(1) as f64;
+    let mut start = segment.start;
+    tokens
+        .iter()
+        .map(|token| {
+            let end = start + (token.chars().count() + 1) as f64 * secs_per_char;
+            let word = Word {
+                start,
+                end,
+                word: token.to_string(),
+            };
+            start = end;
+            word
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transcript(segments: &[(f64, f64, &str)]) -> Transcript {
+        Transcript {
+            text: segments.iter().map(|s| s.2).collect::<Vec<_>>().join(" "),
+            segments: segments
+                .iter()
+                .map(|&(start, end, text)| Segment {
+                    start,
+                    end,
+                    text: text.to_string(),
+                })
+                .collect(),
+            ..Transcript::default()
+        }
+    }
+
+    #[test]
+    fn renders_srt_and_vtt() {
+        let transcript = transcript(&[(0.0, 1.5, "Hello there."), (3661.25, 3663.0, "Bye.")]);
+        let options = CaptionOptions::default();
+
+        let srt = render(&transcript, TranscriptFormat::Srt, &options).unwrap();
+        assert_eq!(
+            srt,
+            "1\n00:00:00,000 --> 00:00:01,500\nHello there.\n\n\
+             2\n01:01:01,250 --> 01:01:03,000\nBye.\n\n"
+        );
+
+        let vtt = render(&transcript, TranscriptFormat::Vtt, &options).unwrap();
+        assert!(vtt.starts_with("WEBVTT\n\n00:00:00.000 --> 00:00:01.500\nHello there.\n"));
+    }
+
+    #[test]
+    fn splits_long_segments_within_limits() {
+        let text = "one two three four five six seven eight nine ten eleven twelve";
+        let transcript = transcript(&[(0.0, 12.0, text)]);
+        let options = CaptionOptions {
+            max_line_chars: 15,
+            max_lines: 2,
+            max_duration_secs: 5.0,
+        };
+
+        let captions = captions(&transcript, &options);
+        assert!(captions.len() > 2);
+        for caption in &captions {
+            assert!(caption.lines.len() <= 2);
+            assert!(caption.lines.iter().all(|line| line.len() <= 15));
+            assert!(caption.end - caption.start <= 5.0 + 1e-9);
+        }
+        let words: Vec<String> = captions.iter().flat_map(|c| c.lines.clone()).collect();
+        assert_eq!(words.join(" "), text);
+        assert_eq!(captions.last().unwrap().end, 12.0);
+    }
+
+    #[test]
+    fn uses_word_timestamps_when_they_line_up() {
+        let mut transcript = transcript(&[(0.0, 10.0, "Hi, you.")]);
+        transcript.words = vec![
+            Word {
+                start: 1.0,
+                end: 1.4,
+                word: "Hi".to_string(),
+            },
+            Word {
+                start: 8.0,
+                end: 8.5,
+                word: "you".to_string(),
+            },
+        ];
+        let options = CaptionOptions {
+            max_duration_secs: 3.0,
+            ..CaptionOptions::default()
+        };
+
+        let captions = captions(&transcript, &options);
+        assert_eq!(captions.len(), 2);
+        assert_eq!(
+            (captions[0].start, captions[0].lines[0].as_str()),
+            (1.0, "Hi,")
+        );
+        assert_eq!((captions[1].start, captions[1].end), (8.0, 8.5));
+    }
+}
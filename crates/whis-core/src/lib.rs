@@ -1,13 +1,35 @@
 pub mod audio;
 pub mod clipboard;
 pub mod config;
+pub mod error;
+pub mod export;
+pub mod provider;
 pub mod settings;
+pub mod streaming;
 pub mod transcribe;
+pub mod transcript;
 pub mod verbose;
 
-pub use audio::{AudioChunk, AudioRecorder, RecordingData, RecordingOutput};
+pub use audio::{
+    AudioChunk, AudioFormat, AudioRecorder, AutoStopOptions, AutoStopReason, AutoStopReceiver,
+    CaptureStats, ChunkReceiver, DeviceFallback, EncodedAudio, FinalizeOptions, InputDeviceInfo,
+    InputLevel, LevelReceiver, RecordingData, RecordingOutput, VadOptions, list_input_devices,
+    resolve_input_device,
+};
 pub use clipboard::copy_to_clipboard;
 pub use config::TranscriptionProvider;
+pub use error::WhisError;
+pub use export::{CaptionOptions, TranscriptFormat};
+pub use provider::{
+    LocalTranscriber, LocalWhisperConfig, ProviderChain, RetryPolicy, Transcriber,
+    TranscriberRegistry, Transcript, TranscriptionOptions,
+};
 pub use settings::Settings;
-pub use transcribe::{parallel_transcribe, transcribe_audio, ChunkTranscription};
+pub use streaming::StreamingTranscription;
+pub use transcript::{Segment, Word};
+pub use transcribe::{
+    parallel_transcribe, parallel_transcribe_stream, transcribe_audio, ChunkTranscription,
+    MAX_CONCURRENT_REQUESTS,
+};
+pub use tokio_util::sync::CancellationToken;
 pub use verbose::set_verbose;
@@ -0,0 +1,204 @@
+use anyhow::Result;
+use async_trait::async_trait;
+
+use super::openai_compat::{self, Endpoint, Prompt, Timestamps};
+use super::{Transcriber, Transcript, TranscriptionOptions};
+use crate::audio::EncodedAudio;
+use crate::settings::Settings;
+
+/// Model used when no custom model is configured
+const DEFAULT_CUSTOM_MODEL: &str = "whisper-1";
+/// Path appended to a base URL to reach the transcription endpoint
+const TRANSCRIPTIONS_PATH: &str = "/audio/transcriptions";
+/// Path of the translation endpoint, next to the transcription one
+const TRANSLATIONS_PATH: &str = "/audio/translations";
+
+/// Connection details for an OpenAI-compatible server
+#[derive(Debug, Clone)]
+pub struct CustomEndpoint {
+    /// Base URL (e.g., "http://localhost:8000/v1") or the full transcription URL
+    pub base_url: String,
+    pub model: String,
+    /// Header carrying the API key (None means "Authorization: Bearer <key>")
+    pub auth_header: Option<String>,
+    /// Extra multipart form fields sent with every request
+    pub form_fields: Vec<(String, String)>,
+}
+
+impl CustomEndpoint {
+    /// Build the endpoint from settings, or None if no base URL is configured
+    pub fn from_settings(settings: &Settings) -> Option<Self> {
+        let base_url = settings.custom_base_url.clone()?;
+        Some(Self {
+            base_url,
+            model: settings
+                .custom_model
+                .clone()
+                .unwrap_or_else(|| DEFAULT_CUSTOM_MODEL.to_string()),
+            auth_header: settings.custom_auth_header.clone(),
+            form_fields: settings
+                .custom_form_fields
+                .iter()
+                .map(|(k, v)| (k.clone(), v.clone()))
+                .collect(),
+        })
+    }
+
+    /// Full URL of the transcription endpoint
+    pub fn transcriptions_url(&self) -> String {
+        let base = self.base_url.trim_end_matches('/');
+        if base.ends_with(TRANSCRIPTIONS_PATH) {
+            base.to_string()
+        } else {
+            format!("{base}{TRANSCRIPTIONS_PATH}")
+        }
+    }
+
+    /// Full URL of the translation endpoint
+    pub fn translations_url(&self) -> String {
+        let transcriptions = self.transcriptions_url();
+        let base = transcriptions
+            .strip_suffix(TRANSCRIPTIONS_PATH)
+            .unwrap_or(&transcriptions);
+        format!("{base}{TRANSLATIONS_PATH}")
+    }
+}
+
+/// Transcription via a self-hosted or proxied OpenAI-compatible server
+/// (faster-whisper-server, LocalAI, vLLM, ...)
+pub struct CustomTranscriber {
+    client: reqwest::Client,
+    endpoint: CustomEndpoint,
+    url: String,
+    translation_url: String,
+}
+
+impl CustomTranscriber {
+    pub fn new(endpoint: CustomEndpoint) -> Result<Self> {
+        let url = endpoint.transcriptions_url();
+        if !url.starts_with("http://") && !url.starts_with("https://") {
+            anyhow::bail!(
+                "Invalid custom provider URL: {url}. It must start with http:// or https://"
+            );
+        }
+
+        Ok(Self {
+            client: super::http_client()?,
+            translation_url: endpoint.translations_url(),
+            endpoint,
+            url,
+        })
+    }
+}
+
+#[async_trait]
+impl Transcriber for CustomTranscriber {
+    fn name(&self) -> &str {
+        "custom"
+    }
+
+    async fn transcribe(
+        &self,
+        audio: EncodedAudio,
+        options: &TranscriptionOptions,
+    ) -> Result<Transcript> {
+        let endpoint = Endpoint {
+            label: "Custom provider",
+            url: &self.url,
+            translation_url: Some(&self.translation_url),
+            model: &self.endpoint.model,
+            auth_header: self.endpoint.auth_header.as_deref(),
+            form_fields: &self.endpoint.form_fields,
+            timestamps: Timestamps::VerboseJson,
+            prompt: Prompt::Text,
+        };
+        openai_compat::transcribe(&self.client, &endpoint, audio, options).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::AudioFormat;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    fn endpoint(base_url: &str) -> CustomEndpoint {
+        CustomEndpoint {
+            base_url: base_url.to_string(),
+            model: DEFAULT_CUSTOM_MODEL.to_string(),
+            auth_header: None,
+            form_fields: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn joins_endpoint_paths_to_the_base_url() {
+        for base_url in [
+            "http://localhost:8000/v1",
+            "http://localhost:8000/v1/",
+            "http://localhost:8000/v1/audio/transcriptions",
+            "http://localhost:8000/v1/audio/transcriptions/",
+        ] {
+            let endpoint = endpoint(base_url);
+            assert_eq!(
+                endpoint.transcriptions_url(),
+                "http://localhost:8000/v1/audio/transcriptions"
+            );
+            assert_eq!(
+                endpoint.translations_url(),
+                "http://localhost:8000/v1/audio/translations"
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_urls_without_a_scheme() {
+        assert!(CustomTranscriber::new(endpoint("localhost:8000/v1")).is_err());
+        assert!(CustomTranscriber::new(endpoint("https://example.com/v1")).is_ok());
+    }
+
+    /// Answer a single request with a transcript and return the raw request
+    async fn serve_once(listener: TcpListener) -> String {
+        let (mut socket, _) = listener.accept().await.unwrap();
+        let mut request = Vec::new();
+        let mut buffer = [0; 4096];
+        // The multipart body ends with the closing boundary
+        while !request.ends_with(b"--\r\n") {
+            let read = socket.read(&mut buffer).await.unwrap();
+            if read == 0 {
+                break;
+            }
+            request.extend_from_slice(&buffer[..read]);
+        }
+        let body = r#"{"text":"hello"}"#;
+        let response = format!(
+            "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\n\r\n{body}",
+            body.len()
+        );
+        socket.write_all(response.as_bytes()).await.unwrap();
+        String::from_utf8_lossy(&request).into_owned()
+    }
+
+    #[tokio::test]
+    async fn sends_the_key_in_the_configured_header() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}/v1", listener.local_addr().unwrap());
+        let server = tokio::spawn(serve_once(listener));
+
+        let transcriber = CustomTranscriber::new(CustomEndpoint {
+            auth_header: Some("X-Api-Key".to_string()),
+            ..endpoint(&base_url)
+        })
+        .unwrap();
+        let audio = EncodedAudio::new(&b"audio"[..], AudioFormat::Wav);
+        let options = TranscriptionOptions::new("secret", None);
+        let transcript = transcriber.transcribe(audio, &options).await.unwrap();
+        assert_eq!(transcript.text, "hello");
+
+        let request = server.await.unwrap().to_lowercase();
+        assert!(request.starts_with("post /v1/audio/transcriptions "));
+        assert!(request.contains("x-api-key: secret\r\n"));
+        assert!(!request.contains("authorization:"));
+    }
+}
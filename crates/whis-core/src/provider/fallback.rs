@@ -0,0 +1,322 @@
+//! Falling back to other providers when one fails.
+//!
+//! A [`ProviderChain`] tries its backends in order, each with the full retry
+//! policy. The next backend is only tried for failures another provider may
+//! not share: an outage, a rate limit or exhausted quota, a network error, a
+//! rejected API key or a missing translation endpoint. Anything else, such as
+//! a file the provider refused, fails right away.
+
+use anyhow::Result;
+use std::sync::Arc;
+
+use super::{
+    Transcriber, TranscriberRegistry, Transcript, TranscriptionOptions, transcribe_with_retry,
+};
+use crate::audio::EncodedAudio;
+use crate::config::TranscriptionProvider;
+use crate::error::WhisError;
+use crate::settings::Settings;
+
+/// Backends to try in order until one of them transcribes the audio
+#[derive(Clone)]
+pub struct ProviderChain {
+    /// Uses the API key from the request options
+    primary: Arc<dyn Transcriber>,
+    /// Tried in order after `primary`, each with its own API key
+    fallbacks: Vec<(Arc<dyn Transcriber>, String)>,
+}
+
+impl From<Arc<dyn Transcriber>> for ProviderChain {
+    fn from(transcriber: Arc<dyn Transcriber>) -> Self {
+        Self::new(transcriber)
+    }
+}
+
+impl ProviderChain {
+    /// A chain of just `transcriber`, without fallbacks
+    pub fn new(transcriber: Arc<dyn Transcriber>) -> Self {
+        Self {
+            primary: transcriber,
+            fallbacks: Vec::new(),
+        }
+    }
+
+    /// Add a backend to try after the ones already in the chain
+    pub fn with_fallback(mut self, transcriber: Arc<dyn Transcriber>, api_key: &str) -> Self {
+        self.fallbacks.push((transcriber, api_key.to_string()));
+        self
+    }
+
+    /// The backend for `provider`, followed by the fallback providers
+    /// configured in settings
+    ///
+    /// Backends come from [`TranscriberRegistry::from_settings`], so a custom
+    /// endpoint uses its configured base URL and model.
+    pub fn from_settings(settings: &Settings, provider: &TranscriptionProvider) -> Result<Self> {
+        let registry = TranscriberRegistry::from_settings(settings)?;
+        Self::from_registry(&registry, settings, provider)
+    }
+
+    /// Like [`Self::from_settings`], with the backends looked up in `registry`
+    ///
+    /// Fallbacks that are already in the chain or aren't set up (no API key,
+    /// no local model) are skipped.
+    pub fn from_registry(
+        registry: &TranscriberRegistry,
+        settings: &Settings,
+        provider: &TranscriptionProvider,
+    ) -> Result<Self> {
+        let mut chain = Self::new(registry.for_provider(provider)?);
+        for fallback in &settings.fallback_providers {
+            if chain.names().any(|name| name == fallback.to_string()) {
+                continue;
+            }
+            let Some(api_key) = settings.api_key_for(fallback) else {
+                crate::verbose!("Skipping fallback provider {fallback}: no API key configured");
+                continue;
+            };
+            chain = chain.with_fallback(registry.for_provider(fallback)?, &api_key);
+        }
+        Ok(chain)
+    }
+
+    /// Names of the backends, in the order they are tried
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.backends().map(|transcriber| transcriber.name())
+    }
+
+    /// Fail with [`WhisError::TranslationUnsupported`] when `options` ask for
+    /// a translation that none of the backends can do
+    ///
+    /// Front-ends check this before recording, rather than when the first
+    /// chunk is uploaded.
+    pub fn check_options(&self, options: &TranscriptionOptions) -> Result<()> {
+        if options.translate
+            && !self
+                .backends()
+                .any(|backend| backend.supports_translation())
+        {
+            return Err(WhisError::TranslationUnsupported {
+                provider: self.primary.name().to_string(),
+            }
+            .into());
+        }
+        Ok(())
+    }
+
+    fn backends(&self) -> impl Iterator<Item = &Arc<dyn Transcriber>> {
+        std::iter::once(&self.primary)
+            .chain(self.fallbacks.iter().map(|(transcriber, _)| transcriber))
+    }
+
+    /// Transcribe with the first backend that succeeds
+    ///
+    /// [`Transcript::provider`] names the backend that produced the text.
+    /// When every backend fails, the last one's error is returned.
+    pub async fn transcribe(
+        &self,
+        audio: EncodedAudio,
+        options: &TranscriptionOptions,
+    ) -> Result<Transcript> {
+        let mut used = self.primary.as_ref();
+        let mut result = transcribe_with_retry(used, audio.clone(), options).await;
+
+        for (transcriber, api_key) in &self.fallbacks {
+            let Err(error) = &result else { break };
+            if !worth_falling_back(error) {
+                break;
+            }
+            crate::verbose!(
+                "{} failed ({error:#}); falling back to {}",
+                used.name(),
+                transcriber.name()
+            );
+            let options = TranscriptionOptions {
+                api_key: api_key.clone(),
+                ..options.clone()
+            };
+            used = transcriber.as_ref();
+            result = transcribe_with_retry(used, audio.clone(), &options).await;
+        }
+
+        let mut transcript = result?;
+        transcript.provider = Some(used.name().to_string());
+        Ok(transcript)
+    }
+}
+
+/// Whether another provider may succeed where this one failed
+fn worth_falling_back(error: &anyhow::Error) -> bool {
+    match WhisError::find(error) {
+        Some(
+            WhisError::Auth { .. }
+            | WhisError::RateLimited { .. }
+            | WhisError::TranslationUnsupported { .. },
+        ) => true,
+        Some(known) => known.is_retryable(),
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::AudioFormat;
+    use crate::provider::RetryPolicy;
+    use async_trait::async_trait;
+    use reqwest::StatusCode;
+    use reqwest::header::HeaderMap;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    /// Always fails with `status`, or succeeds when it is None
+    struct Fixed {
+        name: &'static str,
+        status: Option<StatusCode>,
+        calls: AtomicU32,
+    }
+
+    impl Fixed {
+        fn new(name: &'static str, status: Option<StatusCode>) -> Arc<Self> {
+            Arc::new(Self {
+                name,
+                status,
+                calls: AtomicU32::new(0),
+            })
+        }
+    }
+
+    #[async_trait]
+    impl Transcriber for Fixed {
+        fn name(&self) -> &str {
+            self.name
+        }
+
+        async fn transcribe(
+            &self,
+            _audio: EncodedAudio,
+            options: &TranscriptionOptions,
+        ) -> Result<Transcript> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            match self.status {
+                Some(status) => {
+                    let message = "failed".to_string();
+                    Err(
+                        WhisError::from_response(self.name, status, &HeaderMap::new(), message)
+                            .into(),
+                    )
+                }
+                None => Ok(Transcript::from_text(format!("key {}", options.api_key))),
+            }
+        }
+    }
+
+    async fn transcribe(chain: &ProviderChain) -> Result<Transcript> {
+        let options = TranscriptionOptions {
+            retry: RetryPolicy {
+                max_retries: 1,
+                initial_delay: std::time::Duration::from_millis(1),
+                max_delay: std::time::Duration::from_millis(1),
+            },
+            ..TranscriptionOptions::new("primary", None)
+        };
+        let audio = EncodedAudio::new(&b"audio"[..], AudioFormat::Wav);
+        chain.transcribe(audio, &options).await
+    }
+
+    #[tokio::test]
+    async fn falls_back_on_outages_and_bad_keys() {
+        let down = Fixed::new("down", Some(StatusCode::SERVICE_UNAVAILABLE));
+        let unauthorized = Fixed::new("unauthorized", Some(StatusCode::UNAUTHORIZED));
+        let working = Fixed::new("working", None);
+        let chain = ProviderChain::new(down.clone())
+            .with_fallback(unauthorized.clone(), "second")
+            .with_fallback(working.clone(), "third");
+
+        let transcript = transcribe(&chain).await.unwrap();
+        assert_eq!(transcript.text, "key third");
+        assert_eq!(transcript.provider.as_deref(), Some("working"));
+        // The outage is retried before falling back, the bad key isn't
+        assert_eq!(down.calls.load(Ordering::SeqCst), 2);
+        assert_eq!(unauthorized.calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn stops_at_rejected_requests() {
+        let rejecting = Fixed::new("rejecting", Some(StatusCode::PAYLOAD_TOO_LARGE));
+        let working = Fixed::new("working", None);
+        let chain = ProviderChain::new(rejecting).with_fallback(working.clone(), "second");
+
+        let error = transcribe(&chain).await.unwrap_err();
+        assert!(error.to_string().contains("413"));
+        assert_eq!(working.calls.load(Ordering::SeqCst), 0);
+
+        let transcript = transcribe(&ProviderChain::new(working)).await.unwrap();
+        assert_eq!(transcript.text, "key primary");
+        assert_eq!(transcript.provider.as_deref(), Some("working"));
+    }
+
+    #[test]
+    fn skips_fallbacks_that_are_not_set_up() {
+        let settings = Settings {
+            provider: TranscriptionProvider::Mistral,
+            fallback_providers: vec![
+                TranscriptionProvider::Mistral,
+                TranscriptionProvider::Custom,
+                TranscriptionProvider::Local,
+            ],
+            local_model_path: Some("ggml-base.bin".to_string()),
+            ..Settings::default()
+        };
+        let chain = ProviderChain::from_settings(&settings, &settings.provider).unwrap();
+        assert_eq!(chain.names().collect::<Vec<_>>(), ["mistral", "local"]);
+    }
+
+    #[tokio::test]
+    async fn translates_with_a_provider_that_can() {
+        let mistral: Arc<dyn Transcriber> =
+            Arc::new(crate::provider::MistralTranscriber::new().unwrap());
+        let working = Fixed::new("working", None);
+        let options = TranscriptionOptions {
+            translate: true,
+            ..TranscriptionOptions::new("primary", None)
+        };
+        let audio = EncodedAudio::new(&b"audio"[..], AudioFormat::Wav);
+
+        // Mistral fails before sending anything
+        let error = ProviderChain::new(mistral.clone())
+            .transcribe(audio.clone(), &options)
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            WhisError::find(&error),
+            Some(WhisError::TranslationUnsupported { .. })
+        ));
+
+        let chain = ProviderChain::new(mistral).with_fallback(working, "second");
+        let transcript = chain.transcribe(audio, &options).await.unwrap();
+        assert_eq!(transcript.provider.as_deref(), Some("working"));
+    }
+
+    #[test]
+    fn rejects_translation_up_front_when_no_provider_can() {
+        let mistral: Arc<dyn Transcriber> =
+            Arc::new(crate::provider::MistralTranscriber::new().unwrap());
+        let translate = TranscriptionOptions {
+            translate: true,
+            ..TranscriptionOptions::new("primary", None)
+        };
+
+        let chain = ProviderChain::new(mistral.clone());
+        let error = chain.check_options(&translate).unwrap_err();
+        assert!(matches!(
+            WhisError::find(&error),
+            Some(WhisError::TranslationUnsupported { .. })
+        ));
+        chain
+            .check_options(&TranscriptionOptions::new("primary", None))
+            .unwrap();
+
+        let chain = chain.with_fallback(Fixed::new("working", None), "second");
+        chain.check_options(&translate).unwrap();
+    }
+}
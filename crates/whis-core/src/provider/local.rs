@@ -0,0 +1,297 @@
+//! Offline transcription with whisper.cpp.
+//!
+//! [`LocalTranscriber`] is a [`Transcriber`] like the HTTP backends: it
+//! decodes the audio it is given back into samples and runs the model
+//! in-process. The actual inference is only compiled with the
+//! `local-whisper` cargo feature.
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tokio_util::sync::CancellationToken;
+
+use super::{Transcriber, TranscriptionOptions};
+use crate::audio::{EncodedAudio, RecordingData};
+use crate::transcript::Transcript;
+
+/// Sample rate expected by whisper.cpp
+pub const WHISPER_SAMPLE_RATE: u32 = 16_000;
+
+/// Magic bytes at the start of a GGML model file ("ggml" as little-endian u32)
+const GGML_MAGIC: &[u8; 4] = b"lmgg";
+
+/// Configuration for a local whisper.cpp transcription
+#[derive(Debug, Clone)]
+pub struct LocalWhisperConfig {
+    /// Path to a GGML model file (e.g., ggml-base.en.bin)
+    pub model_path: PathBuf,
+    /// Number of CPU threads (None = all available cores)
+    pub threads: Option<usize>,
+    /// Optional language hint (ISO-639-1 code, e.g., "en", "de")
+    pub language: Option<String>,
+    /// Vocabulary passed to whisper.cpp as the initial prompt
+    pub prompt: Option<String>,
+    /// Translate the speech to English instead of transcribing it
+    pub translate: bool,
+}
+
+impl LocalWhisperConfig {
+    /// Thread count to use, defaulting to the number of available cores
+    pub fn thread_count(&self) -> usize {
+        self.threads.filter(|&n| n > 0).unwrap_or_else(|| {
+            std::thread::available_parallelism()
+                .map(|n| n.get())
+                .unwrap_or(4)
+        })
+    }
+}
+
+/// Offline transcription with a whisper.cpp model
+///
+/// The language, prompt and translation come from the request options, which
+/// override the ones in the config. Cancelling `options.cancel` stops a run
+/// that is under way.
+pub struct LocalTranscriber {
+    config: LocalWhisperConfig,
+    /// Loaded on first use and kept for the following chunks. The lock also
+    /// runs one transcription at a time, as each already uses every core.
+    model: Arc<Mutex<Option<Model>>>,
+}
+
+impl LocalTranscriber {
+    pub fn new(config: LocalWhisperConfig) -> Self {
+        Self {
+            config,
+            model: Arc::new(Mutex::new(None)),
+        }
+    }
+}
+
+#[async_trait]
+impl Transcriber for LocalTranscriber {
+    fn name(&self) -> &str {
+        "local"
+    }
+
+    async fn transcribe(
+        &self,
+        audio: EncodedAudio,
+        options: &TranscriptionOptions,
+    ) -> Result<Transcript> {
+        let config = LocalWhisperConfig {
+            language: options.language.clone(),
+            prompt: initial_prompt(options),
+            translate: options.translate,
+            ..self.config.clone()
+        };
+        let model = self.model.clone();
+        let cancel = options.cancel.clone();
+
+        // whisper.cpp is CPU-bound, so keep it off the async runtime
+        tokio::task::spawn_blocking(move || {
+            let recording = RecordingData::from_bytes(audio.data.to_vec(), None)?;
+            let input = to_whisper_input(
+                recording.samples(),
+                recording.sample_rate(),
+                recording.channels(),
+            )?;
+
+            let mut model = model.lock().unwrap_or_else(|e| e.into_inner());
+            let model = match &mut *model {
+                Some(model) => model,
+                None => model.insert(load_model(&config)?),
+            };
+            run_model(model, &input, &config, &cancel)
+        })
+        .await
+        .context("Failed to join task")?
+    }
+}
+
+/// The vocabulary followed by the preceding text, as whisper.cpp takes a
+/// single initial prompt
+fn initial_prompt(options: &TranscriptionOptions) -> Option<String> {
+    match (&options.prompt, &options.previous_text) {
+        (Some(prompt), Some(previous)) => Some(format!("{prompt}\n{previous}")),
+        (prompt, previous) => prompt.clone().or_else(|| previous.clone()),
+    }
+}
+
+/// Whether whis-core was built with the `local-whisper` feature
+pub const fn is_available() -> bool {
+    cfg!(feature = "local-whisper")
+}
+
+/// Check that a path points to a readable GGML model file
+pub fn validate_model_path(path: &Path) -> Result<()> {
+    if !path.exists() {
+        anyhow::bail!("Model file not found: {}", path.display());
+    }
+    if !path.is_file() {
+        anyhow::bail!("Model path is not a file: {}", path.display());
+    }
+
+    let mut magic = [0u8; 4];
+    std::fs::File::open(path)
+        .and_then(|mut file| file.read_exact(&mut magic))
+        .with_context(|| format!("Failed to read model file: {}", path.display()))?;
+
+    if &magic != GGML_MAGIC {
+        anyhow::bail!(
+            "Not a GGML whisper model: {}. Download one from https://huggingface.co/ggerganov/whisper.cpp",
+            path.display()
+        );
+    }
+
+    Ok(())
+}
+
+/// Downmix interleaved samples to mono and resample to 16 kHz
+fn to_whisper_input(samples: &[f32], sample_rate: u32, channels: u16) -> Result<Vec<f32>> {
+    let mono = crate::audio::downmix_to_mono(samples, channels);
+    crate::audio::resample(&mono, 1, sample_rate, WHISPER_SAMPLE_RATE)
+}
+
+/// Transcribe interleaved f32 samples in-process
+///
+/// Loads the model for this one call; [`LocalTranscriber`] keeps it loaded.
+pub fn transcribe_samples(
+    samples: &[f32],
+    sample_rate: u32,
+    channels: u16,
+    config: &LocalWhisperConfig,
+) -> Result<Transcript> {
+    let input = to_whisper_input(samples, sample_rate, channels)?;
+    run_model(
+        &load_model(config)?,
+        &input,
+        config,
+        &CancellationToken::new(),
+    )
+}
+
+/// A loaded whisper.cpp model
+#[cfg(feature = "local-whisper")]
+type Model = whisper_rs::WhisperContext;
+/// Never constructed: without the feature no model can be loaded
+#[cfg(not(feature = "local-whisper"))]
+type Model = std::convert::Infallible;
+
+#[cfg(feature = "local-whisper")]
+fn load_model(config: &LocalWhisperConfig) -> Result<Model> {
+    use whisper_rs::{WhisperContext, WhisperContextParameters};
+
+    validate_model_path(&config.model_path)?;
+    crate::verbose!("Local whisper model: {}", config.model_path.display());
+    let model_path = config
+        .model_path
+        .to_str()
+        .context("Model path is not valid UTF-8")?;
+    WhisperContext::new_with_params(model_path, WhisperContextParameters::default())
+        .context("Failed to load whisper model")
+}
+
+#[cfg(not(feature = "local-whisper"))]
+fn load_model(_config: &LocalWhisperConfig) -> Result<Model> {
+    anyhow::bail!(
+        "Local transcription is not available: whis was built without the `local-whisper` feature"
+    )
+}
+
+/// Run the model on 16 kHz mono samples, stopping early once `cancel` is cancelled
+#[cfg(feature = "local-whisper")]
+fn run_model(
+    model: &Model,
+    input: &[f32],
+    config: &LocalWhisperConfig,
+    cancel: &CancellationToken,
+) -> Result<Transcript> {
+    use crate::error::WhisError;
+    use crate::transcript::Segment;
+    use std::ffi::c_void;
+    use whisper_rs::{FullParams, SamplingStrategy};
+
+    /// Polled by whisper.cpp during the run; `user_data` is the token
+    unsafe extern "C" fn is_cancelled(user_data: *mut c_void) -> bool {
+        // SAFETY: set to a token that outlives the run below
+        unsafe { &*(user_data as *const CancellationToken) }.is_cancelled()
+    }
+
+    let threads = config.thread_count();
+    crate::verbose!(
+        "Local whisper input: {} samples at {} Hz, {} thread(s)",
+        input.len(),
+        WHISPER_SAMPLE_RATE,
+        threads
+    );
+
+    let mut state = model
+        .create_state()
+        .context("Failed to create whisper state")?;
+
+    let mut params = FullParams::new(SamplingStrategy::Greedy { best_of: 1 });
+    params.set_n_threads(threads as i32);
+    params.set_language(Some(config.language.as_deref().unwrap_or("auto")));
+    params.set_translate(config.translate);
+    if let Some(prompt) = &config.prompt {
+        params.set_initial_prompt(prompt);
+    }
+    params.set_print_special(false);
+    params.set_print_progress(false);
+    params.set_print_realtime(false);
+    params.set_print_timestamps(false);
+    // whisper-rs' set_abort_callback_safe misreads its own closure pointer,
+    // so install a plain callback instead
+    // SAFETY: `cancel` is borrowed for this whole function, and whisper.cpp
+    // only calls back during `full` below
+    unsafe {
+        params.set_abort_callback(Some(is_cancelled));
+        params.set_abort_callback_user_data(cancel as *const CancellationToken as *mut c_void);
+    }
+
+    let result = state.full(params, input);
+    if cancel.is_cancelled() {
+        return Err(WhisError::Cancelled.into());
+    }
+    result.context("Local whisper transcription failed")?;
+
+    let segments = state
+        .full_n_segments()
+        .context("Failed to read whisper segments")?;
+    let mut transcript = Transcript::default();
+    for i in 0..segments {
+        let text = state
+            .full_get_segment_text_lossy(i)
+            .context("Failed to read whisper segment text")?;
+        transcript.text.push_str(&text);
+
+        // whisper.cpp reports times in centiseconds
+        let start = state
+            .full_get_segment_t0(i)
+            .context("Failed to read whisper segment time")?;
+        let end = state
+            .full_get_segment_t1(i)
+            .context("Failed to read whisper segment time")?;
+        transcript.segments.push(Segment {
+            start: start as f64 / 100.0,
+            end: end as f64 / 100.0,
+            text: text.trim().to_string(),
+        });
+    }
+    transcript.text = transcript.text.trim().to_string();
+    transcript.duration = Some(input.len() as f64 / WHISPER_SAMPLE_RATE as f64);
+
+    Ok(transcript)
+}
+
+#[cfg(not(feature = "local-whisper"))]
+fn run_model(
+    model: &Model,
+    _input: &[f32],
+    _config: &LocalWhisperConfig,
+    _cancel: &CancellationToken,
+) -> Result<Transcript> {
+    match *model {}
+}
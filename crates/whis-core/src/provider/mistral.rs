@@ -0,0 +1,55 @@
+use anyhow::Result;
+use async_trait::async_trait;
+
+use super::openai_compat::{self, Endpoint, Prompt, Timestamps};
+use super::{Transcriber, Transcript, TranscriptionOptions};
+use crate::audio::EncodedAudio;
+
+/// Mistral API endpoint
+const MISTRAL_API_URL: &str = "https://api.mistral.ai/v1/audio/transcriptions";
+/// Mistral Voxtral model
+const MISTRAL_MODEL: &str = "voxtral-mini-latest";
+
+/// Transcription via the Mistral Voxtral API
+pub struct MistralTranscriber {
+    client: reqwest::Client,
+}
+
+impl MistralTranscriber {
+    pub fn new() -> Result<Self> {
+        Ok(Self {
+            client: super::http_client()?,
+        })
+    }
+}
+
+#[async_trait]
+impl Transcriber for MistralTranscriber {
+    fn name(&self) -> &str {
+        "mistral"
+    }
+
+    fn supports_translation(&self) -> bool {
+        // Voxtral only transcribes
+        false
+    }
+
+    async fn transcribe(
+        &self,
+        audio: EncodedAudio,
+        options: &TranscriptionOptions,
+    ) -> Result<Transcript> {
+        let endpoint = Endpoint {
+            label: "Mistral",
+            url: MISTRAL_API_URL,
+            // Voxtral only transcribes
+            translation_url: None,
+            model: MISTRAL_MODEL,
+            auth_header: None,
+            form_fields: &[],
+            timestamps: Timestamps::SegmentsWithoutLanguage,
+            prompt: Prompt::ContextBias,
+        };
+        openai_compat::transcribe(&self.client, &endpoint, audio, options).await
+    }
+}
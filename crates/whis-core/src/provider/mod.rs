@@ -0,0 +1,188 @@
+//! Pluggable transcription backends.
+//!
+//! Every backend implements the [`Transcriber`] trait. Backends are looked up by
+//! name in a [`TranscriberRegistry`], so applications can register their own
+//! implementations next to the built-in OpenAI and Mistral providers, the
+//! configurable OpenAI-compatible custom endpoint and offline whisper.cpp
+//! transcription ([`local`]). A [`ProviderChain`] falls back to other
+//! backends when one is unavailable.
+
+mod custom;
+mod fallback;
+pub mod local;
+mod mistral;
+mod openai;
+mod openai_compat;
+mod retry;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio_util::sync::CancellationToken;
+
+use crate::audio::EncodedAudio;
+use crate::config::TranscriptionProvider;
+use crate::settings::Settings;
+
+pub use custom::{CustomEndpoint, CustomTranscriber};
+pub use fallback::ProviderChain;
+pub use local::{LocalTranscriber, LocalWhisperConfig};
+pub use mistral::MistralTranscriber;
+pub use openai::OpenAITranscriber;
+pub use retry::{RetryPolicy, transcribe_with_retry};
+
+pub use crate::transcript::Transcript;
+
+/// API request timeout in seconds
+pub(crate) const API_TIMEOUT_SECS: u64 = 300;
+
+/// Per-request options passed to a [`Transcriber`]
+#[derive(Debug, Clone, Default)]
+pub struct TranscriptionOptions {
+    /// API key for the backend (ignored by backends that don't need one)
+    pub api_key: String,
+    /// Optional language hint (ISO-639-1 code, e.g., "en", "de")
+    pub language: Option<String>,
+    /// Names and jargon the audio is likely to contain, to help the backend
+    /// spell them
+    pub prompt: Option<String>,
+    /// End of the text transcribed just before this audio, for continuity
+    /// (ignored by backends that only take a vocabulary)
+    pub previous_text: Option<String>,
+    /// When transcribing chunks, pass each chunk's text to the next one as
+    /// `previous_text`. Chunks then wait for each other instead of being
+    /// transcribed in parallel.
+    pub prompt_previous_chunk: bool,
+    /// File name (without extension) reported to the backend for the uploaded
+    /// audio; the extension is taken from the audio format
+    pub file_stem: String,
+    /// Ask for word-level timestamps in addition to segments, where the
+    /// backend supports them
+    pub word_timestamps: bool,
+    /// Translate the speech to English instead of transcribing it (fails
+    /// with [`crate::WhisError::TranslationUnsupported`] where the backend
+    /// can't)
+    pub translate: bool,
+    /// How failed requests are retried (applied by [`transcribe_with_retry`])
+    pub retry: RetryPolicy,
+    /// Abandons the request, and any retries, once cancelled
+    pub cancel: CancellationToken,
+}
+
+impl TranscriptionOptions {
+    pub fn new(api_key: &str, language: Option<&str>) -> Self {
+        Self {
+            api_key: api_key.to_string(),
+            language: language.map(str::to_string),
+            prompt: None,
+            previous_text: None,
+            prompt_previous_chunk: false,
+            file_stem: "audio".to_string(),
+            word_timestamps: false,
+            translate: false,
+            retry: RetryPolicy::default(),
+            cancel: CancellationToken::new(),
+        }
+    }
+
+    /// Options with the retry policy and prompt from `settings`
+    pub fn from_settings(settings: &Settings, api_key: &str, language: Option<&str>) -> Self {
+        Self {
+            retry: settings.retry_policy(),
+            prompt: settings.prompt.clone(),
+            prompt_previous_chunk: settings.prompt_previous_chunk,
+            ..Self::new(api_key, language)
+        }
+    }
+}
+
+/// A transcription backend
+#[async_trait]
+pub trait Transcriber: Send + Sync {
+    /// Name used to look up this backend in a [`TranscriberRegistry`]
+    fn name(&self) -> &str;
+
+    /// Whether this backend can translate to English (see
+    /// [`TranscriptionOptions::translate`])
+    fn supports_translation(&self) -> bool {
+        true
+    }
+
+    /// Transcribe encoded audio data
+    async fn transcribe(
+        &self,
+        audio: EncodedAudio,
+        options: &TranscriptionOptions,
+    ) -> Result<Transcript>;
+}
+
+/// Registry of available transcription backends, keyed by name
+#[derive(Clone, Default)]
+pub struct TranscriberRegistry {
+    transcribers: HashMap<String, Arc<dyn Transcriber>>,
+}
+
+impl TranscriberRegistry {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a registry containing the built-in backends
+    pub fn with_defaults() -> Result<Self> {
+        let mut registry = Self::new();
+        registry.register(Arc::new(OpenAITranscriber::new()?));
+        registry.register(Arc::new(MistralTranscriber::new()?));
+        Ok(registry)
+    }
+
+    /// Create a registry containing the built-in backends plus any backends
+    /// configured in settings (a custom OpenAI-compatible endpoint, a local
+    /// whisper.cpp model)
+    pub fn from_settings(settings: &Settings) -> Result<Self> {
+        let mut registry = Self::with_defaults()?;
+        if let Some(endpoint) = CustomEndpoint::from_settings(settings) {
+            registry.register(Arc::new(CustomTranscriber::new(endpoint)?));
+        }
+        if let Some(config) = settings.local_whisper_config() {
+            registry.register(Arc::new(LocalTranscriber::new(config)));
+        }
+        Ok(registry)
+    }
+
+    /// Register a backend under its [`Transcriber::name`], replacing any existing one
+    pub fn register(&mut self, transcriber: Arc<dyn Transcriber>) {
+        self.transcribers
+            .insert(transcriber.name().to_string(), transcriber);
+    }
+
+    /// Look up a backend by name
+    pub fn get(&self, name: &str) -> Option<Arc<dyn Transcriber>> {
+        self.transcribers.get(name).cloned()
+    }
+
+    /// Look up the backend for a configured provider
+    pub fn for_provider(&self, provider: &TranscriptionProvider) -> Result<Arc<dyn Transcriber>> {
+        let name = provider.to_string();
+        self.get(&name)
+            .ok_or_else(|| anyhow::anyhow!("No transcriber registered for provider: {name}"))
+    }
+
+    /// Names of all registered backends, sorted
+    pub fn names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.transcribers.keys().map(String::as_str).collect();
+        names.sort_unstable();
+        names
+    }
+}
+
+/// Create an HTTP client with the standard API timeout
+pub(crate) fn http_client() -> Result<reqwest::Client> {
+    use anyhow::Context;
+
+    reqwest::Client::builder()
+        .timeout(std::time::Duration::from_secs(API_TIMEOUT_SECS))
+        .build()
+        .context("Failed to create HTTP client")
+}
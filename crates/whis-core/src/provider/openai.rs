@@ -0,0 +1,51 @@
+use anyhow::Result;
+use async_trait::async_trait;
+
+use super::openai_compat::{self, Endpoint, Prompt, Timestamps};
+use super::{Transcriber, Transcript, TranscriptionOptions};
+use crate::audio::EncodedAudio;
+
+/// OpenAI API endpoint
+const OPENAI_API_URL: &str = "https://api.openai.com/v1/audio/transcriptions";
+/// OpenAI endpoint translating speech to English
+const OPENAI_TRANSLATION_URL: &str = "https://api.openai.com/v1/audio/translations";
+/// OpenAI Whisper model
+const OPENAI_MODEL: &str = "whisper-1";
+
+/// Transcription via the OpenAI Whisper API
+pub struct OpenAITranscriber {
+    client: reqwest::Client,
+}
+
+impl OpenAITranscriber {
+    pub fn new() -> Result<Self> {
+        Ok(Self {
+            client: super::http_client()?,
+        })
+    }
+}
+
+#[async_trait]
+impl Transcriber for OpenAITranscriber {
+    fn name(&self) -> &str {
+        "openai"
+    }
+
+    async fn transcribe(
+        &self,
+        audio: EncodedAudio,
+        options: &TranscriptionOptions,
+    ) -> Result<Transcript> {
+        let endpoint = Endpoint {
+            label: "OpenAI",
+            url: OPENAI_API_URL,
+            translation_url: Some(OPENAI_TRANSLATION_URL),
+            model: OPENAI_MODEL,
+            auth_header: None,
+            form_fields: &[],
+            timestamps: Timestamps::VerboseJson,
+            prompt: Prompt::Text,
+        };
+        openai_compat::transcribe(&self.client, &endpoint, audio, options).await
+    }
+}
@@ -0,0 +1,264 @@
+//! Shared request logic for backends speaking the OpenAI
+//! `/v1/audio/transcriptions` multipart protocol.
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+
+use super::{Transcript, TranscriptionOptions};
+use crate::audio::EncodedAudio;
+use crate::error::WhisError;
+use crate::transcript::{Segment, Word};
+
+/// Response from an OpenAI-compatible transcription API
+///
+/// Only `text` is guaranteed; the rest is present in verbose responses.
+#[derive(Deserialize, Debug)]
+struct TranscriptionResponse {
+    text: String,
+    #[serde(default)]
+    language: Option<String>,
+    #[serde(default)]
+    duration: Option<f64>,
+    #[serde(default)]
+    segments: Vec<Segment>,
+    #[serde(default)]
+    words: Vec<Word>,
+    /// Mistral reports the audio length here instead of in `duration`
+    #[serde(default)]
+    usage: Option<Usage>,
+}
+
+#[derive(Deserialize, Debug)]
+struct Usage {
+    #[serde(default)]
+    prompt_audio_seconds: Option<f64>,
+}
+
+impl From<TranscriptionResponse> for Transcript {
+    fn from(response: TranscriptionResponse) -> Self {
+        // Whisper puts a leading space before each segment and word
+        Self {
+            text: response.text,
+            language: response.language,
+            duration: response
+                .duration
+                .or_else(|| response.usage.and_then(|usage| usage.prompt_audio_seconds)),
+            segments: response
+                .segments
+                .into_iter()
+                .map(|segment| Segment {
+                    text: segment.text.trim().to_string(),
+                    ..segment
+                })
+                .collect(),
+            words: response
+                .words
+                .into_iter()
+                .map(|word| Word {
+                    word: word.word.trim().to_string(),
+                    ..word
+                })
+                .collect(),
+            // Filled in by the ProviderChain that sent the request
+            provider: None,
+        }
+    }
+}
+
+/// How an endpoint is asked for timestamps
+pub(crate) enum Timestamps {
+    /// `response_format=verbose_json` with `timestamp_granularities[]`, as
+    /// OpenAI and most compatible servers accept
+    VerboseJson,
+    /// `timestamp_granularities=segment` (Mistral), which the API rejects
+    /// together with a language hint, so it is only sent without one
+    SegmentsWithoutLanguage,
+}
+
+/// How an endpoint is given the vocabulary and the preceding text
+pub(crate) enum Prompt {
+    /// A free-text `prompt` field, as OpenAI and most compatible servers accept
+    Text,
+    /// One `context_bias` field per vocabulary term (Mistral), which has no
+    /// room for the preceding text
+    ContextBias,
+}
+
+/// Endpoint description for an OpenAI-compatible backend
+pub(crate) struct Endpoint<'a> {
+    /// Human-readable provider name used in log and error messages
+    pub label: &'a str,
+    pub url: &'a str,
+    /// Endpoint translating speech to English, if the backend has one
+    pub translation_url: Option<&'a str>,
+    pub model: &'a str,
+    /// Header carrying the API key (None means "Authorization: Bearer <key>")
+    pub auth_header: Option<&'a str>,
+    /// Extra multipart form fields sent with every request
+    pub form_fields: &'a [(String, String)],
+    pub timestamps: Timestamps,
+    pub prompt: Prompt,
+}
+
+impl Endpoint<'_> {
+    /// Build the auth header name and value for an API key
+    fn auth(&self, api_key: &str) -> (String, String) {
+        match self.auth_header {
+            Some(name) if !name.eq_ignore_ascii_case("authorization") => {
+                (name.to_string(), api_key.to_string())
+            }
+            _ => ("Authorization".to_string(), format!("Bearer {api_key}")),
+        }
+    }
+}
+
+/// Send audio to an OpenAI-compatible transcription endpoint
+pub(crate) async fn transcribe(
+    client: &reqwest::Client,
+    endpoint: &Endpoint<'_>,
+    audio: EncodedAudio,
+    options: &TranscriptionOptions,
+) -> Result<Transcript> {
+    let label = endpoint.label;
+    let url = match endpoint.translation_url {
+        _ if !options.translate => endpoint.url,
+        Some(url) => url,
+        None => {
+            return Err(WhisError::TranslationUnsupported {
+                provider: label.to_string(),
+            }
+            .into());
+        }
+    };
+    crate::verbose!("{label} API: {url}");
+    crate::verbose!("Model: {}", endpoint.model);
+
+    // Shares the audio instead of copying it, so retries stay cheap
+    let length = audio.data.len() as u64;
+    let mut form = reqwest::multipart::Form::new()
+        .text("model", endpoint.model.to_string())
+        .part(
+            "file",
+            reqwest::multipart::Part::stream_with_length(audio.data, length)
+                .file_name(format!("{}.{}", options.file_stem, audio.format.extension()))
+                .mime_str(audio.format.mime_type())?,
+        );
+
+    // Add language hint if provided (improves accuracy and latency); the
+    // translation endpoint has no such field
+    if let Some(lang) = &options.language
+        && !options.translate
+    {
+        form = form.text("language", lang.clone());
+    }
+
+    // A response_format in the configured fields means the server wants its own
+    let custom_format = endpoint
+        .form_fields
+        .iter()
+        .any(|(name, _)| name == "response_format");
+    match endpoint.timestamps {
+        // Translations come with segments, but can't be asked for granularities
+        Timestamps::VerboseJson if !custom_format && options.translate => {
+            form = form.text("response_format", "verbose_json");
+        }
+        Timestamps::VerboseJson if !custom_format => {
+            form = form
+                .text("response_format", "verbose_json")
+                .text("timestamp_granularities[]", "segment");
+            if options.word_timestamps {
+                form = form.text("timestamp_granularities[]", "word");
+            }
+        }
+        Timestamps::SegmentsWithoutLanguage if options.language.is_none() => {
+            form = form.text("timestamp_granularities", "segment");
+        }
+        _ => {}
+    }
+
+    let custom_prompt = endpoint
+        .form_fields
+        .iter()
+        .any(|(name, _)| name == "prompt");
+    match endpoint.prompt {
+        Prompt::Text if !custom_prompt => {
+            let prompt = [&options.prompt, &options.previous_text]
+                .into_iter()
+                .flatten()
+                .map(|text| text.trim())
+                .filter(|text| !text.is_empty())
+                .collect::<Vec<_>>()
+                .join(" ");
+            if !prompt.is_empty() {
+                form = form.text("prompt", prompt);
+            }
+        }
+        Prompt::ContextBias => {
+            for term in options.prompt.iter().flat_map(|prompt| vocabulary(prompt)) {
+                form = form.text("context_bias", term.to_string());
+            }
+        }
+        _ => {}
+    }
+
+    for (name, value) in endpoint.form_fields {
+        form = form.text(name.clone(), value.clone());
+    }
+
+    let mut request = client.post(url).multipart(form);
+
+    // Self-hosted servers may run without authentication
+    if !options.api_key.is_empty() {
+        let (name, value) = endpoint.auth(&options.api_key);
+        request = request.header(name, value);
+    }
+
+    crate::verbose!("Sending request to {label}...");
+    let response = request.send().await.map_err(|source| {
+        if source.is_builder() {
+            anyhow::Error::new(source).context(format!("Failed to send request to {label} API"))
+        } else {
+            WhisError::Network {
+                provider: label.to_string(),
+                source,
+            }
+            .into()
+        }
+    })?;
+
+    crate::verbose!("Response status: {}", response.status());
+
+    if !response.status().is_success() {
+        let status = response.status();
+        let headers = response.headers().clone();
+        let error_text = response
+            .text()
+            .await
+            .unwrap_or_else(|_| "Unknown error".to_string());
+        crate::verbose!("API error response: {error_text}");
+        return Err(WhisError::from_response(label, status, &headers, error_text).into());
+    }
+
+    let text = response
+        .text()
+        .await
+        .context("Failed to get response text")?;
+    let transcription: TranscriptionResponse = serde_json::from_str(&text)
+        .with_context(|| format!("Failed to parse {label} API response"))?;
+
+    crate::verbose!(
+        "Timestamps: {} segment(s), {} word(s)",
+        transcription.segments.len(),
+        transcription.words.len()
+    );
+
+    Ok(transcription.into())
+}
+
+/// Terms of a vocabulary prompt, separated by commas or line breaks
+fn vocabulary(prompt: &str) -> impl Iterator<Item = &str> {
+    prompt
+        .split([',', '\n'])
+        .map(str::trim)
+        .filter(|term| !term.is_empty())
+}
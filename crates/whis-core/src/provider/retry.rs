@@ -0,0 +1,252 @@
+//! Retrying failed transcription requests.
+//!
+//! Rate limits, server errors and dropped connections are usually temporary,
+//! so the request is sent again after an exponentially growing, jittered
+//! delay, or after as long as the server asked for in `Retry-After`. Errors
+//! that won't go away by themselves, such as a bad API key, an exhausted
+//! quota or a rejected file, fail immediately.
+
+use anyhow::Result;
+use std::hash::{BuildHasher, RandomState};
+use std::time::Duration;
+
+use super::{Transcriber, Transcript, TranscriptionOptions};
+use crate::audio::EncodedAudio;
+use crate::error::WhisError;
+
+/// How often and how patiently failed requests are retried
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RetryPolicy {
+    /// Retries after the first attempt (0 = never retry)
+    pub max_retries: u32,
+    /// Delay before the first retry; doubled for every further one
+    pub initial_delay: Duration,
+    /// Upper bound for the delay, including delays asked for by the server
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            initial_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(30),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Delay before retry number `attempt` (0-based): exponential, capped at
+    /// `max_delay`, and randomized between half and all of it so concurrent
+    /// requests don't retry in lockstep
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let exponential = self
+            .initial_delay
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(self.max_delay);
+        let jitter = RandomState::new().hash_one(attempt) as f64 / u64::MAX as f64;
+        exponential.mul_f64(0.5 + jitter / 2.0)
+    }
+}
+
+/// Whether a failed request is worth retrying
+///
+/// Returns the delay the server asked for, if any, for temporary failures.
+fn temporary_failure(error: &anyhow::Error) -> Option<Option<Duration>> {
+    if let Some(known) = WhisError::find(error) {
+        return known.is_retryable().then(|| known.retry_after());
+    }
+    for cause in error.chain() {
+        if let Some(request) = cause.downcast_ref::<reqwest::Error>() {
+            let network = request.is_timeout()
+                || request.is_connect()
+                || request.is_request()
+                || request.is_body();
+            return network.then_some(None);
+        }
+    }
+    None
+}
+
+/// Transcribe with `transcriber`, retrying temporary failures as set in
+/// `options.retry`
+///
+/// The audio is shared between attempts, so retrying doesn't copy it. The
+/// last error is returned once the retries are used up, and
+/// [`WhisError::Cancelled`] as soon as `options.cancel` is cancelled.
+pub async fn transcribe_with_retry(
+    transcriber: &dyn Transcriber,
+    audio: EncodedAudio,
+    options: &TranscriptionOptions,
+) -> Result<Transcript> {
+    options
+        .cancel
+        .run_until_cancelled(retry(transcriber, audio, options))
+        .await
+        .unwrap_or_else(|| Err(WhisError::Cancelled.into()))
+}
+
+async fn retry(
+    transcriber: &dyn Transcriber,
+    audio: EncodedAudio,
+    options: &TranscriptionOptions,
+) -> Result<Transcript> {
+    let policy = options.retry;
+    let mut attempt = 0;
+    loop {
+        let error = match transcriber.transcribe(audio.clone(), options).await {
+            Ok(transcript) => return Ok(transcript),
+            Err(error) => error,
+        };
+        let Some(retry_after) = temporary_failure(&error) else {
+            return Err(error);
+        };
+        if attempt >= policy.max_retries {
+            crate::verbose!("Giving up after {} attempt(s)", attempt + 1);
+            return Err(error);
+        }
+        let delay = match retry_after {
+            Some(delay) if delay > policy.max_delay => {
+                crate::verbose!(
+                    "Server asked to wait {:.1}s, longer than the {:.1}s allowed",
+                    delay.as_secs_f64(),
+                    policy.max_delay.as_secs_f64()
+                );
+                return Err(error);
+            }
+            Some(delay) => delay,
+            None => policy.backoff(attempt),
+        };
+
+        attempt += 1;
+        crate::verbose!(
+            "{error:#}; retrying in {:.1}s ({attempt}/{})",
+            delay.as_secs_f64(),
+            policy.max_retries
+        );
+        tokio::time::sleep(delay).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::AudioFormat;
+    use async_trait::async_trait;
+    use reqwest::StatusCode;
+    use reqwest::header::HeaderMap;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    /// Fails with `status` a number of times, then succeeds
+    struct Flaky {
+        status: StatusCode,
+        failures: u32,
+        calls: AtomicU32,
+    }
+
+    #[async_trait]
+    impl Transcriber for Flaky {
+        fn name(&self) -> &str {
+            "flaky"
+        }
+
+        async fn transcribe(
+            &self,
+            audio: EncodedAudio,
+            _options: &TranscriptionOptions,
+        ) -> Result<Transcript> {
+            assert_eq!(&audio.data[..], b"audio");
+            if self.calls.fetch_add(1, Ordering::SeqCst) < self.failures {
+                let error =
+                    WhisError::from_response("Test", self.status, &HeaderMap::new(), "busy".into());
+                return Err(error.into());
+            }
+            Ok(Transcript::from_text("done"))
+        }
+    }
+
+    fn options(max_retries: u32) -> TranscriptionOptions {
+        TranscriptionOptions {
+            retry: RetryPolicy {
+                max_retries,
+                initial_delay: Duration::from_millis(1),
+                max_delay: Duration::from_millis(10),
+            },
+            ..TranscriptionOptions::default()
+        }
+    }
+
+    async fn run(status: StatusCode, failures: u32, max_retries: u32) -> (Result<Transcript>, u32) {
+        let transcriber = Flaky {
+            status,
+            failures,
+            calls: AtomicU32::new(0),
+        };
+        let audio = EncodedAudio::new(&b"audio"[..], AudioFormat::Wav);
+        let result = transcribe_with_retry(&transcriber, audio, &options(max_retries)).await;
+        (result, transcriber.calls.load(Ordering::SeqCst))
+    }
+
+    #[tokio::test]
+    async fn retries_temporary_failures() {
+        let (result, calls) = run(StatusCode::SERVICE_UNAVAILABLE, 2, 3).await;
+        assert_eq!(result.unwrap().text, "done");
+        assert_eq!(calls, 3);
+
+        let (result, calls) = run(StatusCode::TOO_MANY_REQUESTS, 5, 2).await;
+        assert!(result.unwrap_err().to_string().contains("429"));
+        assert_eq!(calls, 3);
+    }
+
+    #[tokio::test]
+    async fn fails_fast_on_fatal_errors() {
+        let (result, calls) = run(StatusCode::UNAUTHORIZED, 1, 3).await;
+        assert!(result.is_err());
+        assert_eq!(calls, 1);
+    }
+
+    #[tokio::test]
+    async fn stops_waiting_when_cancelled() {
+        let transcriber = Flaky {
+            status: StatusCode::SERVICE_UNAVAILABLE,
+            failures: u32::MAX,
+            calls: AtomicU32::new(0),
+        };
+        let options = TranscriptionOptions {
+            retry: RetryPolicy {
+                max_retries: 3,
+                initial_delay: Duration::from_secs(60),
+                max_delay: Duration::from_secs(60),
+            },
+            ..TranscriptionOptions::default()
+        };
+        let cancel = options.cancel.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            cancel.cancel();
+        });
+
+        let audio = EncodedAudio::new(&b"audio"[..], AudioFormat::Wav);
+        let error = transcribe_with_retry(&transcriber, audio, &options)
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            WhisError::find(&error),
+            Some(WhisError::Cancelled)
+        ));
+        assert_eq!(transcriber.calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn bounds_backoff() {
+        let policy = RetryPolicy::default();
+        for attempt in 0..10 {
+            let delay = policy.backoff(attempt);
+            let full = (policy.initial_delay * 2u32.pow(attempt)).min(policy.max_delay);
+            assert!(
+                delay >= full / 2 && delay <= full,
+                "{delay:?} for attempt {attempt}"
+            );
+        }
+    }
+}
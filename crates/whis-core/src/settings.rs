@@ -1,23 +1,155 @@
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs;
 use std::path::PathBuf;
+use std::time::Duration;
 
+use crate::audio::{
+    AudioFormat, DEFAULT_CHUNK_DURATION_SECS, DEFAULT_CHUNK_THRESHOLD_MB, DeviceFallback,
+    UPLOAD_SAMPLE_RATE,
+};
 use crate::config::TranscriptionProvider;
+use crate::provider::{LocalWhisperConfig, RetryPolicy};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Settings {
     pub shortcut: String,
     #[serde(default)]
     pub provider: TranscriptionProvider,
+    /// Providers tried in order when `provider` is down, rate limited, out of
+    /// quota or rejects its API key (a local model makes an offline fallback)
+    #[serde(default)]
+    pub fallback_providers: Vec<TranscriptionProvider>,
     /// Language hint for transcription (ISO-639-1 code, e.g., "en", "de", "fr")
     /// None = auto-detect, Some("en") = English, etc.
     #[serde(default)]
     pub language: Option<String>,
+    /// Names, jargon and identifiers the recordings are likely to contain,
+    /// sent to the provider so it spells them correctly
+    #[serde(default)]
+    pub prompt: Option<String>,
+    /// Prompt each chunk of a long recording with the end of the previous
+    /// chunk's text. Chunks are then transcribed one after another.
+    #[serde(default)]
+    pub prompt_previous_chunk: bool,
     #[serde(default)]
     pub openai_api_key: Option<String>,
     #[serde(default)]
     pub mistral_api_key: Option<String>,
+    /// Base URL of an OpenAI-compatible server for the custom provider
+    /// (e.g., "http://localhost:8000/v1")
+    #[serde(default)]
+    pub custom_base_url: Option<String>,
+    /// Model name sent to the custom provider (defaults to "whisper-1")
+    #[serde(default)]
+    pub custom_model: Option<String>,
+    #[serde(default)]
+    pub custom_api_key: Option<String>,
+    /// Header carrying the custom API key. None or "Authorization" sends
+    /// "Bearer <key>", any other header receives the raw key.
+    #[serde(default)]
+    pub custom_auth_header: Option<String>,
+    /// Extra multipart form fields sent with every custom provider request
+    #[serde(default)]
+    pub custom_form_fields: BTreeMap<String, String>,
+    /// Path to a GGML whisper.cpp model for the local provider
+    #[serde(default)]
+    pub local_model_path: Option<String>,
+    /// CPU threads for local transcription (None = all available cores)
+    #[serde(default)]
+    pub local_threads: Option<usize>,
+    /// Format used to encode recordings before upload
+    #[serde(default)]
+    pub audio_format: AudioFormat,
+    /// Sample rate recordings are resampled to before upload (0 = device rate)
+    #[serde(default = "default_audio_sample_rate")]
+    pub audio_sample_rate: u32,
+    /// Downmix recordings to mono before upload
+    #[serde(default = "default_audio_mono")]
+    pub audio_mono: bool,
+    /// Input device name (None = system default)
+    #[serde(default)]
+    pub input_device: Option<String>,
+    /// What to do when the configured input device is missing
+    #[serde(default)]
+    pub input_device_fallback: DeviceFallback,
+    /// Trim silence and skip transcription when no speech is detected
+    #[serde(default = "default_vad_enabled")]
+    pub vad_enabled: bool,
+    /// Speech level threshold in dBFS (None = adapt to the noise floor)
+    #[serde(default)]
+    pub vad_threshold_db: Option<f32>,
+    /// Recordings whose encoded size exceeds this many megabytes are split into chunks
+    #[serde(default = "default_chunk_threshold_mb")]
+    pub chunk_threshold_mb: u32,
+    /// Maximum duration of each chunk in seconds
+    #[serde(default = "default_chunk_duration_secs")]
+    pub chunk_duration_secs: u32,
+    /// Memory used for buffered audio before long recordings spill to disk (0 = unlimited)
+    #[serde(default = "default_recording_memory_limit_mb")]
+    pub recording_memory_limit_mb: u32,
+    /// Stop recordings after this many seconds (0 = no limit)
+    #[serde(default)]
+    pub max_recording_secs: u32,
+    /// Stop recordings after this many seconds without speech (0 = never)
+    #[serde(default)]
+    pub silence_timeout_secs: u32,
+    /// Keep the microphone open between recordings so they start with a pre-roll
+    #[serde(default)]
+    pub warm_microphone: bool,
+    /// Audio from before the recording started to include, in milliseconds (warm microphone only)
+    #[serde(default = "default_pre_roll_ms")]
+    pub pre_roll_ms: u32,
+    /// Retries of a failed transcription request (rate limits, server and network errors)
+    #[serde(default = "default_max_retries")]
+    pub max_retries: u32,
+    /// Delay before the first retry in milliseconds; doubled for every further retry
+    #[serde(default = "default_retry_initial_delay_ms")]
+    pub retry_initial_delay_ms: u32,
+    /// Longest delay between retries in seconds, including delays asked for by the provider
+    #[serde(default = "default_retry_max_delay_secs")]
+    pub retry_max_delay_secs: u32,
+}
+
+fn default_audio_sample_rate() -> u32 {
+    UPLOAD_SAMPLE_RATE
+}
+
+fn default_audio_mono() -> bool {
+    true
+}
+
+fn default_vad_enabled() -> bool {
+    true
+}
+
+fn default_chunk_threshold_mb() -> u32 {
+    DEFAULT_CHUNK_THRESHOLD_MB
+}
+
+fn default_chunk_duration_secs() -> u32 {
+    DEFAULT_CHUNK_DURATION_SECS
+}
+
+fn default_recording_memory_limit_mb() -> u32 {
+    256
+}
+
+fn default_pre_roll_ms() -> u32 {
+    1000
+}
+
+fn default_max_retries() -> u32 {
+    RetryPolicy::default().max_retries
+}
+
+fn default_retry_initial_delay_ms() -> u32 {
+    RetryPolicy::default().initial_delay.as_millis() as u32
+}
+
+fn default_retry_max_delay_secs() -> u32 {
+    RetryPolicy::default().max_delay.as_secs() as u32
 }
 
 impl Default for Settings {
@@ -25,9 +157,36 @@ impl Default for Settings {
         Self {
             shortcut: "Ctrl+Shift+R".to_string(),
             provider: TranscriptionProvider::default(),
+            fallback_providers: Vec::new(),
             language: None, // Auto-detect
+            prompt: None,
+            prompt_previous_chunk: false,
             openai_api_key: None,
             mistral_api_key: None,
+            custom_base_url: None,
+            custom_model: None,
+            custom_api_key: None,
+            custom_auth_header: None,
+            custom_form_fields: BTreeMap::new(),
+            local_model_path: None,
+            local_threads: None,
+            audio_format: AudioFormat::default(),
+            audio_sample_rate: default_audio_sample_rate(),
+            audio_mono: default_audio_mono(),
+            input_device: None,
+            input_device_fallback: DeviceFallback::default(),
+            vad_enabled: default_vad_enabled(),
+            vad_threshold_db: None,
+            chunk_threshold_mb: default_chunk_threshold_mb(),
+            chunk_duration_secs: default_chunk_duration_secs(),
+            recording_memory_limit_mb: default_recording_memory_limit_mb(),
+            max_recording_secs: 0,
+            silence_timeout_secs: 0,
+            warm_microphone: false,
+            pre_roll_ms: default_pre_roll_ms(),
+            max_retries: default_max_retries(),
+            retry_initial_delay_ms: default_retry_initial_delay_ms(),
+            retry_max_delay_secs: default_retry_max_delay_secs(),
         }
     }
 }
@@ -42,8 +201,17 @@ impl Settings {
     }
 
     /// Get the API key for the current provider, falling back to environment variables
+    ///
+    /// The custom provider may run without authentication, so it yields an empty
+    /// key as long as a base URL is configured. The local provider never needs a
+    /// key and yields an empty one once a model path is set.
     pub fn get_api_key(&self) -> Option<String> {
-        match &self.provider {
+        self.api_key_for(&self.provider)
+    }
+
+    /// Get the API key for `provider`, like [`Settings::get_api_key`]
+    pub fn api_key_for(&self, provider: &TranscriptionProvider) -> Option<String> {
+        match provider {
             TranscriptionProvider::OpenAI => self
                 .openai_api_key
                 .clone()
@@ -52,6 +220,13 @@ impl Settings {
                 .mistral_api_key
                 .clone()
                 .or_else(|| std::env::var("MISTRAL_API_KEY").ok()),
+            TranscriptionProvider::Custom => self.custom_base_url.as_ref().map(|_| {
+                self.custom_api_key
+                    .clone()
+                    .or_else(|| std::env::var("WHIS_CUSTOM_API_KEY").ok())
+                    .unwrap_or_default()
+            }),
+            TranscriptionProvider::Local => self.local_model_path.as_ref().map(|_| String::new()),
         }
     }
 
@@ -60,6 +235,38 @@ impl Settings {
         self.get_api_key().is_some()
     }
 
+    /// Local whisper.cpp configuration, if a model path is set
+    pub fn local_whisper_config(&self) -> Option<LocalWhisperConfig> {
+        let model_path = self.local_model_path.as_ref()?;
+        Some(LocalWhisperConfig {
+            model_path: PathBuf::from(model_path),
+            threads: self.local_threads,
+            language: self.language.clone(),
+            prompt: self.prompt.clone(),
+            translate: false,
+        })
+    }
+
+    /// Memory for buffered audio in bytes before recordings spill to disk (None = unlimited)
+    pub fn recording_memory_limit(&self) -> Option<usize> {
+        Some(self.recording_memory_limit_mb as usize * 1024 * 1024).filter(|&bytes| bytes > 0)
+    }
+
+    /// Pre-roll to keep while the microphone is warm (None = warm microphone off)
+    pub fn pre_roll(&self) -> Option<Duration> {
+        self.warm_microphone
+            .then(|| Duration::from_millis(self.pre_roll_ms as u64))
+    }
+
+    /// How failed transcription requests are retried
+    pub fn retry_policy(&self) -> RetryPolicy {
+        RetryPolicy {
+            max_retries: self.max_retries,
+            initial_delay: Duration::from_millis(self.retry_initial_delay_ms as u64),
+            max_delay: Duration::from_secs(self.retry_max_delay_secs as u64),
+        }
+    }
+
     /// Load settings from disk
     pub fn load() -> Self {
         let path = Self::path();
@@ -0,0 +1,83 @@
+//! Transcribing a recording while it is still running.
+//!
+//! The front-ends keep the recorder in a slot of their own, where a warm
+//! microphone also stays between recordings, and a [`StreamingTranscription`]
+//! next to it. Stopping and cancelling go through the latter, so the recorder
+//! and the transcription are always wound down together.
+
+use anyhow::{Context, Result};
+use std::future::Future;
+use std::sync::Mutex;
+use tokio::runtime::Handle;
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+
+use crate::audio::{AudioRecorder, FinalizeOptions};
+use crate::provider::{ProviderChain, TranscriptionOptions};
+use crate::transcribe::parallel_transcribe_stream;
+use crate::transcript::Transcript;
+
+/// Transcription of the chunks of a running recording
+pub struct StreamingTranscription {
+    /// Resolves to None when no speech was detected
+    task: JoinHandle<Result<Option<Transcript>>>,
+    cancel: CancellationToken,
+}
+
+impl StreamingTranscription {
+    /// Start `recorder` and transcribe its chunks on `runtime` as they are ready
+    ///
+    /// `options.cancel` is cancelled along with the recording.
+    pub fn start(
+        recorder: &mut AudioRecorder,
+        finalize: FinalizeOptions,
+        chain: ProviderChain,
+        options: TranscriptionOptions,
+        runtime: &Handle,
+    ) -> Result<Self> {
+        let chunks = recorder.stream_chunks(finalize);
+        recorder.start_recording()?;
+
+        let cancel = options.cancel.clone();
+        let task = runtime
+            .spawn(async move { parallel_transcribe_stream(chain, &options, chunks, None).await });
+        Ok(Self { task, cancel })
+    }
+
+    /// Stop the recording in `recorder` and return the transcript to wait for
+    ///
+    /// Earlier chunks are already being transcribed; only the last one is
+    /// left. The transcript is None when no speech was detected.
+    pub fn stop(
+        self,
+        recorder: &Mutex<Option<AudioRecorder>>,
+    ) -> Result<impl Future<Output = Result<Option<Transcript>>> + Send + 'static> {
+        if let Err(e) = stop_recorder(recorder) {
+            self.cancel.cancel();
+            return Err(e);
+        }
+        let task = self.task;
+        Ok(async move { task.await.context("Failed to join task")? })
+    }
+
+    /// Discard the recording in `recorder` and stop transcribing it
+    ///
+    /// Requests in flight are cancelled, and so is a local whisper.cpp run.
+    pub fn cancel(self, recorder: &Mutex<Option<AudioRecorder>>) {
+        // Whatever was recorded is dropped
+        let _ = stop_recorder(recorder);
+        self.cancel.cancel();
+        self.task.abort();
+    }
+}
+
+/// Stop streaming from the recorder in `slot`, putting it back if it is
+/// armed so the microphone stays open for the next recording
+fn stop_recorder(slot: &Mutex<Option<AudioRecorder>>) -> Result<()> {
+    let mut recorder = slot.lock().unwrap().take().context("No active recording")?;
+    let result = recorder.stop_streaming();
+    if recorder.is_armed() {
+        *slot.lock().unwrap() = Some(recorder);
+    }
+    result
+}
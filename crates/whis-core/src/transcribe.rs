@@ -1,360 +1,254 @@
 use anyhow::{Context, Result};
-use reqwest::blocking::multipart;
-use serde::Deserialize;
 use std::sync::Arc;
 use tokio::sync::Semaphore;
 
-use crate::audio::AudioChunk;
+use crate::audio::{AudioChunk, CHUNK_OVERLAP_SECS, ChunkReceiver, EncodedAudio};
 use crate::config::TranscriptionProvider;
+use crate::error::WhisError;
+use crate::provider::{ProviderChain, TranscriptionOptions};
+use crate::settings::Settings;
+use crate::transcript::Transcript;
 
 #[allow(unused_imports)]
 use crate::verbose;
 
-/// Maximum concurrent API requests
-const MAX_CONCURRENT_REQUESTS: usize = 3;
+/// Maximum concurrent API requests, across all transcriptions in the process
+pub const MAX_CONCURRENT_REQUESTS: usize = 3;
 /// Maximum words to search for overlap between chunks
 const MAX_OVERLAP_WORDS: usize = 15;
-/// API request timeout in seconds
-const API_TIMEOUT_SECS: u64 = 300;
-
-/// OpenAI API endpoint
-const OPENAI_API_URL: &str = "https://api.openai.com/v1/audio/transcriptions";
-/// OpenAI Whisper model
-const OPENAI_MODEL: &str = "whisper-1";
-
-/// Mistral API endpoint
-const MISTRAL_API_URL: &str = "https://api.mistral.ai/v1/audio/transcriptions";
-/// Mistral Voxtral model
-const MISTRAL_MODEL: &str = "voxtral-mini-latest";
-
-/// Response from OpenAI transcription API
-#[derive(Deserialize, Debug)]
-struct OpenAITranscriptionResponse {
-    text: String,
-}
+/// Characters of a chunk's text passed on to the next chunk as its prompt
+/// (Whisper only reads the last 224 tokens of a prompt)
+const PREVIOUS_TEXT_CHARS: usize = 200;
 
-/// Response from Mistral transcription API
-#[derive(Deserialize, Debug)]
-struct MistralTranscriptionResponse {
-    text: String,
-    // Additional fields available but not used yet:
-    // model: String,
-    // language: Option<String>,
-    // segments: Vec<...>,
-    // usage: UsageInfo,
-}
+/// Shared by every transcription so that transcribing several recordings at
+/// once (such as a batch of files) still sends at most
+/// [`MAX_CONCURRENT_REQUESTS`] requests
+static REQUEST_PERMITS: Semaphore = Semaphore::const_new(MAX_CONCURRENT_REQUESTS);
 
 /// Result of transcribing a single chunk
 pub struct ChunkTranscription {
     pub index: usize,
-    pub text: String,
+    /// Timestamps are relative to the start of the chunk
+    pub transcript: Transcript,
     pub has_leading_overlap: bool,
+    /// Where the chunk starts in the recording, in seconds
+    pub start_secs: f64,
+    /// Whether the timestamps line up with the other chunks' once offset by
+    /// `start_secs` (see [`AudioChunk::exact_timing`])
+    pub exact_timing: bool,
 }
 
 /// Transcribe a single audio file (blocking, for simple single-file case)
 ///
+/// The backend for `provider` is resolved from `settings`, so a custom
+/// endpoint uses its configured base URL and model, and the configured
+/// fallback providers are tried when it fails.
+///
 /// # Arguments
+/// * `settings` - Settings for the backends, retries and prompt
 /// * `provider` - The transcription provider to use
 /// * `api_key` - API key for the provider
 /// * `language` - Optional language hint (ISO-639-1 code, e.g., "en", "de")
-/// * `audio_data` - MP3 audio data to transcribe
+/// * `audio` - Encoded audio to transcribe
 pub fn transcribe_audio(
+    settings: &Settings,
     provider: &TranscriptionProvider,
     api_key: &str,
     language: Option<&str>,
-    audio_data: Vec<u8>,
+    audio: EncodedAudio,
 ) -> Result<String> {
-    crate::verbose!("Transcribing audio: {} bytes", audio_data.len());
+    crate::verbose!("Transcribing audio: {} bytes ({})", audio.len(), audio.format);
     crate::verbose!("Provider: {:?}", provider);
     crate::verbose!("Language hint: {:?}", language);
 
-    let result = match provider {
-        TranscriptionProvider::OpenAI => transcribe_openai(api_key, language, audio_data),
-        TranscriptionProvider::Mistral => transcribe_mistral(api_key, language, audio_data),
-    };
-
-    match &result {
-        Ok(text) => crate::verbose!("Transcription result: {} chars", text.len()),
-        Err(e) => crate::verbose!("Transcription error: {e}"),
-    }
-
-    result
-}
-
-/// Transcribe using OpenAI Whisper API
-fn transcribe_openai(api_key: &str, language: Option<&str>, audio_data: Vec<u8>) -> Result<String> {
-    crate::verbose!("OpenAI API: {}", OPENAI_API_URL);
-    crate::verbose!("Model: {}", OPENAI_MODEL);
-
-    let client = reqwest::blocking::Client::builder()
-        .timeout(std::time::Duration::from_secs(API_TIMEOUT_SECS))
-        .build()
-        .context("Failed to create HTTP client")?;
-
-    let mut form = multipart::Form::new().text("model", OPENAI_MODEL).part(
-        "file",
-        multipart::Part::bytes(audio_data)
-            .file_name("audio.mp3")
-            .mime_str("audio/mpeg")?,
-    );
-
-    // Add language hint if provided (improves accuracy and latency)
-    if let Some(lang) = language {
-        form = form.text("language", lang.to_string());
-    }
-
-    crate::verbose!("Sending request to OpenAI...");
-    let response = client
-        .post(OPENAI_API_URL)
-        .header("Authorization", format!("Bearer {api_key}"))
-        .multipart(form)
-        .send()
-        .context("Failed to send request to OpenAI API")?;
-
-    crate::verbose!("Response status: {}", response.status());
-
-    if !response.status().is_success() {
-        let status = response.status();
-        let error_text = response
-            .text()
-            .unwrap_or_else(|_| "Unknown error".to_string());
-        crate::verbose!("API error response: {error_text}");
-        anyhow::bail!("OpenAI API error ({status}): {error_text}");
-    }
-
-    let text = response.text().context("Failed to get response text")?;
-    let transcription: OpenAITranscriptionResponse =
-        serde_json::from_str(&text).context("Failed to parse OpenAI API response")?;
-
-    Ok(transcription.text)
-}
-
-/// Transcribe using Mistral Voxtral API
-fn transcribe_mistral(api_key: &str, language: Option<&str>, audio_data: Vec<u8>) -> Result<String> {
-    crate::verbose!("Mistral API: {}", MISTRAL_API_URL);
-    crate::verbose!("Model: {}", MISTRAL_MODEL);
-
-    let client = reqwest::blocking::Client::builder()
-        .timeout(std::time::Duration::from_secs(API_TIMEOUT_SECS))
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
         .build()
-        .context("Failed to create HTTP client")?;
-
-    let mut form = multipart::Form::new().text("model", MISTRAL_MODEL).part(
-        "file",
-        multipart::Part::bytes(audio_data)
-            .file_name("audio.mp3")
-            .mime_str("audio/mpeg")?,
-    );
+        .context("Failed to create async runtime")?;
 
-    // Add language hint if provided (boosts accuracy)
-    if let Some(lang) = language {
-        form = form.text("language", lang.to_string());
-    }
+    let result = runtime.block_on(async {
+        let chain = ProviderChain::from_settings(settings, provider)?;
+        let options = TranscriptionOptions::from_settings(settings, api_key, language);
+        chain.transcribe(audio, &options).await
+    });
 
-    crate::verbose!("Sending request to Mistral...");
-    let response = client
-        .post(MISTRAL_API_URL)
-        .header("Authorization", format!("Bearer {api_key}"))
-        .multipart(form)
-        .send()
-        .context("Failed to send request to Mistral API")?;
-
-    crate::verbose!("Response status: {}", response.status());
-
-    if !response.status().is_success() {
-        let status = response.status();
-        let error_text = response
-            .text()
-            .unwrap_or_else(|_| "Unknown error".to_string());
-        crate::verbose!("API error response: {error_text}");
-        anyhow::bail!("Mistral API error ({status}): {error_text}");
+    match &result {
+        Ok(transcript) => crate::verbose!(
+            "Transcription result: {} chars from {}",
+            transcript.text.len(),
+            transcript.provider.as_deref().unwrap_or_default()
+        ),
+        Err(e) => crate::verbose!("Transcription error: {e}"),
     }
 
-    let text = response.text().context("Failed to get response text")?;
-    let transcription: MistralTranscriptionResponse =
-        serde_json::from_str(&text).context("Failed to parse Mistral API response")?;
-
-    Ok(transcription.text)
+    result.map(|transcript| transcript.text)
 }
 
 /// Transcribe a single chunk asynchronously
-async fn transcribe_chunk_async(
-    provider: &TranscriptionProvider,
-    client: &reqwest::Client,
-    api_key: &str,
-    language: Option<&str>,
+async fn transcribe_chunk(
+    chain: &ProviderChain,
+    options: &TranscriptionOptions,
     chunk: AudioChunk, // Take ownership to avoid clone
-) -> Result<ChunkTranscription> {
-    match provider {
-        TranscriptionProvider::OpenAI => transcribe_chunk_openai_async(client, api_key, language, chunk).await,
-        TranscriptionProvider::Mistral => transcribe_chunk_mistral_async(client, api_key, language, chunk).await,
-    }
-}
-
-/// Transcribe a single chunk using OpenAI Whisper API (async)
-async fn transcribe_chunk_openai_async(
-    client: &reqwest::Client,
-    api_key: &str,
-    language: Option<&str>,
-    chunk: AudioChunk,
+    previous_text: Option<String>,
 ) -> Result<ChunkTranscription> {
     let chunk_index = chunk.index;
     let has_leading_overlap = chunk.has_leading_overlap;
+    let start_secs = chunk.start_secs;
+    let exact_timing = chunk.exact_timing;
 
-    let mut form = reqwest::multipart::Form::new()
-        .text("model", OPENAI_MODEL)
-        .part(
-            "file",
-            reqwest::multipart::Part::bytes(chunk.data)
-                .file_name(format!("audio_chunk_{chunk_index}.mp3"))
-                .mime_str("audio/mpeg")?,
-        );
-
-    // Add language hint if provided
-    if let Some(lang) = language {
-        form = form.text("language", lang.to_string());
-    }
-
-    let response = client
-        .post(OPENAI_API_URL)
-        .header("Authorization", format!("Bearer {api_key}"))
-        .multipart(form)
-        .send()
-        .await
-        .context("Failed to send request to OpenAI API")?;
-
-    if !response.status().is_success() {
-        let status = response.status();
-        let error_text = response
-            .text()
-            .await
-            .unwrap_or_else(|_| "Unknown error".to_string());
-        anyhow::bail!("OpenAI API error ({status}): {error_text}");
-    }
-
-    let text = response
-        .text()
-        .await
-        .context("Failed to get response text")?;
-    let transcription: OpenAITranscriptionResponse =
-        serde_json::from_str(&text).context("Failed to parse OpenAI API response")?;
+    let options = TranscriptionOptions {
+        file_stem: format!("audio_chunk_{chunk_index}"),
+        previous_text,
+        ..options.clone()
+    };
+    let audio = EncodedAudio::new(chunk.data, chunk.format);
+    let transcript = chain.transcribe(audio, &options).await?;
+    crate::verbose!(
+        "Chunk {chunk_index} transcribed by {}",
+        transcript.provider.as_deref().unwrap_or_default()
+    );
 
     Ok(ChunkTranscription {
         index: chunk_index,
-        text: transcription.text,
+        transcript,
         has_leading_overlap,
+        start_secs,
+        exact_timing,
     })
 }
 
-/// Transcribe a single chunk using Mistral Voxtral API (async)
-async fn transcribe_chunk_mistral_async(
-    client: &reqwest::Client,
-    api_key: &str,
-    language: Option<&str>,
-    chunk: AudioChunk,
-) -> Result<ChunkTranscription> {
-    let chunk_index = chunk.index;
-    let has_leading_overlap = chunk.has_leading_overlap;
-
-    let mut form = reqwest::multipart::Form::new()
-        .text("model", MISTRAL_MODEL)
-        .part(
-            "file",
-            reqwest::multipart::Part::bytes(chunk.data)
-                .file_name(format!("audio_chunk_{chunk_index}.mp3"))
-                .mime_str("audio/mpeg")?,
-        );
-
-    // Add language hint if provided
-    if let Some(lang) = language {
-        form = form.text("language", lang.to_string());
+/// Transcribe multiple chunks in parallel with the given backend, or chain of
+/// backends
+///
+/// Timestamps in the result are relative to the start of the recording.
+/// Fails with [`WhisError::Cancelled`] once `options.cancel` is cancelled.
+pub async fn parallel_transcribe(
+    transcriber: impl Into<ProviderChain>,
+    options: &TranscriptionOptions,
+    chunks: Vec<AudioChunk>,
+    progress_callback: Option<Box<dyn Fn(usize, usize) + Send + Sync>>,
+) -> Result<Transcript> {
+    let total_chunks = chunks.len();
+    let (sender, receiver) = tokio::sync::mpsc::unbounded_channel();
+    for chunk in chunks {
+        let _ = sender.send(Ok(chunk));
     }
+    drop(sender);
+
+    let transcript = transcribe_chunk_stream(
+        transcriber.into(),
+        options,
+        receiver,
+        Some(total_chunks),
+        progress_callback,
+    )
+    .await?;
+    Ok(transcript.unwrap_or_default())
+}
 
-    let response = client
-        .post(MISTRAL_API_URL)
-        .header("Authorization", format!("Bearer {api_key}"))
-        .multipart(form)
-        .send()
-        .await
-        .context("Failed to send request to Mistral API")?;
-
-    if !response.status().is_success() {
-        let status = response.status();
-        let error_text = response
-            .text()
-            .await
-            .unwrap_or_else(|_| "Unknown error".to_string());
-        anyhow::bail!("Mistral API error ({status}): {error_text}");
-    }
+/// Transcribe chunks of a streaming recording as they arrive
+///
+/// Requests start as soon as each chunk is received from
+/// [`crate::AudioRecorder::stream_chunks`], so only the final chunk is left
+/// when the recording stops. Returns None when the recording produced no
+/// chunks because no speech was detected, and fails with
+/// [`WhisError::Cancelled`] once `options.cancel` is cancelled.
+pub async fn parallel_transcribe_stream(
+    transcriber: impl Into<ProviderChain>,
+    options: &TranscriptionOptions,
+    chunks: ChunkReceiver,
+    progress_callback: Option<Box<dyn Fn(usize, usize) + Send + Sync>>,
+) -> Result<Option<Transcript>> {
+    transcribe_chunk_stream(transcriber.into(), options, chunks, None, progress_callback).await
+}
 
-    let text = response
-        .text()
+/// Transcribe chunks from a channel in parallel, merging once it closes
+///
+/// `expected` is the total number of chunks when known up front; otherwise
+/// progress reports the number received so far. Cancelling `options.cancel`
+/// stops waiting for chunks and abandons the requests in flight.
+async fn transcribe_chunk_stream(
+    chain: ProviderChain,
+    options: &TranscriptionOptions,
+    chunks: ChunkReceiver,
+    expected: Option<usize>,
+    progress_callback: Option<Box<dyn Fn(usize, usize) + Send + Sync>>,
+) -> Result<Option<Transcript>> {
+    let transcription =
+        transcribe_chunks(chain, options, chunks, expected, progress_callback);
+    options
+        .cancel
+        .run_until_cancelled(transcription)
         .await
-        .context("Failed to get response text")?;
-    let transcription: MistralTranscriptionResponse =
-        serde_json::from_str(&text).context("Failed to parse Mistral API response")?;
-
-    Ok(ChunkTranscription {
-        index: chunk_index,
-        text: transcription.text,
-        has_leading_overlap,
-    })
+        .unwrap_or_else(|| Err(WhisError::Cancelled.into()))
 }
 
-/// Transcribe multiple chunks in parallel with rate limiting
-pub async fn parallel_transcribe(
-    provider: &TranscriptionProvider,
-    api_key: &str,
-    language: Option<&str>,
-    chunks: Vec<AudioChunk>,
+async fn transcribe_chunks(
+    chain: ProviderChain,
+    options: &TranscriptionOptions,
+    mut chunks: ChunkReceiver,
+    expected: Option<usize>,
     progress_callback: Option<Box<dyn Fn(usize, usize) + Send + Sync>>,
-) -> Result<String> {
-    let total_chunks = chunks.len();
-
-    // Create shared HTTP client with timeout
-    let client = reqwest::Client::builder()
-        .timeout(std::time::Duration::from_secs(API_TIMEOUT_SECS))
-        .build()
-        .context("Failed to create HTTP client")?;
-
-    // Semaphore to limit concurrent requests
-    let semaphore = Arc::new(Semaphore::new(MAX_CONCURRENT_REQUESTS));
-    let client = Arc::new(client);
-    let api_key = Arc::new(api_key.to_string());
-    let language = language.map(|s| Arc::new(s.to_string()));
-    let provider = provider.clone();
+) -> Result<Option<Transcript>> {
+    let chain = Arc::new(chain);
+    let options = Arc::new(options.clone());
     let completed = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+    let received = Arc::new(std::sync::atomic::AtomicUsize::new(0));
     let progress_callback = progress_callback.map(Arc::new);
 
-    // Spawn ALL tasks immediately - they'll wait on semaphore inside
-    let mut handles = Vec::with_capacity(total_chunks);
+    // Spawn tasks as chunks arrive - they'll wait on semaphore inside
+    let mut handles = Vec::with_capacity(expected.unwrap_or_default());
+    let mut errors = Vec::new();
+    // Text of the latest chunk, when each chunk is prompted with the one before
+    let mut previous_text: Option<tokio::sync::oneshot::Receiver<String>> = None;
+
+    while let Some(chunk) = chunks.recv().await {
+        let chunk = match chunk {
+            Ok(chunk) => chunk,
+            Err(e) => {
+                // The recording can't be completed, so stop waiting for more chunks
+                errors.push(e.context("Failed to prepare audio chunk"));
+                break;
+            }
+        };
+        received.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
 
-    for chunk in chunks {
-        let semaphore = semaphore.clone();
-        let client = client.clone();
-        let api_key = api_key.clone();
-        let language = language.clone();
-        let provider = provider.clone();
+        let chain = chain.clone();
+        let options = options.clone();
         let completed = completed.clone();
+        let received = received.clone();
         let progress_callback = progress_callback.clone();
+        let (text_sender, wait_for_text) = if options.prompt_previous_chunk {
+            let (sender, receiver) = tokio::sync::oneshot::channel();
+            (Some(sender), previous_text.replace(receiver))
+        } else {
+            (None, None)
+        };
 
         let handle = tokio::spawn(async move {
+            // Without the previous chunk's text (it failed), go on without it
+            let previous_text = match wait_for_text {
+                Some(receiver) => receiver.await.ok(),
+                None => None,
+            };
+
             // Acquire permit INSIDE the task - this is the key fix!
             // All tasks spawn immediately, then wait for permits
-            let _permit = semaphore.acquire_owned().await?;
-
-            // Transcribe this chunk (no retry - data is consumed by the request)
-            let lang_ref = language.as_ref().map(|s| s.as_str());
-            let result = transcribe_chunk_async(&provider, &client, &api_key, lang_ref, chunk).await;
-
-            let transcription = match result {
-                Ok(t) => t,
-                Err(e) => return Err(e),
-            };
+            let _permit = REQUEST_PERMITS.acquire().await?;
+
+            // Retries wait while holding the permit, which also slows down
+            // the other chunks when the provider is rate limiting
+            let transcription = transcribe_chunk(&chain, &options, chunk, previous_text).await?;
+            if let Some(sender) = text_sender {
+                let text = &transcription.transcript.text;
+                let _ = sender.send(text_tail(text, PREVIOUS_TEXT_CHARS).to_string());
+            }
 
             let done = completed.fetch_add(1, std::sync::atomic::Ordering::SeqCst) + 1;
             if let Some(ref cb) = progress_callback {
-                cb(done, total_chunks);
+                let total = expected
+                    .unwrap_or_else(|| received.load(std::sync::atomic::Ordering::SeqCst));
+                cb(done, total);
             }
             Ok(transcription)
         });
@@ -362,9 +256,13 @@ pub async fn parallel_transcribe(
         handles.push(handle);
     }
 
-    // Collect results
+    if handles.is_empty() && errors.is_empty() {
+        return Ok(None);
+    }
+
+    // Collect results (a chunk that failed to encode counts as failed too)
+    let total_chunks = handles.len() + errors.len();
     let mut results = Vec::with_capacity(total_chunks);
-    let mut errors = Vec::new();
 
     for handle in handles {
         match handle.await {
@@ -374,98 +272,495 @@ pub async fn parallel_transcribe(
         }
     }
 
-    // If any chunks failed, return error with details
+    // If any chunks failed, return error with details, keeping the first
+    // failure as the cause so a WhisError in it can still be found
     if !errors.is_empty() {
         let error_msgs: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
-        anyhow::bail!(
+        let summary = format!(
             "Failed to transcribe {} of {} chunks:\n{}",
             errors.len(),
             total_chunks,
             error_msgs.join("\n")
         );
+        return Err(errors.swap_remove(0).context(summary));
     }
 
     // Sort by index to ensure correct order
     results.sort_by_key(|r| r.index);
 
     // Merge transcriptions
-    Ok(merge_transcriptions(results))
+    Ok(Some(merge_transcriptions(results)))
 }
 
 /// Merge transcription results, handling overlaps
-fn merge_transcriptions(transcriptions: Vec<ChunkTranscription>) -> String {
+///
+/// Timestamps are moved from each chunk's own timeline onto the recording's.
+/// Where two chunks overlap and both have exact segment timestamps, each keeps
+/// what lies on its side of the middle of the overlap; otherwise the repeated
+/// words are found by comparing the text.
+fn merge_transcriptions(transcriptions: Vec<ChunkTranscription>) -> Transcript {
     if transcriptions.is_empty() {
-        return String::new();
+        return Transcript::default();
     }
 
     if transcriptions.len() == 1 {
-        return transcriptions.into_iter().next().unwrap().text;
+        let transcription = transcriptions.into_iter().next().unwrap();
+        let mut transcript = transcription.transcript;
+        transcript.offset(transcription.start_secs);
+        return transcript;
     }
 
-    let mut merged = String::new();
-
+    // Where each chunk's overlap with the previous one is cut, if it can be
+    // cut by time (the first chunk has no previous one)
+    let cuts: Vec<Option<f64>> = std::iter::once(None)
+        .chain(transcriptions.windows(2).map(|pair| {
+            let (previous, chunk) = (&pair[0], &pair[1]);
+            let timed = previous.exact_timing
+                && chunk.exact_timing
+                && !previous.transcript.segments.is_empty()
+                && !chunk.transcript.segments.is_empty();
+            (chunk.has_leading_overlap && timed)
+                .then(|| chunk.start_secs + CHUNK_OVERLAP_SECS as f64 / 2.0)
+        }))
+        .collect();
+
+    let mut merged = Transcript::default();
     for (i, transcription) in transcriptions.into_iter().enumerate() {
-        let text = transcription.text.trim();
-
-        if i == 0 {
-            // First chunk - use as-is
-            merged.push_str(text);
-        } else if transcription.has_leading_overlap {
-            // This chunk has overlap - try to find and remove duplicate words
-            let cleaned_text = remove_overlap(&merged, text);
-            if !merged.ends_with(' ') && !cleaned_text.is_empty() && !cleaned_text.starts_with(' ')
-            {
-                merged.push(' ');
-            }
-            merged.push_str(&cleaned_text);
+        let mut transcript = transcription.transcript;
+        transcript.offset(transcription.start_secs);
+
+        let from = cuts[i].unwrap_or(f64::NEG_INFINITY);
+        let until = cuts.get(i + 1).copied().flatten().unwrap_or(f64::INFINITY);
+        let keep = |start: f64, end: f64| (from..until).contains(&((start + end) / 2.0));
+
+        let text = if from.is_finite() || until.is_finite() {
+            transcript
+                .segments
+                .retain(|segment| keep(segment.start, segment.end));
+            let segments: Vec<&str> = transcript
+                .segments
+                .iter()
+                .map(|segment| segment.text.as_str())
+                .collect();
+            join_texts(&segments)
         } else {
-            // No overlap - just append with space
-            if !merged.ends_with(' ') && !text.is_empty() && !text.starts_with(' ') {
-                merged.push(' ');
-            }
-            merged.push_str(text);
+            transcript.text.trim().to_string()
+        };
+        // Without a cut by time, the repeated words are found in the text
+        let text = if transcription.has_leading_overlap && !from.is_finite() {
+            remove_overlap(&merged.text, &text).to_string()
+        } else {
+            text
+        };
+        transcript.words.retain(|word| keep(word.start, word.end));
+
+        append_text(&mut merged.text, &text);
+        merged.segments.append(&mut transcript.segments);
+        merged.words.append(&mut transcript.words);
+        if merged.language.is_none() {
+            merged.language = transcript.language;
+        }
+        merged.duration = match (merged.duration, transcript.duration) {
+            (Some(a), Some(b)) => Some(a.max(b)),
+            (a, b) => a.or(b),
+        };
+        if let Some(provider) = transcript.provider {
+            merged.provider = match merged.provider {
+                Some(providers) if providers.split(", ").any(|p| p == provider) => Some(providers),
+                Some(providers) => Some(format!("{providers}, {provider}")),
+                None => Some(provider),
+            };
         }
     }
 
     merged
 }
 
-/// Remove overlapping text from the beginning of new_text that matches end of existing_text
-fn remove_overlap(existing: &str, new_text: &str) -> String {
-    let existing_words: Vec<&str> = existing.split_whitespace().collect();
-    let new_words: Vec<&str> = new_text.split_whitespace().collect();
+/// The end of `text`, at most `max_chars` long, starting at a word where possible
+fn text_tail(text: &str, max_chars: usize) -> &str {
+    let text = text.trim_end();
+    let Some((start, _)) = text.char_indices().rev().nth(max_chars.saturating_sub(1)) else {
+        return text.trim_start();
+    };
+    let tail = &text[start..];
+    if start == 0 || text[..start].ends_with(char::is_whitespace) {
+        return tail;
+    }
+    match tail.find(char::is_whitespace) {
+        Some(space) => tail[space..].trim_start(),
+        // Text without spaces, such as Chinese or Japanese
+        None => tail,
+    }
+}
 
-    if existing_words.is_empty() || new_words.is_empty() {
-        return new_text.to_string();
+/// Join pieces of text with spaces (except between CJK characters)
+fn join_texts(texts: &[&str]) -> String {
+    let mut joined = String::new();
+    for text in texts {
+        append_text(&mut joined, text.trim());
+    }
+    joined
+}
+
+fn append_text(text: &mut String, next: &str) {
+    let (Some(last), Some(first)) = (text.chars().last(), next.chars().next()) else {
+        text.push_str(next);
+        return;
+    };
+    let needs_space = !last.is_whitespace() && !first.is_whitespace();
+    if needs_space && !(is_cjk(last) && is_cjk(first)) {
+        text.push(' ');
     }
+    text.push_str(next);
+}
+
+/// Scripts written without spaces, where every character counts as a word
+fn is_cjk(c: char) -> bool {
+    matches!(c,
+        '\u{3000}'..='\u{303f}' // CJK punctuation
+        | '\u{3040}'..='\u{30ff}' // Hiragana and Katakana
+        | '\u{3400}'..='\u{4dbf}' // CJK Extension A
+        | '\u{4e00}'..='\u{9fff}' // CJK Unified Ideographs
+        | '\u{f900}'..='\u{faff}' // CJK Compatibility Ideographs
+        | '\u{ff00}'..='\u{ffef}' // Fullwidth forms
+    )
+}
+
+/// A word of a transcript, for comparing overlapping text
+struct Token {
+    /// Byte offset of the word in the text
+    start: usize,
+    /// Lowercase letters and digits of the word
+    normalized: String,
+}
+
+/// Split text into words, ignoring case and punctuation
+fn tokenize(text: &str) -> Vec<Token> {
+    let mut tokens: Vec<Token> = Vec::new();
+    let mut in_word = false;
+    for (start, c) in text.char_indices() {
+        if c.is_whitespace() {
+            in_word = false;
+            continue;
+        }
+        if is_cjk(c) {
+            in_word = false;
+            if c.is_alphanumeric() {
+                tokens.push(Token {
+                    start,
+                    normalized: c.to_string(),
+                });
+            }
+            continue;
+        }
+        if !in_word {
+            tokens.push(Token {
+                start,
+                normalized: String::new(),
+            });
+            in_word = true;
+        }
+        if c.is_alphanumeric() {
+            let token = tokens.last_mut().expect("a word was just started");
+            match c {
+                // Uppercase "ß" is written "SS"
+                'ß' | 'ẞ' => token.normalized.push_str("ss"),
+                _ => token.normalized.extend(c.to_lowercase()),
+            }
+        }
+    }
+    // Stray punctuation (such as a dash) can't be matched
+    tokens.retain(|token| !token.normalized.is_empty());
+    tokens
+}
+
+/// Whether two normalized words are the same, allowing one typo in longer words
+fn similar_words(a: &str, b: &str) -> bool {
+    if a == b {
+        return true;
+    }
+    let (a, b): (Vec<char>, Vec<char>) = (a.chars().collect(), b.chars().collect());
+    if a.len().min(b.len()) < 4 || a.len().abs_diff(b.len()) > 1 {
+        return false;
+    }
+
+    // Levenshtein distance, one row at a time
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.iter().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+    previous[b.len()] <= 1
+}
+
+/// Remove overlapping text from the beginning of new_text that matches end of existing_text
+///
+/// Words are compared without case or punctuation, and up to one word in
+/// four may differ, since the words at a chunk's edge are often misheard.
+fn remove_overlap<'a>(existing: &str, new_text: &'a str) -> &'a str {
+    let existing_words = tokenize(existing);
+    let new_words = tokenize(new_text);
 
     // Look for overlap in the last N words of existing and first N words of new
     // ~2 seconds of audio overlap = roughly 5-15 words
-    let search_end = existing_words.len().min(MAX_OVERLAP_WORDS);
-    let search_new = new_words.len().min(MAX_OVERLAP_WORDS);
+    let max_overlap = existing_words
+        .len()
+        .min(new_words.len())
+        .min(MAX_OVERLAP_WORDS);
 
     // Find the longest matching overlap
     let mut best_overlap = 0;
-
-    for overlap_len in 1..=search_end.min(search_new) {
+    for overlap_len in 1..=max_overlap {
         let end_slice = &existing_words[existing_words.len() - overlap_len..];
         let start_slice = &new_words[..overlap_len];
+        let matching = end_slice
+            .iter()
+            .zip(start_slice)
+            .filter(|(a, b)| similar_words(&a.normalized, &b.normalized))
+            .count();
+        if matching * 4 >= overlap_len * 3 {
+            best_overlap = overlap_len;
+        }
+    }
+
+    if best_overlap == 0 {
+        return new_text;
+    }
+    // Skip the overlapping words
+    new_words
+        .get(best_overlap)
+        .map_or("", |token| &new_text[token.start..])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transcript::Segment;
+    use proptest::prelude::*;
 
-        // Case-insensitive comparison
-        let matches = end_slice
+    fn chunk(index: usize, start_secs: f64, segments: &[(f64, f64, &str)]) -> ChunkTranscription {
+        let segments: Vec<Segment> = segments
+            .iter()
+            .map(|&(start, end, text)| Segment {
+                start,
+                end,
+                text: text.to_string(),
+            })
+            .collect();
+        let text = segments
             .iter()
-            .zip(start_slice.iter())
-            .all(|(a, b)| a.eq_ignore_ascii_case(b));
+            .map(|segment| segment.text.as_str())
+            .collect::<Vec<_>>()
+            .join(" ");
+        ChunkTranscription {
+            index,
+            transcript: Transcript {
+                text,
+                segments,
+                ..Transcript::default()
+            },
+            has_leading_overlap: index > 0,
+            start_secs,
+            exact_timing: true,
+        }
+    }
 
-        if matches {
-            best_overlap = overlap_len;
+    fn text_chunk(index: usize, text: &str) -> ChunkTranscription {
+        ChunkTranscription {
+            index,
+            transcript: Transcript::from_text(text),
+            has_leading_overlap: index > 0,
+            start_secs: 0.0,
+            exact_timing: false,
         }
     }
 
-    if best_overlap > 0 {
-        // Skip the overlapping words
-        new_words[best_overlap..].join(" ")
-    } else {
-        new_text.to_string()
+    fn normalized_words(text: &str) -> Vec<String> {
+        tokenize(text)
+            .into_iter()
+            .map(|token| token.normalized)
+            .collect()
+    }
+
+    #[test]
+    fn merge_offsets_chunk_timestamps() {
+        let merged = merge_transcriptions(vec![
+            chunk(0, 0.0, &[(0.0, 4.0, "one two"), (4.0, 9.5, "three four")]),
+            // Starts 2s before the first chunk ends, repeating "three four"
+            chunk(1, 8.0, &[(0.0, 1.5, "three four"), (1.5, 5.0, "five six")]),
+        ]);
+
+        assert_eq!(merged.text, "one two three four five six");
+        let times: Vec<(f64, f64)> = merged
+            .segments
+            .iter()
+            .map(|segment| (segment.start, segment.end))
+            .collect();
+        assert_eq!(times, [(0.0, 4.0), (4.0, 9.5), (9.5, 13.0)]);
+    }
+
+    #[test]
+    fn merge_falls_back_to_text_for_trimmed_chunks() {
+        let mut chunks = vec![
+            chunk(0, 0.0, &[(0.0, 4.0, "one two"), (4.0, 9.5, "three four")]),
+            // Trimming moved "five six" before the middle of the overlap
+            chunk(1, 8.0, &[(0.0, 0.4, "three four"), (0.4, 1.4, "five six")]),
+        ];
+        for chunk in &mut chunks {
+            chunk.exact_timing = false;
+        }
+
+        let merged = merge_transcriptions(chunks);
+        assert_eq!(merged.text, "one two three four five six");
+    }
+
+    #[test]
+    fn remove_overlap_ignores_case_and_punctuation() {
+        assert_eq!(
+            remove_overlap("We met in Zürich, last week.", "zürich last week and then"),
+            "and then"
+        );
+        assert_eq!(remove_overlap("Ça va bien.", "Ça va? Bien, merci"), "merci");
+        assert_eq!(remove_overlap("one two", "three four"), "three four");
+    }
+
+    #[test]
+    fn merges_cjk_text_by_character() {
+        let merged = merge_transcriptions(vec![
+            text_chunk(0, "今日は良い天気です。"),
+            text_chunk(1, "天気です。散歩に行きましょう。"),
+        ]);
+        assert_eq!(merged.text, "今日は良い天気です。散歩に行きましょう。");
+    }
+
+    #[test]
+    fn merge_lists_each_provider_once() {
+        let chunks = ["openai", "mistral", "openai"]
+            .into_iter()
+            .enumerate()
+            .map(|(index, provider)| {
+                let mut chunk = text_chunk(index, &format!("part {index}"));
+                chunk.transcript.provider = Some(provider.to_string());
+                chunk
+            })
+            .collect();
+        let merged = merge_transcriptions(chunks);
+        assert_eq!(merged.provider.as_deref(), Some("openai, mistral"));
+    }
+
+    #[test]
+    fn text_tail_starts_at_a_word() {
+        assert_eq!(text_tail("one two three four", 9), "four");
+        assert_eq!(text_tail("one two three four", 10), "three four");
+        assert_eq!(text_tail(" short ", 200), "short");
+        assert_eq!(text_tail("今日は良い天気です", 4), "天気です");
+    }
+
+    /// Words that differ in script and case, numbered so none repeats
+    fn word(n: usize) -> String {
+        const BASES: &[&str] = &["straße", "Café", "naïve", "Ökonom", "word", "Ελλάδα", "мир"];
+        format!("{}{n}", BASES[n % BASES.len()])
+    }
+
+    /// Render words as a transcript would, with varying case and punctuation
+    fn render(words: &[String], styles: &[u8]) -> String {
+        words
+            .iter()
+            .zip(styles.iter().cycle())
+            .map(|(word, style)| match style % 4 {
+                0 => word.clone(),
+                1 => format!("{word},"),
+                2 => format!("{}.", word.to_uppercase()),
+                _ => word.to_lowercase(),
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    proptest! {
+        #[test]
+        fn text_merge_keeps_every_word_once(
+            len in 6usize..40,
+            split in 3usize..30,
+            overlap in 1usize..10,
+            misheard in any::<bool>(),
+            styles in prop::collection::vec(any::<u8>(), 1..8),
+            other_styles in prop::collection::vec(any::<u8>(), 1..8),
+        ) {
+            let words: Vec<String> = (0..len).map(word).collect();
+            let split = split.min(len - 1);
+            let overlap = overlap.min(split);
+
+            let first = render(&words[..split], &styles);
+            let mut repeated = words[split - overlap..].to_vec();
+            // The word cut at the chunk's edge often comes out wrong
+            if misheard && overlap >= 4 {
+                repeated[0] = "xq".to_string();
+            }
+            let second = render(&repeated, &other_styles);
+
+            let merged = merge_transcriptions(vec![text_chunk(0, &first), text_chunk(1, &second)]);
+            let expected: Vec<String> = words.iter().flat_map(|w| normalized_words(w)).collect();
+            prop_assert_eq!(normalized_words(&merged.text), expected);
+        }
+
+        #[test]
+        fn timed_merge_keeps_every_segment_once(
+            timings in prop::collection::vec((1u8..=6, 0u8..=2), 20..120),
+            chunk_lengths in prop::collection::vec(10u8..=20, 1..6),
+            overlaps in prop::collection::vec(any::<bool>(), 6),
+        ) {
+            // Quarter seconds keep every time exact
+            let mut time = 0.0;
+            let words: Vec<(f64, f64, String)> = timings
+                .iter()
+                .enumerate()
+                .map(|(n, &(length, gap))| {
+                    let start = time + gap as f64 * 0.25;
+                    time = start + length as f64 * 0.25;
+                    (start, time, word(n))
+                })
+                .collect();
+
+            // Cut the timeline into chunks; a word belongs to every chunk
+            // holding its midpoint, so overlapping chunks share words
+            let mut chunks = Vec::new();
+            let mut start = 0.0;
+            for (index, &length) in chunk_lengths.iter().enumerate() {
+                let last = index == chunk_lengths.len() - 1 || start + length as f64 >= time;
+                let end = if last { f64::INFINITY } else { start + length as f64 };
+                let segments: Vec<(f64, f64, &str)> = words
+                    .iter()
+                    .filter(|(s, e, _)| (start..end).contains(&((s + e) / 2.0)))
+                    .map(|(s, e, text)| (s - start, e - start, text.as_str()))
+                    .collect();
+                let mut transcription = chunk(index, start, &segments);
+                transcription.has_leading_overlap = index > 0 && overlaps[index];
+                chunks.push(transcription);
+                if last {
+                    break;
+                }
+                start = if overlaps[index + 1] {
+                    end - CHUNK_OVERLAP_SECS as f64
+                } else {
+                    end
+                };
+            }
+            // A chunk without speech still gets merged by text
+            prop_assume!(chunks.iter().all(|c| !c.transcript.segments.is_empty()));
+
+            let merged = merge_transcriptions(chunks);
+            let texts: Vec<&str> = merged.segments.iter().map(|s| s.text.as_str()).collect();
+            let expected: Vec<&str> = words.iter().map(|(_, _, text)| text.as_str()).collect();
+            prop_assert_eq!(&texts, &expected);
+            prop_assert_eq!(merged.text, expected.join(" "));
+            for (segment, (start, end, _)) in merged.segments.iter().zip(&words) {
+                prop_assert_eq!((segment.start, segment.end), (*start, *end));
+            }
+        }
     }
 }
@@ -0,0 +1,89 @@
+//! Transcription results with timing.
+//!
+//! Backends that return timestamps fill in [`Transcript::segments`] (and
+//! [`Transcript::words`] when word timestamps were requested). Times are in
+//! seconds from the start of the audio that was uploaded; when chunks of a
+//! long recording are merged, each chunk's times are shifted by where the
+//! chunk starts.
+
+use serde::{Deserialize, Serialize};
+
+/// Text produced by a [`crate::Transcriber`], with timing when available
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Transcript {
+    pub text: String,
+    /// Spoken language as reported by the backend (an ISO-639-1 code or an
+    /// English name, depending on the API)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    /// Length of the transcribed audio in seconds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration: Option<f64>,
+    /// Timed segments, in order (empty if the backend returned none)
+    #[serde(default)]
+    pub segments: Vec<Segment>,
+    /// Timed words, in order (empty unless word timestamps were requested
+    /// and the backend supports them)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub words: Vec<Word>,
+    /// Backend that produced the text, as named by [`crate::Transcriber::name`]
+    /// (several, comma-separated, if chunks of a recording fell back to
+    /// different ones)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provider: Option<String>,
+}
+
+/// A stretch of speech, usually a sentence or phrase
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Segment {
+    /// Start time in seconds
+    pub start: f64,
+    /// End time in seconds
+    pub end: f64,
+    pub text: String,
+}
+
+/// A single word with its timing
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Word {
+    /// Start time in seconds
+    pub start: f64,
+    /// End time in seconds
+    pub end: f64,
+    pub word: String,
+}
+
+impl Transcript {
+    /// A transcript without timing information
+    pub fn from_text(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            ..Self::default()
+        }
+    }
+
+    /// Shift every timestamp by `secs`, e.g. to where a chunk starts in the recording
+    pub fn offset(&mut self, secs: f64) {
+        for segment in &mut self.segments {
+            segment.start += secs;
+            segment.end += secs;
+        }
+        for word in &mut self.words {
+            word.start += secs;
+            word.end += secs;
+        }
+        if let Some(duration) = &mut self.duration {
+            *duration += secs;
+        }
+    }
+
+    /// End of the last timed segment or word, in seconds
+    pub fn end(&self) -> Option<f64> {
+        let segment_end = self.segments.last().map(|segment| segment.end);
+        let word_end = self.words.last().map(|word| word.end);
+        match (segment_end, word_end) {
+            (Some(a), Some(b)) => Some(a.max(b)),
+            (a, b) => a.or(b),
+        }
+    }
+}
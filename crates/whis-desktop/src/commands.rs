@@ -2,11 +2,16 @@ use crate::settings::Settings;
 use crate::shortcuts::ShortcutBackendInfo;
 use crate::state::{AppState, RecordingState};
 use tauri::{AppHandle, State};
+use whis_core::{AudioRecorder, CaptureStats};
 
 #[derive(serde::Serialize)]
 pub struct StatusResponse {
     pub state: String,
     pub config_valid: bool,
+    /// Capture problems counted during the current recording
+    pub capture: Option<CaptureStats>,
+    /// The microphone is kept open between recordings (warm microphone)
+    pub microphone_on: bool,
 }
 
 #[derive(serde::Serialize)]
@@ -31,6 +36,17 @@ pub async fn get_status(state: State<'_, AppState>) -> Result<StatusResponse, St
         has_cached_config || settings.has_api_key()
     };
 
+    let (capture, microphone_on) = {
+        let recorder = state.recorder.lock().unwrap();
+        (
+            recorder
+                .as_ref()
+                .filter(|_| current_state == RecordingState::Recording)
+                .map(AudioRecorder::capture_stats),
+            recorder.as_ref().is_some_and(AudioRecorder::is_armed),
+        )
+    };
+
     Ok(StatusResponse {
         state: match current_state {
             RecordingState::Idle => "Idle".to_string(),
@@ -38,6 +54,8 @@ pub async fn get_status(state: State<'_, AppState>) -> Result<StatusResponse, St
             RecordingState::Transcribing => "Transcribing".to_string(),
         },
         config_valid,
+        capture,
+        microphone_on,
     })
 }
 
@@ -47,6 +65,12 @@ pub async fn toggle_recording(app: AppHandle) -> Result<(), String> {
     Ok(())
 }
 
+#[tauri::command]
+pub async fn cancel_recording(app: AppHandle) -> Result<(), String> {
+    crate::tray::cancel_recording(&app);
+    Ok(())
+}
+
 #[tauri::command]
 pub async fn get_settings(state: State<'_, AppState>) -> Result<Settings, String> {
     let mut settings = state.settings.lock().unwrap();
@@ -55,6 +79,16 @@ pub async fn get_settings(state: State<'_, AppState>) -> Result<Settings, String
     Ok(settings.clone())
 }
 
+/// List microphones for the input device picker
+#[tauri::command]
+pub async fn list_input_devices() -> Result<Vec<whis_core::InputDeviceInfo>, String> {
+    // Device enumeration can block on some hosts (e.g., ALSA probing)
+    tauri::async_runtime::spawn_blocking(whis_core::list_input_devices)
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 pub fn shortcut_backend() -> ShortcutBackendInfo {
     crate::shortcuts::backend_info()
@@ -98,14 +132,28 @@ pub async fn save_settings(
     settings: Settings,
 ) -> Result<SaveSettingsResponse, String> {
     // Check what changed
-    let (config_changed, shortcut_changed) = {
+    let (config_changed, shortcut_changed, microphone_changed) = {
         let current = state.settings.lock().unwrap();
         (
             current.provider != settings.provider
                 || current.openai_api_key != settings.openai_api_key
                 || current.mistral_api_key != settings.mistral_api_key
+                || current.custom_base_url != settings.custom_base_url
+                || current.custom_api_key != settings.custom_api_key
+                || current.local_model_path != settings.local_model_path
+                || current.audio_format != settings.audio_format
+                || current.audio_sample_rate != settings.audio_sample_rate
+                || current.audio_mono != settings.audio_mono
+                || current.vad_enabled != settings.vad_enabled
+                || current.vad_threshold_db != settings.vad_threshold_db
+                || current.chunk_threshold_mb != settings.chunk_threshold_mb
+                || current.chunk_duration_secs != settings.chunk_duration_secs
                 || current.language != settings.language,
             current.shortcut != settings.shortcut,
+            current.warm_microphone != settings.warm_microphone
+                || current.pre_roll_ms != settings.pre_roll_ms
+                || current.input_device != settings.input_device
+                || current.input_device_fallback != settings.input_device_fallback,
         )
     };
 
@@ -120,6 +168,11 @@ pub async fn save_settings(
         *state.transcription_config.lock().unwrap() = None;
     }
 
+    // Reopen (or close) the warm microphone with the new settings
+    if microphone_changed {
+        crate::tray::apply_warm_microphone(&app);
+    }
+
     // Only update shortcut if it actually changed
     let needs_restart = if shortcut_changed {
         crate::shortcuts::update_shortcut(&app, &settings.shortcut)
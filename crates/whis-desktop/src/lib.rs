@@ -32,6 +32,9 @@ pub fn run() {
             // Start IPC listener for --toggle CLI commands
             shortcuts::start_ipc_listener(app.handle().clone());
 
+            // Open the microphone now if it should stay warm between recordings
+            tray::apply_warm_microphone(app.handle());
+
             // If no tray, show main window immediately
             if !tray_available {
                 window::show_main_window(app)?;
@@ -54,7 +57,9 @@ pub fn run() {
             commands::portal_bind_error,
             commands::get_toggle_command,
             commands::toggle_recording,
+            commands::cancel_recording,
             commands::can_reopen_window,
+            commands::list_input_devices,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -24,6 +24,15 @@ fn main() {
         return;
     }
 
+    // Handle --cancel command: discard the recording in the running instance and exit
+    if args.contains(&"--cancel".to_string()) {
+        if let Err(e) = whis_desktop::shortcuts::send_cancel_command() {
+            eprintln!("Failed to cancel: {e}");
+            std::process::exit(1);
+        }
+        return;
+    }
+
     // Handle --install: create .desktop file for proper app_id on Wayland
     if args.contains(&"--install".to_string()) {
         install_desktop_file();
@@ -45,6 +54,7 @@ fn main() {
         println!();
         println!("OPTIONS:");
         println!("    -t, --toggle    Toggle recording in running instance");
+        println!("        --cancel    Discard the recording or transcription in running instance");
         println!("        --install   Install desktop file and icons for app menu");
         println!("        --uninstall Remove desktop file and icons");
         println!("    -h, --help      Print this help message");
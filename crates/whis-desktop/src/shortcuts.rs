@@ -582,6 +582,19 @@ fn print_manual_setup_instructions(compositor: &str, shortcut: &str) {
 
 /// Send toggle command to running instance via Unix socket
 pub fn send_toggle_command() -> Result<(), Box<dyn std::error::Error>> {
+    send_command("toggle")?;
+    println!("Toggle command sent");
+    Ok(())
+}
+
+/// Send cancel command to running instance via Unix socket
+pub fn send_cancel_command() -> Result<(), Box<dyn std::error::Error>> {
+    send_command("cancel")?;
+    println!("Cancel command sent");
+    Ok(())
+}
+
+fn send_command(command: &str) -> Result<(), Box<dyn std::error::Error>> {
     use std::io::Write;
     use std::os::unix::net::UnixStream;
 
@@ -589,8 +602,7 @@ pub fn send_toggle_command() -> Result<(), Box<dyn std::error::Error>> {
 
     match UnixStream::connect(&socket_path) {
         Ok(mut stream) => {
-            stream.write_all(b"toggle")?;
-            println!("Toggle command sent");
+            stream.write_all(command.as_bytes())?;
             Ok(())
         }
         Err(e) => {
@@ -628,13 +640,23 @@ pub fn start_ipc_listener(app_handle: AppHandle) {
                     let mut buf = [0u8; 64];
                     if let Ok(n) = stream.read(&mut buf) {
                         let cmd = String::from_utf8_lossy(&buf[..n]);
-                        if cmd.trim() == "toggle" {
-                            println!("IPC: toggle command received");
-                            let handle = app_handle.clone();
-                            // Dispatch to Tauri's async runtime - the IPC thread has no Tokio runtime
-                            tauri::async_runtime::spawn(async move {
-                                crate::tray::toggle_recording_public(handle);
-                            });
+                        match cmd.trim() {
+                            "toggle" => {
+                                println!("IPC: toggle command received");
+                                let handle = app_handle.clone();
+                                // Dispatch to Tauri's async runtime - the IPC thread has no Tokio runtime
+                                tauri::async_runtime::spawn(async move {
+                                    crate::tray::toggle_recording_public(handle);
+                                });
+                            }
+                            "cancel" => {
+                                println!("IPC: cancel command received");
+                                let handle = app_handle.clone();
+                                tauri::async_runtime::spawn(async move {
+                                    crate::tray::cancel_recording(&handle);
+                                });
+                            }
+                            _ => {}
                         }
                     }
                 }
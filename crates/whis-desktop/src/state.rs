@@ -1,6 +1,10 @@
 use std::sync::Mutex;
+use tauri::async_runtime::JoinHandle;
 use tauri::menu::MenuItem;
-use whis_core::{AudioRecorder, TranscriptionProvider};
+use whis_core::{
+    AudioRecorder, CancellationToken, FinalizeOptions, StreamingTranscription,
+    TranscriptionProvider,
+};
 use crate::settings::Settings;
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -10,18 +14,28 @@ pub enum RecordingState {
     Transcribing,
 }
 
-/// Cached transcription configuration (provider + API key + language)
+/// Cached transcription configuration (provider + API key + language + encoding)
 pub struct TranscriptionConfig {
     pub provider: TranscriptionProvider,
     pub api_key: String,
     pub language: Option<String>,
+    pub finalize: FinalizeOptions,
 }
 
 pub struct AppState {
     pub state: Mutex<RecordingState>,
     pub recorder: Mutex<Option<AudioRecorder>>,
+    /// Transcription of chunks streamed from the current recording
+    pub stream: Mutex<Option<StreamingTranscription>>,
+    /// Transcription of the stopped recording, until it finishes
+    pub transcription_task: Mutex<Option<JoinHandle<()>>>,
+    /// Cancels the requests of the current recording
+    pub cancel: Mutex<CancellationToken>,
+    /// Translate recordings to English instead of transcribing them (tray toggle)
+    pub translate: Mutex<bool>,
     pub transcription_config: Mutex<Option<TranscriptionConfig>>,
     pub record_menu_item: Mutex<Option<MenuItem<tauri::Wry>>>,
+    pub cancel_menu_item: Mutex<Option<MenuItem<tauri::Wry>>>,
     pub settings: Mutex<Settings>,
     /// The actual shortcut binding from the XDG Portal (Wayland only)
     pub portal_shortcut: Mutex<Option<String>>,
@@ -36,8 +50,13 @@ impl AppState {
         Self {
             state: Mutex::new(RecordingState::Idle),
             recorder: Mutex::new(None),
+            stream: Mutex::new(None),
+            transcription_task: Mutex::new(None),
+            cancel: Mutex::new(CancellationToken::new()),
+            translate: Mutex::new(false),
             transcription_config: Mutex::new(None),
             record_menu_item: Mutex::new(None),
+            cancel_menu_item: Mutex::new(None),
             settings: Mutex::new(settings),
             portal_shortcut: Mutex::new(None),
             portal_bind_error: Mutex::new(None),
@@ -1,12 +1,15 @@
 use crate::state::{AppState, RecordingState, TranscriptionConfig};
+use anyhow::{Context, Result};
+use serde::Serialize;
 use tauri::{
     image::Image,
-    menu::{Menu, MenuItem, PredefinedMenuItem},
+    menu::{CheckMenuItem, Menu, MenuItem, PredefinedMenuItem},
     tray::TrayIconBuilder,
     AppHandle, Emitter, Manager, WebviewWindowBuilder, WebviewUrl,
 };
 use whis_core::{
-    copy_to_clipboard, parallel_transcribe, transcribe_audio, AudioRecorder, RecordingOutput,
+    copy_to_clipboard, AudioRecorder, AutoStopOptions, CancellationToken, FinalizeOptions,
+    ProviderChain, StreamingTranscription, TranscriptionOptions, TranscriptionProvider, WhisError,
 };
 
 // Static icons for each state (pre-loaded at compile time)
@@ -20,6 +23,15 @@ pub const TRAY_ID: &str = "whis-tray";
 pub fn setup_tray(app: &tauri::App) -> Result<(), Box<dyn std::error::Error>> {
     // Create menu items
     let record = MenuItem::with_id(app, "record", "Start Recording", true, None::<&str>)?;
+    let cancel = MenuItem::with_id(app, "cancel", "Cancel", false, None::<&str>)?;
+    let translate = CheckMenuItem::with_id(
+        app,
+        "translate",
+        "Translate to English",
+        true,
+        false,
+        None::<&str>,
+    )?;
     let settings = MenuItem::with_id(app, "settings", "Settings", true, None::<&str>)?;
     let sep = PredefinedMenuItem::separator(app)?;
     let quit = MenuItem::with_id(app, "quit", "Quit Whis", true, None::<&str>)?;
@@ -27,9 +39,13 @@ pub fn setup_tray(app: &tauri::App) -> Result<(), Box<dyn std::error::Error>> {
     // Store the record menu item for later updates
     if let Some(state) = app.try_state::<AppState>() {
         *state.record_menu_item.lock().unwrap() = Some(record.clone());
+        *state.cancel_menu_item.lock().unwrap() = Some(cancel.clone());
     }
 
-    let menu = Menu::with_items(app, &[&record, &sep, &settings, &sep, &quit])?;
+    let menu = Menu::with_items(
+        app,
+        &[&record, &cancel, &translate, &sep, &settings, &sep, &quit],
+    )?;
 
     // Use image crate for consistent rendering (same as set_tray_icon)
     let idle_bytes = include_bytes!("../icons/icon-idle.png");
@@ -55,6 +71,15 @@ pub fn setup_tray(app: &tauri::App) -> Result<(), Box<dyn std::error::Error>> {
                     toggle_recording(app_clone);
                 });
             }
+            "cancel" => {
+                cancel_recording(app);
+            }
+            "translate" => {
+                // The menu item checks itself; applies from the next recording
+                let state = app.state::<AppState>();
+                let mut translate = state.translate.lock().unwrap();
+                *translate = !*translate;
+            }
             "settings" => {
                 open_settings_window(app.clone());
             }
@@ -120,17 +145,20 @@ fn toggle_recording(app: AppHandle) {
         RecordingState::Idle => {
             // Start recording
             if let Err(e) = start_recording_sync(&app, &state) {
-                eprintln!("Failed to start recording: {e}");
+                eprintln!("Failed to start recording: {e:#}");
+                report_error(&app, &e);
             }
         }
         RecordingState::Recording => {
             // Stop recording and transcribe
             let app_clone = app.clone();
-            tauri::async_runtime::spawn(async move {
+            let task = tauri::async_runtime::spawn(async move {
                 if let Err(e) = stop_and_transcribe(&app_clone).await {
-                    eprintln!("Failed to transcribe: {e}");
+                    eprintln!("Failed to transcribe: {e:#}");
+                    report_error(&app_clone, &e);
                 }
             });
+            *state.transcription_task.lock().unwrap() = Some(task);
         }
         RecordingState::Transcribing => {
             // Already transcribing, ignore
@@ -138,7 +166,29 @@ fn toggle_recording(app: AppHandle) {
     }
 }
 
-fn start_recording_sync(app: &AppHandle, state: &AppState) -> Result<(), String> {
+/// Failure of a recording or its transcription, as sent to the UI
+#[derive(Clone, Serialize)]
+struct RecordingError {
+    message: String,
+    /// See [`WhisError::kind`]; None for errors whis-core doesn't classify
+    kind: Option<&'static str>,
+    hint: Option<&'static str>,
+    retryable: bool,
+}
+
+/// Tell the UI why the last recording failed
+fn report_error(app: &AppHandle, error: &anyhow::Error) {
+    let known = WhisError::find(error);
+    let payload = RecordingError {
+        message: error.to_string(),
+        kind: known.map(WhisError::kind),
+        hint: known.and_then(WhisError::hint),
+        retryable: known.is_some_and(WhisError::is_retryable),
+    };
+    let _ = app.emit("recording-error", payload);
+}
+
+fn start_recording_sync(app: &AppHandle, state: &AppState) -> Result<()> {
     // Load transcription config if not already loaded
     {
         let mut config_guard = state.transcription_config.lock().unwrap();
@@ -147,23 +197,97 @@ fn start_recording_sync(app: &AppHandle, state: &AppState) -> Result<(), String>
             let provider = settings.provider.clone();
 
             // Get API key using the helper method
-            let api_key = settings
-                .get_api_key()
-                .ok_or_else(|| format!("No {} API key configured. Add it in Settings.", provider))?;
+            let api_key = settings.get_api_key().ok_or_else(|| match provider {
+                TranscriptionProvider::Local => anyhow::anyhow!(
+                    "No local model configured. Set one with: whis config --local-model <PATH>"
+                ),
+                _ => anyhow::anyhow!("No {} API key configured. Add it in Settings.", provider),
+            })?;
 
             let language = settings.language.clone();
+            let finalize = FinalizeOptions::from_settings(&settings);
 
             *config_guard = Some(TranscriptionConfig {
                 provider,
                 api_key,
                 language,
+                finalize,
             });
         }
     }
 
-    // Start recording
-    let mut recorder = AudioRecorder::new().map_err(|e| e.to_string())?;
-    recorder.start_recording().map_err(|e| e.to_string())?;
+    let settings = state.settings.lock().unwrap().clone();
+    let cancel = CancellationToken::new();
+    let (chain, options, finalize) = {
+        let config = state.transcription_config.lock().unwrap();
+        let config = config.as_ref().context("Transcription config not loaded")?;
+        let chain = ProviderChain::from_settings(&settings, &config.provider)?;
+        let options = TranscriptionOptions {
+            translate: *state.translate.lock().unwrap(),
+            cancel: cancel.clone(),
+            ..TranscriptionOptions::from_settings(
+                &settings,
+                &config.api_key,
+                config.language.as_deref(),
+            )
+        };
+        chain.check_options(&options)?;
+        (chain, options, config.finalize)
+    };
+
+    // Start recording, from the warm microphone if it is open
+    let armed = state.recorder.lock().unwrap().take();
+    let mut recorder = match armed {
+        Some(mut recorder) => {
+            recorder.set_auto_stop(AutoStopOptions::from_settings(&settings));
+            recorder
+        }
+        None => AudioRecorder::from_settings(&settings)?,
+    };
+
+    // Chunks are transcribed while the recording continues
+    let stream = StreamingTranscription::start(
+        &mut recorder,
+        finalize,
+        chain,
+        options,
+        tauri::async_runtime::handle().inner(),
+    )?;
+    if let Some(warning) = recorder.fallback_warning() {
+        eprintln!("Warning: {warning}");
+        let _ = app.emit("recording-warning", warning);
+    }
+
+    *state.cancel.lock().unwrap() = cancel;
+    *state.stream.lock().unwrap() = Some(stream);
+
+    // Forward the input level to the UI until the recording stops
+    let mut levels = recorder.subscribe_levels();
+    let level_app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        while levels.changed().await.is_ok() {
+            let state = level_app.state::<AppState>();
+            if *state.state.lock().unwrap() != RecordingState::Recording {
+                break;
+            }
+            let level = *levels.borrow_and_update();
+            let _ = level_app.emit("input-level", level);
+        }
+    });
+
+    // Reaching a limit stops the recording like the tray item would
+    let mut auto_stop = recorder.subscribe_auto_stop();
+    let stop_app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let Ok(reason) = auto_stop.wait_for(Option::is_some).await.map(|reason| *reason) else {
+            return;
+        };
+        if let Some(reason) = reason {
+            println!("Recording stopped: {reason}");
+            let _ = stop_app.emit("recording-auto-stopped", reason.to_string());
+            toggle_recording(stop_app);
+        }
+    });
 
     *state.recorder.lock().unwrap() = Some(recorder);
     *state.state.lock().unwrap() = RecordingState::Recording;
@@ -175,7 +299,7 @@ fn start_recording_sync(app: &AppHandle, state: &AppState) -> Result<(), String>
     Ok(())
 }
 
-async fn stop_and_transcribe(app: &AppHandle) -> Result<(), String> {
+async fn stop_and_transcribe(app: &AppHandle) -> Result<()> {
     let state = app.state::<AppState>();
 
     // Update state to transcribing
@@ -189,62 +313,103 @@ async fn stop_and_transcribe(app: &AppHandle) -> Result<(), String> {
     let result = do_transcription(app, &state).await;
 
     // Always reset state, regardless of success or failure
+    return_to_idle(app, &state);
+
+    result
+}
+
+/// Reset the state after a recording has been transcribed or cancelled
+fn return_to_idle(app: &AppHandle, state: &AppState) {
     {
         *state.state.lock().unwrap() = RecordingState::Idle;
     }
     update_tray(app, RecordingState::Idle);
 
-    result
+    // Settings may have changed while recording
+    let armed = state.recorder.lock().unwrap().is_some();
+    if armed != state.settings.lock().unwrap().warm_microphone {
+        apply_warm_microphone(app);
+    }
+}
+
+/// Discard the current recording, or abort its transcription
+pub fn cancel_recording(app: &AppHandle) {
+    let state = app.state::<AppState>();
+    let current_state = *state.state.lock().unwrap();
+    if current_state == RecordingState::Idle {
+        return;
+    }
+
+    // The transcription may not have taken the recording yet, so stop both
+    if let Some(task) = state.transcription_task.lock().unwrap().take() {
+        task.abort();
+    }
+    let stream = state.stream.lock().unwrap().take();
+    if let Some(stream) = stream {
+        stream.cancel(&state.recorder);
+    }
+    // Also stops the chunk requests of a stopped recording still in flight
+    state.cancel.lock().unwrap().cancel();
+
+    return_to_idle(app, &state);
+    println!("Cancelled");
+    let _ = app.emit("recording-cancelled", ());
+}
+
+/// Open or close the microphone between recordings to match the warm microphone setting
+///
+/// Reopens it if already open, so device changes apply. Does nothing while
+/// a recording is running; it is applied once that recording is transcribed.
+pub fn apply_warm_microphone(app: &AppHandle) {
+    let state = app.state::<AppState>();
+    if *state.state.lock().unwrap() != RecordingState::Idle {
+        return;
+    }
+
+    let settings = state.settings.lock().unwrap().clone();
+    // Close the current microphone first; some devices can't be opened twice
+    *state.recorder.lock().unwrap() = None;
+    if let Some(pre_roll) = settings.pre_roll() {
+        let armed = AudioRecorder::from_settings(&settings).and_then(|mut recorder| {
+            recorder.arm(pre_roll)?;
+            Ok(recorder)
+        });
+        match armed {
+            Ok(recorder) => {
+                println!("Microphone on: keeping {}ms of pre-roll", settings.pre_roll_ms);
+                *state.recorder.lock().unwrap() = Some(recorder);
+            }
+            Err(e) => {
+                eprintln!("Failed to keep the microphone warm: {e}");
+                let _ = app.emit("recording-warning", format!("Microphone not kept warm: {e}"));
+            }
+        }
+    }
+    update_tray(app, RecordingState::Idle);
 }
 
 /// Inner transcription logic - extracted so we can guarantee state cleanup
-async fn do_transcription(app: &AppHandle, state: &AppState) -> Result<(), String> {
-    // Get recorder and config
-    let mut recorder = state
-        .recorder
+async fn do_transcription(app: &AppHandle, state: &AppState) -> Result<()> {
+    let stream = state
+        .stream
         .lock()
         .unwrap()
         .take()
-        .ok_or("No active recording")?;
-
-    let (provider, api_key, language) = {
-        let config = state.transcription_config.lock().unwrap();
-        let config_ref = config.as_ref().ok_or("Transcription config not loaded")?;
-        (
-            config_ref.provider.clone(),
-            config_ref.api_key.clone(),
-            config_ref.language.clone(),
-        )
-    };
-
-    // Finalize recording (synchronous file encoding)
-    let audio_result = recorder.finalize_recording().map_err(|e| e.to_string())?;
-
-    // Transcribe
-    let transcription = match audio_result {
-        // transcribe_audio is synchronous (blocking HTTP), so we should wrap it in spawn_blocking
-        // to avoid blocking the async runtime
-        RecordingOutput::Single(data) => {
-            let provider = provider.clone();
-            let api_key = api_key.clone();
-            let language = language.clone();
-            tauri::async_runtime::spawn_blocking(move || {
-                transcribe_audio(&provider, &api_key, language.as_deref(), data)
-            })
-            .await
-            .map_err(|e| e.to_string())?
-            .map_err(|e| e.to_string())?
-        }
-        RecordingOutput::Chunked(chunks) => {
-            // parallel_transcribe is async, so we can await it directly
-            parallel_transcribe(&provider, &api_key, language.as_deref(), chunks, None)
-                .await
-                .map_err(|e| e.to_string())?
-        }
+        .context("No active recording")?;
+    let transcription = stream
+        .stop(&state.recorder)?
+        .await?
+        .map(|transcript| transcript.text);
+
+    // Nothing was said; leave the clipboard alone
+    let Some(transcription) = transcription else {
+        println!("No speech detected");
+        let _ = app.emit("no-speech-detected", ());
+        return Ok(());
     };
 
     // Copy to clipboard
-    copy_to_clipboard(&transcription).map_err(|e| e.to_string())?;
+    copy_to_clipboard(&transcription)?;
 
     println!("Done: {}", &transcription[..transcription.len().min(50)]);
 
@@ -266,10 +431,20 @@ fn update_tray(app: &AppHandle, new_state: RecordingState) {
         let _ = menu_item.set_text(text);
         let _ = menu_item.set_enabled(new_state != RecordingState::Transcribing);
     }
+    if let Some(ref menu_item) = *app_state.cancel_menu_item.lock().unwrap() {
+        let _ = menu_item.set_enabled(new_state != RecordingState::Idle);
+    }
 
     if let Some(tray) = app.tray_by_id(TRAY_ID) {
         // Update tooltip
+        let microphone_on = app_state
+            .recorder
+            .lock()
+            .unwrap()
+            .as_ref()
+            .is_some_and(AudioRecorder::is_armed);
         let tooltip = match new_state {
+            RecordingState::Idle if microphone_on => "Whis - Microphone on - Click to record",
             RecordingState::Idle => "Whis - Click to record",
             RecordingState::Recording => "Whis - Recording... Click to stop",
             RecordingState::Transcribing => "Whis - Transcribing...",